@@ -116,6 +116,7 @@ decl_derive!([Visit, attributes(has_interner)] => derive_visit);
 decl_derive!([SuperVisit, attributes(has_interner)] => derive_super_visit);
 decl_derive!([Fold, attributes(has_interner)] => derive_fold);
 decl_derive!([Zip, attributes(has_interner)] => derive_zip);
+decl_derive!([StructuralHash, attributes(has_interner)] => derive_structural_hash);
 
 fn derive_has_interner(mut s: synstructure::Structure) -> TokenStream {
     s.underscore_const(true);
@@ -167,17 +168,49 @@ fn derive_any_visit(
     s.bound_impl(
         quote!(::logic_ir::visit:: #trait_name <#interner>),
         quote! {
-            fn #method_name <'i, B>(
+            fn #method_name <V: ::logic_ir::visit::Visitor<#interner>>(
                 &self,
-                visitor: &mut dyn ::logic_ir::visit::Visitor < 'i, #interner, BreakTy = B >,
-            ) -> ::logic_ir::visit::ControlFlow<B>
-            where
-                #interner: 'i
+                visitor: &mut V,
+            ) -> ::logic_ir::visit::ControlFlow<V::BreakTy>
             {
                 match *self {
                     #body
                 }
-                ::logic_ir::visit::ControlFlow::CONTINUE
+                ::logic_ir::visit::ControlFlow::Continue(())
+            }
+        },
+    )
+}
+
+/// Derives StructuralHash, which hashes the variant followed by each of the fields,
+/// for the same structs and enums as Visit
+fn derive_structural_hash(mut s: synstructure::Structure) -> TokenStream {
+    s.underscore_const(true);
+    let input = s.ast();
+    let (interner, kind) = find_interner(&mut s);
+
+    let body = s.each(|bi| {
+        quote! {
+            ::logic_ir::structural_hash::StructuralHash::structural_hash(#bi, interner, state);
+        }
+    });
+
+    if kind == DeriveKind::FromHasInterner {
+        let param = get_generic_param_name(input).unwrap();
+        s.add_where_predicate(
+            parse_quote! { #param: ::logic_ir::structural_hash::StructuralHash<#interner> },
+        );
+    }
+
+    s.add_bounds(synstructure::AddBounds::None);
+    s.bound_impl(
+        quote!(::logic_ir::structural_hash::StructuralHash<#interner>),
+        quote! {
+            fn structural_hash<H: ::std::hash::Hasher>(&self, interner: #interner, state: &mut H) {
+                ::std::hash::Hash::hash(&::std::mem::discriminant(self), state);
+                match *self {
+                    #body
+                }
             }
         },
     )
@@ -257,7 +290,7 @@ fn derive_fold(mut s: synstructure::Structure) -> TokenStream {
         vi.construct(|_, index| {
             let bind = &bindings[index];
             quote! {
-                ::logic_ir::fold::Fold::fold_with(#bind, folder)?
+                ::logic_ir::fold::Fold::fold_with(#bind, folder, outer_binder)?
             }
         })
     });
@@ -285,7 +318,11 @@ fn derive_fold(mut s: synstructure::Structure) -> TokenStream {
         quote! {
             type Folded = #folded;
 
-            fn fold_with<F: Folder<I>>(self, folder: &mut F) -> ::logic_ir::LogicResult<Self::Folded>
+            fn fold_with<F: Folder<I>>(
+                self,
+                folder: &mut F,
+                outer_binder: ::logic_ir::DebruijnIdx,
+            ) -> ::logic_ir::LogicResult<Self::Folded>
             {
                 Ok(match self { #body })
             }
@@ -1,7 +1,7 @@
 #[macro_use]
 extern crate logic_ir;
 
-use logic_engine::{GoalExt, RecursiveSolver, Solution};
+use logic_engine::{GoalExt, ImplRule, RecursiveSolver, Solution, SpecializationGraph};
 use logic_ir::*;
 use logic_parse::{ast, ParseResult};
 use std::sync::Arc;
@@ -21,9 +21,37 @@ pub trait LoweringDatabase: salsa::Database {
     fn src(&self) -> Arc<String>;
     fn interner(&self) -> LogicInterner;
     fn ast(&self) -> ParseResult<ast::Program>;
+    /// the program as it is lowered, with every clause as it is written
+    fn raw_ir(&self) -> ParseResult<Program<logic_ir::LogicInterner>>;
+    /// the program with only the first of alpha-equivalent clauses
     fn ir(&self) -> ParseResult<Program<logic_ir::LogicInterner>>;
     fn env(&self) -> logic_ir::LogicResult<Environment<LogicInterner>>;
+    /// which impls specialize which, so that goals are solved with the most specific impl
+    fn specialization_graph(&self) -> logic_ir::LogicResult<SpecializationGraph<LogicInterner>>;
     fn query(&self, unparsed_goal: Arc<String>) -> logic_ir::LogicResult<Solution<LogicInterner>>;
+    /// the impls whose well formedness can't be proven
+    fn ill_formed_impls(&self) -> logic_ir::LogicResult<Vec<ImplDatum<LogicInterner>>>;
+    /// the pairs of impls and `Implemented` clauses that overlap, in the order they are declared
+    fn overlapping_impls(&self) -> logic_ir::LogicResult<Vec<Overlap>>;
+    /// the names of the traits whose impls may overlap
+    fn marker_traits(&self) -> Vec<Ident>;
+    /// the impls violating the orphan rule, in the order they are declared
+    fn orphan_impls(&self) -> Vec<Orphan>;
+}
+
+/// two impls or clauses that may both prove a trait is implemented for the same types, as
+/// clauses along with the spans of their declarations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overlap {
+    pub clauses: [Clause<LogicInterner>; 2],
+    pub spans: [ast::Span; 2],
+}
+
+/// an impl for neither a local trait nor a local type, along with the span of its declaration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Orphan {
+    pub impl_datum: ImplDatum<LogicInterner>,
+    pub span: ast::Span,
 }
 
 #[salsa::database(Lowering, Logic)]
@@ -35,6 +63,13 @@ pub struct Database {
 impl salsa::Database for Database {
 }
 
+// everything the queries return is `Send + Sync`, so snapshots can be queried from other threads
+impl salsa::ParallelDatabase for Database {
+    fn snapshot(&self) -> salsa::Snapshot<Self> {
+        salsa::Snapshot::new(Database { storage: self.storage.snapshot() })
+    }
+}
+
 impl Database {
     pub fn new(src: &str) -> Self {
         let mut db = Self::default();
@@ -48,32 +83,129 @@ fn ast(db: &dyn LoweringDatabase) -> ParseResult<ast::Program> {
     logic_parse::parse_program(&src)
 }
 
-fn ir(db: &dyn LoweringDatabase) -> ParseResult<Program<LogicInterner>> {
+fn raw_ir(db: &dyn LoweringDatabase) -> ParseResult<Program<LogicInterner>> {
     let ast = db.ast()?;
     Ok(logic_ir::lower_ast(&ast).expect("todo error handling"))
 }
 
+fn ir(db: &dyn LoweringDatabase) -> ParseResult<Program<LogicInterner>> {
+    let mut program = db.raw_ir()?;
+    program.dedup_clauses();
+    Ok(program)
+}
+
 fn interner(_db: &dyn LoweringDatabase) -> LogicInterner {
     logic_ir::LogicInterner
 }
 
 fn env(db: &dyn LoweringDatabase) -> logic_ir::LogicResult<logic_ir::Environment<LogicInterner>> {
     let ir = db.ir().expect("todo proper error handling");
-    let env = logic_ir::Environment::new(ir.clauses);
+    let env = logic_ir::Environment::new(ir.program_clauses());
     Ok(env)
 }
 
+fn specialization_graph(
+    db: &dyn LoweringDatabase,
+) -> logic_ir::LogicResult<SpecializationGraph<LogicInterner>> {
+    let ir = db.ir().expect("todo proper error handling");
+    let solver = RecursiveSolver::new(LogicInterner, db.env()?);
+    Ok(solver.specialization_graph(&ir.impls))
+}
+
 fn query(
     db: &dyn LoweringDatabase,
     unparsed_goal: Arc<String>,
 ) -> logic_ir::LogicResult<Solution<LogicInterner>> {
     let env = db.env()?;
-    let solver = RecursiveSolver::new(LogicInterner, env);
+    let solver = RecursiveSolver::new(LogicInterner, env)
+        .with_specialization_graph(db.specialization_graph()?)
+        .with_marker_traits(db.marker_traits());
+    let ast = db.ast().expect("todo proper error handling");
     let parsed_goal = logic_parse::parse_goal(&unparsed_goal).expect("error handling");
-    let goal = logic_ir::lower_goal(&parsed_goal).expect("todo error handling");
+    let goal = logic_ir::lower_goal_in(&ast, &parsed_goal).expect("todo error handling");
     let peeled_goal = goal.peel(LogicInterner);
     solver.solve(&peeled_goal)
 }
 
+fn ill_formed_impls(
+    db: &dyn LoweringDatabase,
+) -> logic_ir::LogicResult<Vec<ImplDatum<LogicInterner>>> {
+    let ir = db.ir().expect("todo proper error handling");
+    let solver = RecursiveSolver::new(LogicInterner, db.env()?);
+    let mut ill_formed = vec![];
+    for impl_datum in &ir.impls {
+        let goal = impl_datum.well_formed_goal(LogicInterner).peel(LogicInterner);
+        // an ambiguous answer doesn't prove anything either
+        if !solver.solve(&goal).is_ok_and(|solution| solution.is_unique()) {
+            ill_formed.push(impl_datum.clone());
+        }
+    }
+    Ok(ill_formed)
+}
+
+fn overlapping_impls(db: &dyn LoweringDatabase) -> logic_ir::LogicResult<Vec<Overlap>> {
+    let ast = db.ast().expect("todo proper error handling");
+    let ir = db.ir().expect("todo proper error handling");
+    // the clauses as they are written, `ir` only keeps the first of alpha-equivalent ones
+    let clauses = db.raw_ir().expect("todo proper error handling").clauses;
+    let (impl_spans, clause_spans) = (impl_spans(&ast), clause_spans(&ast));
+    let rule = |rule| match rule {
+        ImplRule::Impl(i) => {
+            let implication = ir.impls[i].implemented_implication(LogicInterner);
+            (Clause::intern(LogicInterner, ClauseData::Implies(implication)), impl_spans[i].clone())
+        }
+        ImplRule::Clause(i) => (clauses.at(i).clone(), clause_spans[i].clone()),
+    };
+    let solver =
+        RecursiveSolver::new(LogicInterner, db.env()?).with_marker_traits(db.marker_traits());
+    let mut overlapping = solver
+        .overlapping_impls(&ir.impls, clauses.as_slice())
+        .into_iter()
+        .map(|(a, b)| {
+            let (mut a, mut b) = (rule(a), rule(b));
+            if b.1.lo < a.1.lo {
+                std::mem::swap(&mut a, &mut b);
+            }
+            Overlap { clauses: [a.0, b.0], spans: [a.1, b.1] }
+        })
+        .collect::<Vec<_>>();
+    overlapping.sort_by_key(|overlap| overlap.spans.clone().map(|span| span.lo));
+    Ok(overlapping)
+}
+
+fn marker_traits(db: &dyn LoweringDatabase) -> Vec<Ident> {
+    let ir = db.ir().expect("todo proper error handling");
+    let marker_traits = ir.traits.iter().filter(|trait_datum| trait_datum.marker);
+    marker_traits.map(|trait_datum| trait_datum.name.clone()).collect()
+}
+
+fn orphan_impls(db: &dyn LoweringDatabase) -> Vec<Orphan> {
+    let ast = db.ast().expect("todo proper error handling");
+    let ir = db.ir().expect("todo proper error handling");
+    let spans = impl_spans(&ast);
+    ir.orphan_impls()
+        .into_iter()
+        .map(|i| Orphan { impl_datum: ir.impls[i].clone(), span: spans[i].clone() })
+        .collect()
+}
+
+/// the spans of the impl declarations, which are lowered in the order they are declared
+fn impl_spans(ast: &ast::Program) -> Vec<ast::Span> {
+    ast.items
+        .iter()
+        .filter(|item| matches!(item.kind, ast::ItemKind::Impl(..)))
+        .map(|item| item.span.clone())
+        .collect()
+}
+
+/// the spans of the clauses, which are lowered in the order they are written
+fn clause_spans(ast: &ast::Program) -> Vec<ast::Span> {
+    ast.items
+        .iter()
+        .filter(|item| matches!(item.kind, ast::ItemKind::Clause(..)))
+        .map(|item| item.span.clone())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,174 @@
+use crate::{Database, LoweringDatabase};
+
+/// the source of each pair of overlapping impls
+fn overlapping_impls(program: &str) -> Vec<[&str; 2]> {
+    let overlapping = Database::new(program).overlapping_impls().unwrap();
+    overlapping
+        .iter()
+        .map(|overlap| overlap.spans.clone().map(|span| &program[span.lo..span.hi]))
+        .collect()
+}
+
+#[test]
+fn test_coherence_detects_overlapping_headers() {
+    let program = r"
+        impl<T> Clone for Vec<T> {}
+        impl Clone for Vec<i32> {}
+        impl Clone for Vec<u32> {}
+        impl Clone for Box<i32> {}
+        impl<T> Eq<T> for Box<T> {}
+        impl<T> Eq<i32> for Box<T> {}
+        impl Eq<u32> for Box<i32> {}
+    ";
+    assert_eq!(
+        overlapping_impls(program),
+        [
+            ["impl<T> Clone for Vec<T> {}", "impl Clone for Vec<i32> {}"],
+            ["impl<T> Clone for Vec<T> {}", "impl Clone for Vec<u32> {}"],
+            ["impl<T> Eq<T> for Box<T> {}", "impl<T> Eq<i32> for Box<T> {}"],
+        ]
+    );
+}
+
+#[test]
+fn test_coherence_checks_implemented_clauses() {
+    let program = r"
+        impl Clone for Vec<i32> {}
+        for<T> { Vec<T>: Foo :- true }.
+        Vec<i32>: Foo.
+        for<T> { Vec<T>: Clone :- T: Copy }.
+        i32: Copy.
+        Vec<u32>: Foo.
+        Vec<u32>: Foo.
+        WellFormed(Vec<u32>).
+        WellFormed(Vec<u32>).
+    ";
+    assert_eq!(
+        overlapping_impls(program),
+        [
+            ["impl Clone for Vec<i32> {}", "for<T> { Vec<T>: Clone :- T: Copy }."],
+            ["for<T> { Vec<T>: Foo :- true }.", "Vec<i32>: Foo."],
+            ["for<T> { Vec<T>: Foo :- true }.", "Vec<u32>: Foo."],
+        ]
+    );
+}
+
+#[test]
+fn test_coherence_considers_where_clauses() {
+    let program = r"
+        impl Copy for i32 {}
+        impl Display for u32 {}
+        impl<T> Clone for Vec<T> where T: Copy {}
+        impl Clone for Vec<u32> {}
+        impl<T> Debug for T where T: Display {}
+        impl<T> Debug for Vec<T> where T: Copy {}
+        impl Debug for u32 {}
+    ";
+    assert_eq!(
+        overlapping_impls(program),
+        [["impl<T> Debug for T where T: Display {}", "impl Debug for u32 {}"]]
+    );
+}
+
+#[test]
+fn test_coherence_allows_specializing_default_impls() {
+    let program = r"
+        impl Copy for u32 {}
+        default impl<T> Clone for Vec<T> {}
+        impl<T> Clone for Vec<T> where T: Copy {}
+        impl Clone for Vec<u32> {}
+        default impl<T> Debug for T {}
+        default impl<T> Debug for T {}
+        impl<T> Display for Box<T> {}
+        impl Display for Box<i32> {}
+    ";
+    assert_eq!(
+        overlapping_impls(program),
+        [
+            // only the `default` impl may be specialized
+            ["impl<T> Clone for Vec<T> where T: Copy {}", "impl Clone for Vec<u32> {}"],
+            // neither impl is more specific than the other
+            ["default impl<T> Debug for T {}", "default impl<T> Debug for T {}"],
+            ["impl<T> Display for Box<T> {}", "impl Display for Box<i32> {}"],
+        ]
+    );
+}
+
+#[test]
+fn test_coherence_allows_overlapping_marker_impls() {
+    let program = r"
+        #[marker] trait Eq {}
+        trait Ord {}
+        impl<T> Eq for Vec<T> {}
+        impl Eq for Vec<i32> {}
+        impl<T> Ord for Vec<T> {}
+        impl Ord for Vec<i32> {}
+    ";
+    assert_eq!(
+        overlapping_impls(program),
+        [["impl<T> Ord for Vec<T> {}", "impl Ord for Vec<i32> {}"]]
+    );
+}
+
+/// the source of each impl violating the orphan rule
+fn orphan_impls(program: &str) -> Vec<&str> {
+    let orphans = Database::new(program).orphan_impls();
+    orphans.iter().map(|orphan| &program[orphan.span.lo..orphan.span.hi]).collect()
+}
+
+#[test]
+fn test_orphan_impls_need_a_local_trait_or_type() {
+    let program = r"
+        #[crate(std)] struct Vec<T> {}
+        #[crate(std)] trait Clone {}
+        #[crate(std)] trait From<T> {}
+        struct Local {}
+        trait Show {}
+        impl Show for i32 {}
+        impl Clone for Local {}
+        impl Clone for Vec<Local> {}
+        impl Clone for i32 {}
+        impl<T> From<Local> for Vec<T> {}
+        impl<T> From<T> for Local {}
+        impl<T> From<Local> for T {}
+        #[crate(std)] impl<T> Clone for Vec<T> {}
+        #[crate(std)] impl Clone for Local {}
+        #[crate(std)] impl Show for i32 {}
+    ";
+    assert_eq!(
+        orphan_impls(program),
+        [
+            "impl Clone for Vec<Local> {}",
+            "impl Clone for i32 {}",
+            "impl<T> From<Local> for T {}",
+            "#[crate(std)] impl Show for i32 {}",
+        ]
+    );
+}
+
+#[test]
+fn test_orphan_impls_see_through_fundamental_types() {
+    let program = r"
+        #[fundamental] #[crate(std)] struct Box<T> {}
+        #[crate(std)] struct Vec<T> {}
+        #[crate(std)] trait Clone {}
+        #[crate(std)] trait From<T> {}
+        struct Local {}
+        trait Show {}
+        impl Clone for Box<Local> {}
+        impl<'a> Clone for &'a Local {}
+        impl Clone for Box<Vec<Local>> {}
+        impl Clone for dyn Show {}
+        impl<T> From<Local> for Box<T> {}
+        impl<T> From<Local> for Vec<T> {}
+        impl<'a, T> From<Box<Local>> for &'a T {}
+    ";
+    assert_eq!(
+        orphan_impls(program),
+        [
+            "impl Clone for Box<Vec<Local>> {}",
+            "impl<T> From<Local> for Box<T> {}",
+            "impl<'a, T> From<Box<Local>> for &'a T {}",
+        ]
+    );
+}
@@ -59,3 +59,481 @@ fn test_lower_nested_quantified_goal() {
     );
     assert_eq!(expected, lower_goal!(goal));
 }
+
+#[test]
+fn test_lower_eq_goal() {
+    let expected = goal!(exists<1> domain eq ty!(var 0:0), ty!(i32));
+    assert_eq!(lower_goal!("exists<T> { T = i32 }"), expected);
+}
+
+#[test]
+fn test_lower_rejects_eq_consequent() {
+    for program in ["i32 = u32.", "for<T> { T = i32 :- T: Copy }."] {
+        let ast = logic_parse::parse_program(program).unwrap();
+        assert!(matches!(lower_ast(&ast), Err(LoweringError::EqInConsequent(..))));
+    }
+}
+
+#[test]
+fn test_lower_rejects_outlives_consequent() {
+    let ast = logic_parse::parse_program("for<'a> { i32: 'a :- true }.").unwrap();
+    assert!(matches!(lower_ast(&ast), Err(LoweringError::OutlivesInConsequent(..))));
+    // a hypothesis can assume a region constraint
+    let ast = logic_parse::parse_goal("forall<'a, 'b> { if ('a: 'b) { true } }").unwrap();
+    assert!(logic_ir::lower_goal(&ast).is_ok());
+}
+
+#[test]
+fn test_lower_lifetimes() {
+    let goal = lower_goal!("forall<'a, T> { exists<'b> { Ref<'b, T>: Trait<'static>, T: 'a } }");
+    let lifetime = |debruijn, index| BoundVar::new(DebruijnIdx::new(debruijn), index);
+    let ref_ty = TyKind::Structure(
+        Ident::unspanned("Ref"),
+        subst![lifetime(0, 0).to_lifetime(LogicInterner), ty!(var 1:1)],
+    )
+    .intern(LogicInterner);
+    let trait_ref = TraitRef {
+        trait_name: Ident::unspanned("Trait"),
+        args: subst![LifetimeData::Static.intern(LogicInterner)],
+    };
+    let outlives =
+        TyOutlives { ty: ty!(var 1:1), lifetime: lifetime(1, 0).to_lifetime(LogicInterner) };
+    let goal_data = |data| Goal::intern(LogicInterner, data);
+    let expected = goal_data(GoalData::And(
+        goal!(domain impl ref_ty, trait_ref),
+        goal_data(GoalData::DomainGoal(DomainGoal::Holds(Constraint::TyOutlives(outlives)))),
+    ));
+    let variables = |kinds: &[VariableKind]| {
+        Variables::intern(LogicInterner, kinds.iter().map(|&kind| Variable::new(kind)))
+    };
+    let expected = goal_data(GoalData::Quantified(
+        Quantifier::Exists,
+        Binders::new(variables(&[VariableKind::Lifetime]), expected),
+    ));
+    let expected = goal_data(GoalData::Quantified(
+        Quantifier::ForAll,
+        Binders::new(variables(&[VariableKind::Lifetime, VariableKind::Ty]), expected),
+    ));
+    assert_eq!(goal, expected);
+
+    let ast = logic_parse::parse_program("Ref<'a>: Trait.").unwrap();
+    assert!(matches!(lower_ast(&ast), Err(LoweringError::UnboundLifetime(..))));
+}
+
+#[test]
+fn test_lower_consts() {
+    // a const parameter is parsed as a type and resolved to a const by its binder
+    let goal = lower_goal!("forall<T, const N> { Array<T, N>: Trait<3, true> }");
+    let array = TyKind::Structure(
+        Ident::unspanned("Array"),
+        subst![ty!(var 0:0), BoundVar::new(DebruijnIdx::ZERO, 1).to_const(LogicInterner)],
+    )
+    .intern(LogicInterner);
+    let constant = |value| ConstData::Value(value).intern(LogicInterner);
+    let trait_ref = TraitRef {
+        trait_name: Ident::unspanned("Trait"),
+        args: subst![constant(ConstValue::Int(3)), constant(ConstValue::Bool(true))],
+    };
+    let variables = [VariableKind::Ty, VariableKind::Const(None)].map(Variable::new);
+    let expected = GoalData::Quantified(
+        Quantifier::ForAll,
+        Binders::new(
+            Variables::intern(LogicInterner, variables),
+            goal!(domain impl array, trait_ref),
+        ),
+    );
+    assert_eq!(goal, Goal::intern(LogicInterner, expected));
+
+    let ast = logic_parse::parse_goal("forall<const N> { N: Trait }").unwrap();
+    assert!(matches!(logic_ir::lower_goal(&ast), Err(LoweringError::ConstAsType(..))));
+}
+
+#[test]
+fn test_lower_builtin_tys() {
+    let goal = lower_goal!("forall<'a, T, const N> { &'a mut [T; N]: Trait, fn(T) = (T,) }");
+    let bound = |index| BoundVar::new(DebruijnIdx::ZERO, index);
+    let interned = |kind: TyKind<LogicInterner>| kind.intern(LogicInterner);
+    let array = interned(TyKind::Array(ty!(var 0:1), bound(2).to_const(LogicInterner)));
+    let lifetime = bound(0).to_lifetime(LogicInterner);
+    let reference = interned(TyKind::Ref(Mutability::Mut, lifetime, array));
+    let trait_ref = TraitRef { trait_name: Ident::unspanned("Trait"), args: subst![] };
+    let fn_ptr = interned(TyKind::Fn(FnSig {
+        params: subst![ty!(var 0:1)],
+        ret: interned(TyKind::unit(LogicInterner)),
+    }));
+    let tuple = interned(TyKind::Tuple(subst![ty!(var 0:1)]));
+    let eq = Constraint::Eq(EqConstraint { lhs: fn_ptr, rhs: tuple });
+    let expected = Goal::intern(
+        LogicInterner,
+        GoalData::And(
+            goal!(domain impl reference, trait_ref),
+            Goal::intern(LogicInterner, GoalData::DomainGoal(DomainGoal::Holds(eq))),
+        ),
+    );
+    let variables = [VariableKind::Lifetime, VariableKind::Ty, VariableKind::Const(None)];
+    let variables = Variables::intern(LogicInterner, variables.map(Variable::new));
+    let expected = GoalData::Quantified(Quantifier::ForAll, Binders::new(variables, expected));
+    assert_eq!(goal, Goal::intern(LogicInterner, expected));
+
+    let ast = logic_parse::parse_goal("forall<T> { [i32; T]: Trait }").unwrap();
+    assert!(matches!(logic_ir::lower_goal(&ast), Err(LoweringError::UnboundConst(..))));
+}
+
+#[test]
+fn test_lower_typed_const_params() {
+    let program = lower!("impl<const N: usize> Default for [T; N] {}");
+    assert_eq!(
+        program.program_clauses(),
+        lower!("for<const N: usize> { [T; N]: Default :- true }.").clauses
+    );
+    // only whether a const is an integer or a `bool` is kept
+    let goal = lower_goal!("forall<const N: bool> { [T; N]: Default }");
+    assert_ne!(goal, lower_goal!("forall<const N> { [T; N]: Default }"));
+    assert_ne!(goal, lower_goal!("forall<const N: usize> { [T; N]: Default }"));
+    let goal = lower_goal!("forall<const N: u8> { [T; N]: Default }");
+    assert_eq!(goal, lower_goal!("forall<const N: usize> { [T; N]: Default }"));
+    let ast = logic_parse::parse_goal("forall<const N: Vec<u8>> { [T; N]: Default }").unwrap();
+    assert!(matches!(logic_ir::lower_goal(&ast), Err(LoweringError::InvalidConstTy(..))));
+}
+
+#[test]
+fn test_lower_impl_to_program_clause() {
+    let program = r"
+        trait Clone {}
+        impl<T> Clone for Vec<T> where T: Clone, T: Copy {}
+        impl Clone for i32 {}
+    ";
+    let ir = lower!(program);
+    assert_eq!(ir.traits.len(), 1);
+    assert!(ir.clauses.is_empty());
+
+    // the same as writing the clauses by hand
+    let expected = lower!(
+        "for<Self> { WellFormed(Self: Clone) :- Self: Clone }.
+        for<Self> { Self: Clone :- FromEnv(Self: Clone) }.
+        dyn Clone: Clone.
+        for<T> { Vec<T>: Clone :- T: Clone, T: Copy }.
+        i32: Clone."
+    );
+    assert_eq!(ir.program_clauses(), expected.clauses);
+}
+
+#[test]
+fn test_lower_decls_to_well_formed_and_implied_bound_clauses() {
+    let program = r"
+        struct i32 {}
+        struct Set<T> where T: Ord, T: Hash {}
+        trait Ord<Rhs> where Self: Eq<Rhs>, Rhs = i32 {}
+    ";
+    let expected = lower!(
+        "WellFormed(i32).
+        for<T> { WellFormed(Set<T>) :- T: Ord, T: Hash }.
+        for<T> { FromEnv(T: Ord) :- FromEnv(Set<T>) }.
+        for<T> { FromEnv(T: Hash) :- FromEnv(Set<T>) }.
+        for<Self, Rhs> {
+            WellFormed(Self: Ord<Rhs>) :- Self: Ord<Rhs>, WellFormed(Self: Eq<Rhs>), Rhs = i32
+        }.
+        for<Self, Rhs> { Self: Ord<Rhs> :- FromEnv(Self: Ord<Rhs>) }.
+        for<Self, Rhs> { FromEnv(Self: Eq<Rhs>) :- FromEnv(Self: Ord<Rhs>) }.
+        for<Rhs> { dyn Ord<Rhs>: Ord<Rhs> :- true }.
+        for<Rhs> { dyn Ord<Rhs>: Eq<Rhs> :- true }."
+    );
+    assert_eq!(lower!(program).program_clauses(), expected.clauses);
+}
+
+#[test]
+fn test_lower_object_safe_traits_to_dyn_clauses() {
+    let program = r"
+        trait Any {}
+        trait Iterator<T> where Self: Into<Vec<T>> {}
+        trait Into<T> where Self: Any {}
+    ";
+    let expected = lower!(
+        "for<Self> { WellFormed(Self: Any) :- Self: Any }.
+        for<Self> { Self: Any :- FromEnv(Self: Any) }.
+        dyn Any: Any.
+        for<Self, T> {
+            WellFormed(Self: Iterator<T>) :- Self: Iterator<T>, WellFormed(Self: Into<Vec<T>>)
+        }.
+        for<Self, T> { Self: Iterator<T> :- FromEnv(Self: Iterator<T>) }.
+        for<Self, T> { FromEnv(Self: Into<Vec<T>>) :- FromEnv(Self: Iterator<T>) }.
+        for<T> { dyn Iterator<T>: Iterator<T> :- true }.
+        for<T> { dyn Iterator<T>: Into<Vec<T>> :- true }.
+        for<T> { dyn Iterator<T>: Any :- true }.
+        for<Self, T> { WellFormed(Self: Into<T>) :- Self: Into<T>, WellFormed(Self: Any) }.
+        for<Self, T> { Self: Into<T> :- FromEnv(Self: Into<T>) }.
+        for<Self, T> { FromEnv(Self: Any) :- FromEnv(Self: Into<T>) }.
+        for<T> { dyn Into<T>: Into<T> :- true }.
+        for<T> { dyn Into<T>: Any :- true }."
+    );
+    assert_eq!(lower!(program).program_clauses(), expected.clauses);
+}
+
+#[test]
+fn test_lower_rejects_dyn_of_object_unsafe_traits() {
+    let program = r"
+        trait Sized {}
+        trait Clone where Self: Sized {}
+        trait Copy where Self: Clone {}
+        trait Eq<Rhs> {}
+        trait Ord where Self: Eq<Self> {}
+        trait Hash where Vec<Self>: Eq<i32> {}
+        trait Display where Self: 'static {}
+        trait Debug where Self: Display {}
+    ";
+    let object_unsafe = lower!(program)
+        .traits
+        .iter()
+        .filter(|trait_datum| !trait_datum.object_safe)
+        .map(|trait_datum| trait_datum.name.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(object_unsafe, ["Clone", "Copy", "Ord", "Hash"]);
+
+    for ty in ["dyn Clone", "dyn Debug + Copy", "Box<dyn Ord>"] {
+        let ast = logic_parse::parse_program(&format!("{} {}: Debug.", program, ty)).unwrap();
+        assert!(matches!(lower_ast(&ast), Err(LoweringError::NotObjectSafe(..))), "{}", ty);
+    }
+    let _ir = lower!(&format!("{} dyn Debug + Eq<i32>: Debug.", program));
+
+    // goals are lowered in the context of the program as well
+    let ast = logic_parse::parse_program(program).unwrap();
+    let goal = logic_parse::parse_goal("dyn Clone: Clone").unwrap();
+    assert!(matches!(lower_goal_in(&ast, &goal), Err(LoweringError::NotObjectSafe(..))));
+}
+
+#[test]
+fn test_lower_marker_traits() {
+    let ir = lower!("#[marker] trait Eq {} trait Clone {}");
+    assert_eq!(
+        ir.traits.iter().map(|trait_datum| trait_datum.marker).collect::<Vec<_>>(),
+        [true, false]
+    );
+    let ast = logic_parse::parse_program("#[marker] trait Iterator { type Item; }").unwrap();
+    assert!(matches!(lower_ast(&ast), Err(LoweringError::MarkerWithItems(..))));
+}
+
+#[test]
+fn test_lower_attrs() {
+    // the attributes can be given in any order
+    let ir = lower!(
+        "#[crate(std)] #[fundamental] struct Box<T> {}
+        #[marker] #[crate(std)] trait Eq {}"
+    );
+    assert_eq!(ir.structs[0].krate, Some(Ident::unspanned("std")));
+    assert!(ir.structs[0].fundamental);
+    assert_eq!(ir.traits[0].krate, Some(Ident::unspanned("std")));
+    assert!(ir.traits[0].marker);
+    for program in [
+        "#[marker] struct Foo {}",
+        "#[fundamental] trait Foo {}",
+        "#[marker] impl Foo for i32 {}",
+        "#[crate] struct Foo {}",
+        "#[fundamental(std)] struct Foo {}",
+        "#[crate(std)] #[crate(core)] trait Foo {}",
+        "#[derive(Clone)] struct Foo {}",
+    ] {
+        let ast = logic_parse::parse_program(program).unwrap();
+        assert!(matches!(lower_ast(&ast), Err(LoweringError::InvalidAttr(..))), "{}", program);
+    }
+}
+
+#[test]
+fn test_lower_assoc_ty_values_to_normalize_clauses() {
+    let program = r"
+        impl<T> Iterator for Vec<T> where T: Clone { type Item = T; type Iter = Iter<T>; }
+    ";
+    let expected = lower!(
+        "for<T> { Vec<T>: Iterator :- T: Clone }.
+        for<T> { Normalize(<Vec<T> as Iterator>::Item -> T) :- T: Clone }.
+        for<T> { Normalize(<Vec<T> as Iterator>::Iter -> Iter<T>) :- T: Clone }."
+    );
+    assert_eq!(lower!(program).program_clauses(), expected.clauses);
+}
+
+#[test]
+fn test_lower_opaque_tys_to_bound_and_reveal_clauses() {
+    let program = r"
+        opaque type Iter<T>: Iterator<T> + Clone = Vec<T>;
+        Iter<i32>: Debug.
+    ";
+    // the opaque type is declared so that `Iter` is lowered to it in the clauses
+    let expected = lower!(
+        "opaque type Iter<T> = ();
+        Iter<i32>: Debug.
+        for<T> { Iter<T>: Iterator<T> :- true }.
+        for<T> { Iter<T>: Clone :- true }.
+        for<T> { Normalize(Iter<T> -> Vec<T>) :- Reveal }."
+    );
+    assert_eq!(lower!(program).program_clauses(), expected.clauses);
+}
+
+#[test]
+fn test_lower_with_arena_interner_shares_equal_values() {
+    let program = logic_parse::parse_program(
+        r"
+        impl Clone for Vec<i32> {}
+        impl Clone for Vec<Vec<i32>> {}
+    ",
+    )
+    .unwrap();
+    let arenas = Arenas::default();
+    let interner = ArenaInterner::new(&arenas);
+    let ir = lower_ast_with(interner, &program).unwrap();
+    let self_ty = |i: usize| ir.impls[i].binders.skip_binders().header.ty;
+    let inner_ty = match self_ty(1).kind(interner) {
+        TyKind::Structure(_, args) => *args.as_slice()[0].ty(interner).unwrap(),
+        _ => unreachable!(),
+    };
+    assert_eq!(self_ty(0), inner_ty);
+    assert!(std::ptr::eq(self_ty(0).data(interner), inner_ty.data(interner)));
+    assert_ne!(self_ty(0), self_ty(1));
+}
+
+#[test]
+fn test_lower_ty_flags() {
+    let program = lower!("impl<T, 'a> Clone for (Vec<i32>, &'a T) {}");
+    let flags = program.impls[0].binders.skip_binders().header.ty.flags(LogicInterner);
+    let expected = TyFlags { has_infer: false, has_placeholder: false, outer_binder: 1, size: 6 };
+    assert_eq!(flags, expected);
+
+    let infer = InferVar::new(InferIdx::new(0)).to_ty(LogicInterner);
+    let placeholder = PlaceholderVar { universe: UniverseIdx::ROOT, index: 0 }.to_ty(LogicInterner);
+    let outer = ty!(var 2:0);
+    let tys = [infer, placeholder, outer].map(|ty| ty.into_generic_arg(LogicInterner));
+    let tuple = TyKind::Tuple(Subst::intern(LogicInterner, tys)).intern(LogicInterner);
+    let expected = TyFlags { has_infer: true, has_placeholder: true, outer_binder: 3, size: 4 };
+    assert_eq!(tuple.flags(LogicInterner), expected);
+}
+
+#[test]
+fn test_visit_breaks_at_first_bound_var() {
+    /// counts the types it visits until it finds a bound variable
+    struct FindBound {
+        visited: usize,
+    }
+
+    impl Visitor<LogicInterner> for FindBound {
+        type BreakTy = BoundVar;
+
+        fn interner(&self) -> LogicInterner {
+            LogicInterner
+        }
+
+        fn visit_ty(&mut self, ty: &Ty<LogicInterner>) -> ControlFlow<BoundVar> {
+            self.visited += 1;
+            match ty.kind(LogicInterner) {
+                &TyKind::Bound(bound) => ControlFlow::Break(bound),
+                _ => ty.super_visit_with(self),
+            }
+        }
+    }
+
+    let program = lower!("impl<T> Clone for (Vec<i32>, T, i32) {}");
+    let mut visitor = FindBound { visited: 0 };
+    let found = program.impls[0].binders.visit_with(&mut visitor);
+    assert_eq!(found, ControlFlow::Break(BoundVar::new(DebruijnIdx::ZERO, 0)));
+    // the tuple, `Vec<i32>`, `i32` and `T`, but not the `i32` after it
+    assert_eq!(visitor.visited, 4);
+}
+
+/// the binders of a goal `exists<..> { .. }` or `forall<..> { .. }`
+fn quantified(goal: &Goal<LogicInterner>) -> &Binders<Goal<LogicInterner>> {
+    match goal.data(LogicInterner) {
+        GoalData::Quantified(_, binders) => binders,
+        goal => panic!("expected a quantified goal, found `{:?}`", goal),
+    }
+}
+
+#[test]
+fn test_subst_under_nested_binders() {
+    let goal = lower_goal!("exists<T> { forall<U> { Pair<T, U>: Foo } }");
+    let body = quantified(&goal).skip_binders();
+    let substituted = subst![ty!(i32)].apply(LogicInterner, body.clone());
+    assert_eq!(substituted, lower_goal!("forall<U> { Pair<i32, U>: Foo }"));
+
+    // substituting the variable of the removed binder for itself changes nothing, as long as
+    // it is shifted past the `forall` rather than captured by it
+    let substituted = subst![ty!(var 0:0)].apply(LogicInterner, body.clone());
+    assert_eq!(&substituted, body);
+}
+
+#[test]
+fn test_shift_under_nested_binders() {
+    let goal = lower_goal!("forall<T> { exists<U> { Pair<T, U>: Foo } }");
+    let body = quantified(&goal).skip_binders();
+    let nested = lower_goal!("forall<T> { forall<V> { exists<U> { Pair<T, U>: Foo } } }");
+    let nested_body = quantified(quantified(&nested).skip_binders()).skip_binders();
+
+    let shifted = body.clone().shifted_in(LogicInterner);
+    assert_eq!(&shifted, nested_body);
+    assert_eq!(&shifted.shifted_out(LogicInterner).unwrap(), body);
+    // `T` refers to the binder that would be removed
+    assert!(body.clone().shifted_out(LogicInterner).is_err());
+}
+
+#[test]
+fn test_alpha_eq_ignores_binder_order() {
+    let src = "
+        for<T, U> { Pair<T, U>: Trait :- U: Trait }.
+        for<U, T> { Pair<T, U>: Trait :- U: Trait }.
+        for<T, U> { Pair<U, T>: Trait :- U: Trait }.
+    ";
+    let program = lower_ast(&logic_parse::parse_program(src).unwrap()).unwrap();
+    let implications = program
+        .clauses
+        .iter()
+        .map(|clause| {
+            let ClauseData::Implies(implication) = clause.data(LogicInterner);
+            implication.clone()
+        })
+        .collect::<Vec<_>>();
+    assert_ne!(implications[0], implications[1]);
+    assert!(implications[0].alpha_eq(LogicInterner, &implications[1]));
+    let hash =
+        |implication: &Binders<Implication<LogicInterner>>| implication.alpha_hash(LogicInterner);
+    assert_eq!(hash(&implications[0]), hash(&implications[1]));
+    assert!(!implications[0].alpha_eq(LogicInterner, &implications[2]));
+
+    // the database only keeps the first of the clauses that are alpha-equivalent
+    let clauses = program.clauses.as_slice();
+    assert_eq!(lower!(src).clauses.as_slice(), [clauses[0].clone(), clauses[2].clone()]);
+}
+
+#[test]
+fn test_alpha_hash_is_the_same_for_every_interner() {
+    let src = "
+        for<T, U> { Pair<T, U>: Trait :- U: Trait, exists<'a> { &'a T: Trait } }.
+        for<U, T, const N> { Pair<[T; N], U>: Trait :- not { T: Trait } }.
+    ";
+    let ast = logic_parse::parse_program(src).unwrap();
+    let arenas = Arenas::default();
+    let interner = ArenaInterner::new(&arenas);
+    let (program, arena_program) =
+        (lower_ast(&ast).unwrap(), lower_ast_with(interner, &ast).unwrap());
+    for (clause, arena_clause) in program.clauses.iter().zip(arena_program.clauses.iter()) {
+        let ClauseData::Implies(implication) = clause.data(LogicInterner);
+        let ClauseData::Implies(arena_implication) = arena_clause.data(interner);
+        assert_eq!(implication.alpha_hash(LogicInterner), arena_implication.alpha_hash(interner));
+    }
+}
+
+#[test]
+fn test_alpha_eq_ignores_infer_vars() {
+    let infer = |index| InferVar::new(InferIdx::new(index)).to_ty(LogicInterner);
+    let pair = |lhs: Ty<LogicInterner>, rhs: Ty<LogicInterner>| {
+        TyKind::Structure(Ident::unspanned("Pair"), subst![lhs, rhs]).intern(LogicInterner)
+    };
+    let canonical = |ty: Ty<LogicInterner>| Canonical {
+        binders: Variables::intern(LogicInterner, [Variable::new(VariableKind::Ty)]),
+        value: subst![ty],
+    };
+
+    let a = canonical(pair(infer(3), ty!(var 0:0)));
+    let b = canonical(pair(infer(7), ty!(var 0:0)));
+    assert!(a.alpha_eq(LogicInterner, &b));
+    assert_eq!(a.alpha_hash(LogicInterner), b.alpha_hash(LogicInterner));
+    // the same variable twice is not the same as two different ones
+    let a = canonical(pair(infer(3), infer(3)));
+    let b = canonical(pair(infer(3), infer(4)));
+    assert!(!a.alpha_eq(LogicInterner, &b));
+}
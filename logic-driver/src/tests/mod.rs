@@ -1 +1,4 @@
+mod coherence_tests;
 mod lowering_tests;
+mod parallel_tests;
+mod print_tests;
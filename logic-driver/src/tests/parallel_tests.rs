@@ -0,0 +1,38 @@
+use crate::{Database, LoweringDatabase};
+use logic_engine::Solution;
+use logic_ir::*;
+use salsa::ParallelDatabase;
+use std::sync::Arc;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_ir_is_send_and_sync() {
+    assert_send_sync::<Program<LogicInterner>>();
+    assert_send_sync::<Environment<LogicInterner>>();
+    assert_send_sync::<Solution<LogicInterner>>();
+}
+
+#[test]
+fn test_query_snapshots_from_other_threads() {
+    let db = Database::new(
+        r"
+        trait Clone {}
+        impl<T> Clone for Vec<T> where T: Clone {}
+        impl Clone for i32 {}
+    ",
+    );
+    let goals =
+        ["Vec<i32>: Clone", "Vec<Vec<i32>>: Clone", "Vec<u32>: Clone", "exists<T> { T: Clone }"];
+    let handles = goals
+        .iter()
+        .map(|&goal| {
+            let db = db.snapshot();
+            std::thread::spawn(move || db.query(Arc::new(goal.to_owned())))
+        })
+        .collect::<Vec<_>>();
+    for (goal, handle) in goals.iter().zip(handles) {
+        let solution = handle.join().unwrap();
+        assert_eq!(solution, db.query(Arc::new(goal.to_string())), "{}", goal);
+    }
+}
@@ -0,0 +1,542 @@
+use logic_ir::*;
+use logic_parse::ast;
+use proptest::prelude::*;
+
+const TY_NAMES: &[&str] = &["A", "B", "T", "i32", "u32"];
+const TY_CONSTRUCTORS: &[&str] = &["Vec", "Box", "Result"];
+const TRAIT_NAMES: &[&str] = &["Clone", "Copy", "Trait"];
+const PREDICATE_NAMES: &[&str] = &["cool", "parent"];
+const ASSOC_TY_NAMES: &[&str] = &["Item", "Output"];
+// deliberately overlaps with `TY_NAMES` so that some variables shadow type names
+const VAR_NAMES: &[&str] = &["A", "B", "X"];
+const LIFETIME_NAMES: &[&str] = &["'a", "'b"];
+const CONST_TY_NAMES: &[&str] = &["usize", "bool"];
+
+fn ident(names: &'static [&'static str]) -> impl Strategy<Value = Ident> {
+    prop::sample::select(names).prop_map(Ident::unspanned)
+}
+
+fn arb_ty() -> impl Strategy<Value = ast::Ty> + Clone {
+    let leaf = prop_oneof![
+        8 => ident(TY_NAMES).prop_map(|name| ast::Ty::Structure(name, vec![])),
+        1 => Just(ast::Ty::Never),
+    ];
+    leaf.prop_recursive(3, 16, 3, |inner| {
+        let tys = prop::collection::vec(inner.clone(), 0..3);
+        let mutability = prop_oneof![Just(Mutability::Not), Just(Mutability::Mut)];
+        prop_oneof![
+            6 => {
+                let args = prop::collection::vec(arb_generic_arg(inner.clone()), 1..3);
+                (ident(TY_CONSTRUCTORS), args)
+                    .prop_map(|(name, args)| ast::Ty::Structure(name, args))
+            },
+            2 => arb_projection_ty(inner.clone()).prop_map(ast::Ty::Projection),
+            1 => tys.clone().prop_map(ast::Ty::Tuple),
+            1 => (mutability, arb_lifetime(), inner.clone()).prop_map(|(mutability, lifetime, ty)| {
+                ast::Ty::Ref(mutability, lifetime, Box::new(ty))
+            }),
+            1 => inner.clone().prop_map(|ty| ast::Ty::Slice(Box::new(ty))),
+            1 => (inner.clone(), arb_const())
+                .prop_map(|(ty, len)| ast::Ty::Array(Box::new(ty), Box::new(len))),
+            1 => (tys, inner.clone()).prop_map(|(params, ret)| ast::Ty::Fn(params, Box::new(ret))),
+            1 => {
+                let args = prop::collection::vec(arb_generic_arg(inner), 0..2);
+                let bound = (ident(TRAIT_NAMES), args)
+                    .prop_map(|(trait_name, args)| ast::TraitRef { trait_name, args });
+                prop::collection::vec(bound, 1..3).prop_map(ast::Ty::Dyn)
+            },
+        ]
+    })
+}
+
+fn arb_const() -> impl Strategy<Value = ast::Const> {
+    prop_oneof![
+        ident(VAR_NAMES).prop_map(ast::Const::Param),
+        arb_const_value().prop_map(ast::Const::Value),
+    ]
+}
+
+fn arb_lifetime() -> impl Strategy<Value = ast::Lifetime> {
+    prop_oneof![ident(LIFETIME_NAMES).prop_map(ast::Lifetime::Named), Just(ast::Lifetime::Static),]
+}
+
+fn arb_const_value() -> impl Strategy<Value = ConstValue> {
+    prop_oneof![
+        any::<u8>().prop_map(|i| ConstValue::Int(i.into())),
+        any::<bool>().prop_map(ConstValue::Bool)
+    ]
+}
+
+fn arb_generic_arg(
+    ty: impl Strategy<Value = ast::Ty> + Clone,
+) -> impl Strategy<Value = ast::GenericArg> + Clone {
+    prop_oneof![
+        6 => ty.prop_map(ast::GenericArg::Ty),
+        2 => arb_lifetime().prop_map(ast::GenericArg::Lifetime),
+        1 => arb_const_value().prop_map(ast::GenericArg::Const),
+    ]
+}
+
+fn arb_projection_ty(
+    ty: impl Strategy<Value = ast::Ty> + Clone,
+) -> impl Strategy<Value = ast::ProjectionTy> {
+    let args = prop::collection::vec(arb_generic_arg(ty.clone()), 0..2);
+    (ty, ident(TRAIT_NAMES), args, ident(ASSOC_TY_NAMES)).prop_map(
+        |(ty, trait_name, args, name)| ast::ProjectionTy {
+            ty: Box::new(ty),
+            trait_ref: ast::TraitRef { trait_name, args },
+            name,
+        },
+    )
+}
+
+fn arb_impl_constraint() -> impl Strategy<Value = ast::ImplConstraint> {
+    let args = prop::collection::vec(arb_generic_arg(arb_ty()), 0..2);
+    (arb_ty(), ident(TRAIT_NAMES), args).prop_map(
+        |(ty, trait_name, args)| ast::ImplConstraint {
+            ty,
+            trait_ref: ast::TraitRef { trait_name, args },
+        },
+    )
+}
+
+fn arb_domain_goal() -> impl Strategy<Value = ast::DomainGoal> {
+    let implemented = arb_impl_constraint().prop_map(ast::Constraint::Implemented);
+    let args = prop::collection::vec(arb_generic_arg(arb_ty()), 0..3);
+    let predicate = (ident(PREDICATE_NAMES), args)
+        .prop_map(|(name, args)| ast::Constraint::Predicate(ast::Predicate { name, args }));
+    let well_formed = prop_oneof![
+        arb_ty().prop_map(ast::WellFormed::Ty),
+        arb_impl_constraint().prop_map(ast::WellFormed::Trait),
+    ];
+    let from_env = prop_oneof![
+        arb_ty().prop_map(ast::FromEnv::Ty),
+        arb_impl_constraint().prop_map(ast::FromEnv::Trait),
+    ];
+    prop_oneof![
+        4 => prop_oneof![implemented, predicate].prop_map(ast::DomainGoal::Holds),
+        1 => well_formed.prop_map(ast::DomainGoal::WellFormed),
+        1 => from_env.prop_map(ast::DomainGoal::FromEnv),
+        1 => (arb_alias_ty(), arb_ty())
+            .prop_map(|(alias, ty)| ast::DomainGoal::Normalize(ast::Normalize { alias, ty })),
+        1 => Just(ast::DomainGoal::Reveal),
+    ]
+}
+
+fn arb_alias_ty() -> impl Strategy<Value = ast::AliasTy> {
+    let args = prop::collection::vec(arb_generic_arg(arb_ty()), 0..2);
+    prop_oneof![
+        arb_projection_ty(arb_ty()).prop_map(ast::AliasTy::Projection),
+        (ident(TY_CONSTRUCTORS), args).prop_map(|(name, args)| ast::AliasTy::Opaque(name, args)),
+    ]
+}
+
+/// domain goals that may only appear in goals and not as the consequent of a clause
+fn arb_goal_domain_goal() -> impl Strategy<Value = ast::DomainGoal> {
+    let eq = (arb_ty(), arb_ty())
+        .prop_map(|(lhs, rhs)| ast::Constraint::Eq(ast::EqConstraint { lhs, rhs }));
+    let lifetime_outlives = (arb_lifetime(), arb_lifetime()).prop_map(|(a, b)| {
+        ast::Constraint::LifetimeOutlives(ast::LifetimeOutlives { a, b })
+    });
+    let ty_outlives = (arb_ty(), arb_lifetime()).prop_map(|(ty, lifetime)| {
+        ast::Constraint::TyOutlives(ast::TyOutlives { ty, lifetime })
+    });
+    prop_oneof![
+        8 => arb_domain_goal(),
+        2 => eq.prop_map(ast::DomainGoal::Holds),
+        1 => lifetime_outlives.prop_map(ast::DomainGoal::Holds),
+        1 => ty_outlives.prop_map(ast::DomainGoal::Holds),
+    ]
+}
+
+fn arb_vars(min: usize) -> impl Strategy<Value = Vec<Var>> {
+    let var = prop_oneof![
+        3 => ident(VAR_NAMES).prop_map(Var::new),
+        1 => ident(LIFETIME_NAMES).prop_map(Var::lifetime),
+        1 => (ident(VAR_NAMES), prop::option::of(ident(CONST_TY_NAMES))).prop_map(|(name, ty)| {
+            Var::constant(name, ty.map(|ty| ast::Ty::Structure(ty, vec![])))
+        }),
+    ];
+    prop::collection::vec(var, min..3)
+}
+
+fn arb_clause_with(goal: impl Strategy<Value = ast::Goal>) -> impl Strategy<Value = ast::Clause> {
+    prop_oneof![
+        arb_domain_goal().prop_map(ast::Clause::DomainGoal),
+        (arb_vars(0), arb_domain_goal(), goal).prop_map(|(vars, consequent, condition)| {
+            ast::Clause::Implies(ast::Implication { vars, consequent, condition })
+        })
+    ]
+}
+
+fn arb_goal() -> impl Strategy<Value = ast::Goal> {
+    let leaf = prop_oneof![
+        4 => arb_goal_domain_goal().prop_map(ast::Goal::DomainGoal),
+        1 => Just(ast::Goal::True),
+    ];
+    leaf.prop_recursive(4, 24, 2, |inner| {
+        prop_oneof![
+            (inner.clone(), inner.clone())
+                .prop_map(|(lhs, rhs)| ast::Goal::And(Box::new(lhs), Box::new(rhs))),
+            (inner.clone(), inner.clone())
+                .prop_map(|(lhs, rhs)| ast::Goal::Or(Box::new(lhs), Box::new(rhs))),
+            (arb_vars(1), inner.clone())
+                .prop_map(|(vars, goal)| ast::Goal::Exists(vars, Box::new(goal))),
+            (arb_vars(1), inner.clone())
+                .prop_map(|(vars, goal)| ast::Goal::ForAll(vars, Box::new(goal))),
+            (arb_clause_with(inner.clone()), inner.clone()).prop_map(|(clause, goal)| {
+                ast::Goal::Implies(Box::new(clause), Box::new(goal))
+            }),
+            inner.prop_map(|goal| ast::Goal::Not(Box::new(goal))),
+        ]
+    })
+}
+
+fn arb_program() -> impl Strategy<Value = ast::Program> {
+    prop::collection::vec(arb_clause_with(arb_goal()), 0..4).prop_map(|clauses| {
+        let items = clauses.into_iter().map(ast::ItemKind::Clause);
+        ast::Program::new(items.map(ast::Item::unspanned).collect())
+    })
+}
+
+/// The lowering rejects shadowed variables, so this renames any binder whose name is
+/// already in scope (or repeated within the same binder) to a fresh name.
+/// Lifetimes that aren't bound are rejected as well, these are replaced by `'static`,
+/// and so are const parameters named as types, these are replaced by `i32`,
+/// and array lengths that don't name a const parameter, these are replaced by `0`.
+#[derive(Default)]
+struct Unshadow {
+    scopes: Vec<Vec<(Symbol, Symbol, ast::VarKind)>>,
+    fresh: usize,
+}
+
+impl Unshadow {
+    fn lookup(&self, symbol: &Symbol) -> Option<(Symbol, ast::VarKind)> {
+        let mut innermost_first = self.scopes.iter().rev().flatten();
+        let (_, renamed, kind) = innermost_first.find(|(name, ..)| name == symbol)?;
+        Some((renamed.clone(), kind.clone()))
+    }
+
+    fn enter(&mut self, vars: &mut [Var]) {
+        let mut scope = vec![];
+        for var in vars {
+            let name = var.ident.symbol.clone();
+            let taken =
+                self.scopes.iter().flatten().chain(&scope).any(|(_, renamed, _)| renamed == &name);
+            if taken {
+                self.fresh += 1;
+                var.ident.symbol = Symbol::from(format!("{}{}", name, self.fresh));
+            }
+            scope.push((name, var.ident.symbol.clone(), var.kind.clone()));
+        }
+        self.scopes.push(scope);
+    }
+
+    fn program(&mut self, program: &mut ast::Program) {
+        for item in &mut program.items {
+            match &mut item.kind {
+                ast::ItemKind::Clause(clause) => self.clause(clause),
+                ast::ItemKind::Struct(..)
+                | ast::ItemKind::Trait(..)
+                | ast::ItemKind::Impl(..)
+                | ast::ItemKind::OpaqueTy(..) => {}
+            }
+        }
+    }
+
+    fn clause(&mut self, clause: &mut ast::Clause) {
+        match clause {
+            ast::Clause::DomainGoal(domain_goal) => self.domain_goal(domain_goal),
+            ast::Clause::Implies(implication) => {
+                self.enter(&mut implication.vars);
+                self.domain_goal(&mut implication.consequent);
+                self.goal(&mut implication.condition);
+                self.scopes.pop();
+            }
+            ast::Clause::And(lhs, rhs) => {
+                self.clause(lhs);
+                self.clause(rhs);
+            }
+        }
+    }
+
+    fn goal(&mut self, goal: &mut ast::Goal) {
+        match goal {
+            ast::Goal::DomainGoal(domain_goal) => self.domain_goal(domain_goal),
+            ast::Goal::And(lhs, rhs) | ast::Goal::Or(lhs, rhs) => {
+                self.goal(lhs);
+                self.goal(rhs);
+            }
+            ast::Goal::Implies(clause, goal) => {
+                self.clause(clause);
+                self.goal(goal);
+            }
+            ast::Goal::Exists(vars, goal) | ast::Goal::ForAll(vars, goal) => {
+                self.enter(vars);
+                self.goal(goal);
+                self.scopes.pop();
+            }
+            ast::Goal::Not(goal) => self.goal(goal),
+            ast::Goal::True => {}
+        }
+    }
+
+    fn domain_goal(&mut self, domain_goal: &mut ast::DomainGoal) {
+        match domain_goal {
+            ast::DomainGoal::Holds(ast::Constraint::Implemented(constraint))
+            | ast::DomainGoal::WellFormed(ast::WellFormed::Trait(constraint))
+            | ast::DomainGoal::FromEnv(ast::FromEnv::Trait(constraint)) => {
+                self.ty(&mut constraint.ty);
+                constraint.trait_ref.args.iter_mut().for_each(|arg| self.generic_arg(arg));
+            }
+            ast::DomainGoal::WellFormed(ast::WellFormed::Ty(ty))
+            | ast::DomainGoal::FromEnv(ast::FromEnv::Ty(ty)) => self.ty(ty),
+            ast::DomainGoal::Normalize(normalize) => {
+                match &mut normalize.alias {
+                    ast::AliasTy::Projection(projection) => self.projection_ty(projection),
+                    ast::AliasTy::Opaque(_, args) =>
+                        args.iter_mut().for_each(|arg| self.generic_arg(arg)),
+                }
+                self.ty(&mut normalize.ty);
+            }
+            ast::DomainGoal::Reveal => {}
+            ast::DomainGoal::Holds(ast::Constraint::Predicate(predicate)) =>
+                predicate.args.iter_mut().for_each(|arg| self.generic_arg(arg)),
+            ast::DomainGoal::Holds(ast::Constraint::Eq(eq)) => {
+                self.ty(&mut eq.lhs);
+                self.ty(&mut eq.rhs);
+            }
+            ast::DomainGoal::Holds(ast::Constraint::LifetimeOutlives(outlives)) => {
+                self.lifetime(&mut outlives.a);
+                self.lifetime(&mut outlives.b);
+            }
+            ast::DomainGoal::Holds(ast::Constraint::TyOutlives(outlives)) => {
+                self.ty(&mut outlives.ty);
+                self.lifetime(&mut outlives.lifetime);
+            }
+        }
+    }
+
+    fn generic_arg(&mut self, arg: &mut ast::GenericArg) {
+        match arg {
+            ast::GenericArg::Ty(ty) => match self.const_param(ty) {
+                // the only place a const parameter may be named
+                Some(renamed) => {
+                    let name = Ident::new(ast::Span::new(0, 0), renamed);
+                    *ty = ast::Ty::Structure(name, vec![]);
+                }
+                None => self.ty(ty),
+            },
+            ast::GenericArg::Lifetime(lifetime) => self.lifetime(lifetime),
+            ast::GenericArg::Const(..) => {}
+        }
+    }
+
+    /// the renamed const parameter if `ty` names one
+    fn const_param(&self, ty: &ast::Ty) -> Option<Symbol> {
+        match ty {
+            ast::Ty::Structure(name, args) if args.is_empty() => match self.lookup(&name.symbol)? {
+                (renamed, ast::VarKind::Const(..)) => Some(renamed),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn lifetime(&mut self, lifetime: &mut ast::Lifetime) {
+        if let ast::Lifetime::Named(name) = lifetime {
+            match self.lookup(&name.symbol) {
+                Some((renamed, _)) => name.symbol = renamed,
+                None => *lifetime = ast::Lifetime::Static,
+            }
+        }
+    }
+
+    fn ty(&mut self, ty: &mut ast::Ty) {
+        match ty {
+            ast::Ty::Structure(name, args) if args.is_empty() => match self.lookup(&name.symbol) {
+                Some((_, ast::VarKind::Const(..))) => name.symbol = Symbol::from("i32"),
+                Some((renamed, _)) => name.symbol = renamed,
+                None => {}
+            },
+            ast::Ty::Structure(_, args) => args.iter_mut().for_each(|arg| self.generic_arg(arg)),
+            ast::Ty::Projection(projection) => self.projection_ty(projection),
+            ast::Ty::Tuple(tys) => tys.iter_mut().for_each(|ty| self.ty(ty)),
+            ast::Ty::Ref(_, lifetime, ty) => {
+                self.lifetime(lifetime);
+                self.ty(ty);
+            }
+            ast::Ty::Slice(ty) => self.ty(ty),
+            ast::Ty::Array(ty, len) => {
+                self.ty(ty);
+                if let ast::Const::Param(name) = &mut **len {
+                    match self.lookup(&name.symbol) {
+                        Some((renamed, ast::VarKind::Const(..))) => name.symbol = renamed,
+                        _ => **len = ast::Const::Value(ConstValue::Int(0)),
+                    }
+                }
+            }
+            ast::Ty::Fn(params, ret) => {
+                params.iter_mut().for_each(|ty| self.ty(ty));
+                self.ty(ret);
+            }
+            ast::Ty::Never => {}
+            ast::Ty::Dyn(bounds) => bounds
+                .iter_mut()
+                .flat_map(|bound| &mut bound.args)
+                .for_each(|arg| self.generic_arg(arg)),
+        }
+    }
+
+    fn projection_ty(&mut self, projection: &mut ast::ProjectionTy) {
+        self.ty(&mut projection.ty);
+        projection.trait_ref.args.iter_mut().for_each(|arg| self.generic_arg(arg));
+    }
+}
+
+fn unshadowed_program() -> impl Strategy<Value = ast::Program> {
+    arb_program().prop_map(|mut program| {
+        Unshadow::default().program(&mut program);
+        program
+    })
+}
+
+fn unshadowed_goal() -> impl Strategy<Value = ast::Goal> {
+    arb_goal().prop_map(|mut goal| {
+        Unshadow::default().goal(&mut goal);
+        goal
+    })
+}
+
+proptest! {
+    // the lowered ir is nameless, so equality of the lowered forms is alpha-equivalence
+    #[test]
+    fn test_print_program_roundtrips(program in unshadowed_program()) {
+        let lowered = lower_ast(&program).unwrap();
+        let printed = print(LogicInterner, &lowered);
+        let reparsed = logic_parse::parse_program(&printed)
+            .map_err(|err| TestCaseError::fail(format!("failed to parse `{}`: {}", printed, err)))?;
+        prop_assert_eq!(lower_ast(&reparsed).unwrap(), lowered, "printed as `{}`", printed);
+    }
+
+    #[test]
+    fn test_print_goal_roundtrips(goal in unshadowed_goal()) {
+        let lowered = logic_ir::lower_goal(&goal).unwrap();
+        let printed = print(LogicInterner, &lowered);
+        let reparsed = logic_parse::parse_goal(&printed)
+            .map_err(|err| TestCaseError::fail(format!("failed to parse `{}`: {}", printed, err)))?;
+        let relowered = logic_ir::lower_goal(&reparsed).unwrap();
+        prop_assert_eq!(relowered, lowered, "printed as `{}`", printed);
+    }
+
+    #[test]
+    fn test_format_program_roundtrips(program in arb_program()) {
+        let formatted = logic_parse::format_program(&program);
+        let reparsed = logic_parse::parse_program(&formatted)
+            .map_err(|err| TestCaseError::fail(format!("failed to parse `{}`: {}", formatted, err)))?;
+        prop_assert_eq!(reparsed, program, "formatted as `{}`", formatted);
+    }
+}
+
+#[test]
+fn test_print_names_bound_variables() {
+    let program = r"
+        for<X, Y> { X: Trait<Y> :- exists<Z> { Z: Trait<X> } }.
+        i32: Trait.
+    ";
+    let ir = lower_ast(&logic_parse::parse_program(program).unwrap()).unwrap();
+    let expected = "for<T, U> { T: Trait<U> :- exists<V> { V: Trait<T> } }.\ni32: Trait.\n";
+    assert_eq!(ir.to_string(), expected);
+}
+
+#[test]
+fn test_print_avoids_capturing_type_names() {
+    let goal = lower_goal!("exists<X> { X: Trait<T> }");
+    assert_eq!(print(LogicInterner, &goal), "exists<U> { U: Trait<T> }");
+}
+
+#[test]
+fn test_print_parenthesizes_connectives() {
+    let goal = lower_goal!("(i32: A; u32: A), (i32: B; if (i32: C) { u32: C, true })");
+    assert_eq!(
+        print(LogicInterner, &goal),
+        "(i32: A; u32: A), (i32: B; if (i32: C) { u32: C, true })"
+    );
+    let goal = lower_goal!("not { i32: A; u32: A }, not { true }");
+    assert_eq!(print(LogicInterner, &goal), "not { i32: A; u32: A }, not { true }");
+}
+
+#[test]
+fn test_print_decls() {
+    let program = r"
+        struct Vec<X> {}
+        struct Ref<A, B> where A: Outlives<B> {}
+        struct Slice<'x, X> where X: 'x {}
+        struct Array<X, const L> {}
+        #[fundamental] #[crate(std)] struct Box<X> {}
+        #[marker] trait Eq<Rhs> where Self: PartialEq<Rhs> {}
+        #[crate(core)] trait Iterator { type Item; type IntoIter; }
+        opaque type Iter<X>: Iterator + Clone = Vec<X>;
+        opaque type Unit = ();
+        impl<X> Clone for Vec<X> where X: Clone {}
+        impl Clone for i32 {}
+        impl<const L> Default for Array<u8, L> {}
+        impl Zero for Array<u8, 0> {}
+        #[crate(std)] default impl<X> Clone for Box<X> where X: Clone {}
+        impl<X> Iterator for Vec<X> { type Item = <X as Iterator>::Item; type IntoIter = X; }
+    ";
+    let ir = lower_ast(&logic_parse::parse_program(program).unwrap()).unwrap();
+    let expected = "\
+struct Vec<T> {}
+struct Ref<T, U> where T: Outlives<U> {}
+struct Slice<'a, T> where T: 'a {}
+struct Array<T, const N> {}
+#[fundamental] #[crate(std)] struct Box<T> {}
+#[marker] trait Eq<T> where Self: PartialEq<T> {}
+#[crate(core)] trait Iterator { type Item; type IntoIter; }
+opaque type Iter<T>: Iterator + Clone = Vec<T>;
+opaque type Unit = ();
+impl<T> Clone for Vec<T> where T: Clone {}
+impl Clone for i32 {}
+impl<const N> Default for Array<u8, N> {}
+impl Zero for Array<u8, 0> {}
+#[crate(std)] default impl<T> Clone for Box<T> where T: Clone {}
+impl<T> Iterator for Vec<T> { type Item = <T as Iterator>::Item; type IntoIter = T; }
+";
+    assert_eq!(ir.to_string(), expected);
+    assert_eq!(lower_ast(&logic_parse::parse_program(expected).unwrap()).unwrap(), ir);
+}
+
+#[test]
+fn test_print_lifetimes() {
+    let goal = lower_goal!("forall<'x, X> { exists<'y> { Ref<'y, X>: Trait<'static>, 'x: 'y } }");
+    assert_eq!(
+        print(LogicInterner, &goal),
+        "forall<'a, T> { exists<'b> { Ref<'b, T>: Trait<'static>, 'a: 'b } }"
+    );
+}
+
+#[test]
+fn test_print_builtin_tys() {
+    let goal = lower_goal!("forall<'x, X, const L> { ((), (X,), &'x mut [X; L], fn(X) -> !): C }");
+    assert_eq!(
+        print(LogicInterner, &goal),
+        "forall<'a, T, const N> { ((), (T,), &'a mut [T; N], fn(T) -> !): C }"
+    );
+    let goal = lower_goal!("exists<X> { &'static [X] = fn() }");
+    assert_eq!(print(LogicInterner, &goal), "exists<T> { &'static [T] = fn() }");
+    let goal = lower_goal!("forall<'x, X> { &'x dyn Iterator<X> + Send = Box<dyn Any> }");
+    assert_eq!(
+        print(LogicInterner, &goal),
+        "forall<'a, T> { &'a dyn Iterator<T> + Send = Box<dyn Any> }"
+    );
+}
+
+#[test]
+fn test_print_opaque_tys() {
+    let goal = lower_goal!("forall<X> { if (Reveal) { Normalize(Iter<X> -> Vec<X>) } }");
+    assert_eq!(
+        print(LogicInterner, &goal),
+        "forall<T> { if (Reveal) { Normalize(Iter<T> -> Vec<T>) } }"
+    );
+}
@@ -0,0 +1,70 @@
+//! folds large types that only have a single variable for the folder to replace,
+//! which shows how much is saved by skipping the subtrees without any compared to a folder
+//! that ignores the flags and walks the whole type
+use criterion::{criterion_group, criterion_main, Criterion};
+use logic_ir::*;
+
+const DEPTH: usize = 12;
+
+fn pair(
+    interner: LogicInterner,
+    lhs: Ty<LogicInterner>,
+    rhs: Ty<LogicInterner>,
+) -> Ty<LogicInterner> {
+    let args = [lhs.into_generic_arg(interner), rhs.into_generic_arg(interner)];
+    TyKind::Structure(Ident::unspanned("Pair"), Subst::intern(interner, args)).intern(interner)
+}
+
+/// a complete binary tree of `Pair`s with `i32` leaves
+fn ground(interner: LogicInterner, depth: usize) -> Ty<LogicInterner> {
+    if depth == 0 {
+        return TyKind::Structure(Ident::unspanned("i32"), Subst::empty(interner)).intern(interner);
+    }
+    pair(interner, ground(interner, depth - 1), ground(interner, depth - 1))
+}
+
+/// substitutes the variables of the innermost binder like `Subst::apply` but without looking
+/// at the flags, so it walks every subtree
+struct FullWalkSubst<'a> {
+    interner: LogicInterner,
+    subst: &'a Subst<LogicInterner>,
+}
+
+impl Folder<LogicInterner> for FullWalkSubst<'_> {
+    fn interner(&self) -> LogicInterner {
+        self.interner
+    }
+
+    fn fold_ty(
+        &mut self,
+        ty: Ty<LogicInterner>,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Ty<LogicInterner>> {
+        match ty.kind(self.interner) {
+            TyKind::Bound(bound) if bound.debruijn == outer_binder =>
+                Ok(self.subst.at(bound.index).assert_ty_ref(self.interner).clone()),
+            _ => ty.fold_inner_with(self, outer_binder),
+        }
+    }
+}
+
+fn bench_fold(c: &mut Criterion) {
+    let interner = LogicInterner;
+    let var = BoundVar::new(DebruijnIdx::ZERO, 0).to_ty(interner);
+    let ty = pair(interner, var, ground(interner, DEPTH));
+    let subst = Subst::intern(interner, [ground(interner, 0).into_generic_arg(interner)]);
+
+    let full_walk = FullWalkSubst { interner, subst: &subst }.fold(ty.clone()).unwrap();
+    assert_eq!(full_walk, subst.apply(interner, ty.clone()));
+
+    let mut group = c.benchmark_group("fold");
+    group.bench_function("subst", |b| b.iter(|| subst.apply(interner, ty.clone())));
+    group.bench_function("subst_full_walk", |b| {
+        b.iter(|| FullWalkSubst { interner, subst: &subst }.fold(ty.clone()).unwrap())
+    });
+    group.bench_function("shift", |b| b.iter(|| ty.clone().shifted_in(interner)));
+    group.finish();
+}
+
+criterion_group!(benches, bench_fold);
+criterion_main!(benches);
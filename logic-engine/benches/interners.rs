@@ -0,0 +1,79 @@
+//! compares the interner that shares nothing with the hash-consing one on large programs
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use logic_engine::{GoalExt, RecursiveSolver};
+use logic_ir::*;
+use logic_parse::ast;
+use std::fmt::Write;
+
+const STRUCTS: usize = 200;
+const DEPTH: usize = 5;
+
+/// `n` structs that are `Clone`, along with `Vec` and `Pair` which are `Clone` if their
+/// arguments are
+fn program(n: usize) -> ast::Program {
+    let mut src = String::from(
+        "trait Clone {}
+        struct Vec<T> {}
+        struct Pair<T, U> {}
+        impl<T> Clone for Vec<T> where T: Clone {}
+        impl<T, U> Clone for Pair<T, U> where T: Clone, U: Clone {}
+        ",
+    );
+    for i in 0..n {
+        writeln!(src, "struct S{} {{}} impl Clone for S{} {{}}", i, i).unwrap();
+    }
+    logic_parse::parse_program(&src).unwrap()
+}
+
+/// a complete binary tree of `Pair`s with `Vec<S{i}>` leaves, so every leaf is a distinct goal
+fn goal(depth: usize) -> ast::Goal {
+    fn ty(depth: usize, leaf: &mut usize) -> String {
+        if depth == 0 {
+            *leaf += 1;
+            return format!("Vec<S{}>", *leaf % STRUCTS);
+        }
+        format!("Pair<{}, {}>", ty(depth - 1, leaf), ty(depth - 1, leaf))
+    }
+    logic_parse::parse_goal(&format!("{}: Clone", ty(depth, &mut 0))).unwrap()
+}
+
+fn solver<I: Interner>(interner: I, program: &ast::Program) -> RecursiveSolver<I> {
+    let program = lower_ast_with(interner, program).unwrap();
+    RecursiveSolver::new(interner, Environment::new(program.program_clauses()))
+}
+
+fn bench_lower(c: &mut Criterion) {
+    let program = program(STRUCTS);
+    let mut group = c.benchmark_group("lower");
+    group.bench_function("LogicInterner", |b| b.iter(|| lower_ast(&program).unwrap()));
+    group.bench_function("ArenaInterner", |b| {
+        b.iter(|| {
+            let arenas = Arenas::default();
+            black_box(lower_ast_with(ArenaInterner::new(&arenas), &program).unwrap());
+        })
+    });
+    group.finish();
+}
+
+fn bench_solve(c: &mut Criterion) {
+    let (program, goal) = (program(STRUCTS), goal(DEPTH));
+    let mut group = c.benchmark_group("solve");
+    group.sample_size(10);
+
+    let solver = solver(LogicInterner, &program);
+    let peeled = lower_goal_in(&program, &goal).unwrap().peel(LogicInterner);
+    assert!(solver.solve(&peeled).unwrap().is_unique());
+    group.bench_function("LogicInterner", |b| b.iter(|| solver.solve(&peeled).unwrap()));
+
+    let arenas = Arenas::default();
+    let interner = ArenaInterner::new(&arenas);
+    let solver = self::solver(interner, &program);
+    let peeled = lower_goal_in_with(interner, &program, &goal).unwrap().peel(interner);
+    assert!(solver.solve(&peeled).unwrap().is_unique());
+    group.bench_function("ArenaInterner", |b| b.iter(|| solver.solve(&peeled).unwrap()));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_lower, bench_solve);
+criterion_main!(benches);
@@ -28,6 +28,14 @@ impl<I: Interner> Zipper<I> for MatchZipper<I> {
         if can_match { Ok(()) } else { Err(LogicError::NoSolution) }
     }
 
+    fn zip_lifetimes(&mut self, a: &Lifetime<I>, b: &Lifetime<I>) -> LogicResult<()> {
+        if a == b { Ok(()) } else { Err(LogicError::NoSolution) }
+    }
+
+    fn zip_consts(&mut self, a: &Const<I>, b: &Const<I>) -> LogicResult<()> {
+        if a == b { Ok(()) } else { Err(LogicError::NoSolution) }
+    }
+
     fn zip_binders<T>(
         &mut self,
         a: &logic_ir::Binders<T>,
@@ -0,0 +1,92 @@
+//! checks that no two impls of a trait apply to the same types
+use crate::infer::{InferCtxt, InferenceTable};
+use crate::RecursiveSolver;
+use logic_ir::*;
+use std::collections::HashSet;
+
+/// Something that proves a trait is implemented, an impl or a clause written by hand, as an
+/// index into the impls or the clauses of the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImplRule {
+    Impl(usize),
+    Clause(usize),
+}
+
+impl<I: Interner> RecursiveSolver<I> {
+    /// The pairs of impls and `Implemented` clauses that overlap, the impls coming first in the
+    /// order of `impls` followed by the clauses in the order of `clauses`, with the earlier
+    /// rule first. Either rule would then prove the same goal, which makes its solution
+    /// ambiguous, unless one of them is an impl that specializes the other.
+    pub fn overlapping_impls(
+        &self,
+        impls: &[ImplDatum<I>],
+        clauses: &[Clause<I>],
+    ) -> Vec<(ImplRule, ImplRule)> {
+        let interner = self.interner;
+        let graph = self.specialization_graph(impls);
+        let impl_rules = impls.iter().enumerate().map(|(i, impl_datum)| {
+            (ImplRule::Impl(i), impl_datum.implemented_implication(interner))
+        });
+        let mut seen = HashSet::new();
+        let clause_rules = clauses.iter().enumerate().filter_map(|(i, clause)| {
+            let ClauseData::Implies(implication) = clause.data(interner);
+            implemented_trait(implication)?;
+            // a clause written twice is only kept once, see `Program::dedup_clauses`
+            let first = seen.insert(implication.alpha_normalized(interner));
+            first.then(|| (ImplRule::Clause(i), implication.clone()))
+        });
+        let rules = impl_rules.chain(clause_rules).collect::<Vec<_>>();
+
+        let mut overlapping = vec![];
+        for (i, (rule_a, a)) in rules.iter().enumerate() {
+            for (rule_b, b) in &rules[i + 1..] {
+                let specialization = match (*rule_a, *rule_b) {
+                    (ImplRule::Impl(i), ImplRule::Impl(j)) =>
+                        graph.specializes(i, j) || graph.specializes(j, i),
+                    _ => false,
+                };
+                if !specialization && self.rules_overlap(a, b) {
+                    overlapping.push((*rule_a, *rule_b));
+                }
+            }
+        }
+        overlapping
+    }
+
+    /// Two rules implementing a trait overlap if their consequents unify and the conditions
+    /// of both can then hold together. An ambiguous solution may or may not hold so it counts
+    /// as overlap. The impls of a marker trait never overlap as it doesn't matter which of
+    /// them is used.
+    fn rules_overlap(&self, a: &Binders<Implication<I>>, b: &Binders<Implication<I>>) -> bool {
+        let interner = self.interner;
+        let trait_name = implemented_trait(a);
+        if trait_name != implemented_trait(b)
+            || trait_name.is_none_or(|name| self.marker_traits.contains(name))
+        {
+            return false;
+        }
+
+        let mut table = InferenceTable::new(interner);
+        let a = table.instantiate(a.clone());
+        let b = table.instantiate(b.clone());
+        let mut infcx = InferCtxt::new(self, table, Subst::empty(interner));
+        let environment = Environment::empty(interner);
+        if infcx.unify(&environment, &a.consequent, &b.consequent).is_err() {
+            return false;
+        }
+        for condition in [a.condition, b.condition] {
+            if infcx.add_goal(&environment, condition).is_err() {
+                return false;
+            }
+        }
+        infcx.solve().is_ok()
+    }
+}
+
+/// the trait that the implication proves to be implemented, if any
+fn implemented_trait<I: Interner>(implication: &Binders<Implication<I>>) -> Option<&Ident> {
+    match &implication.skip_binders().consequent {
+        DomainGoal::Holds(Constraint::Implemented(header)) => Some(&header.trait_ref.trait_name),
+        _ => None,
+    }
+}
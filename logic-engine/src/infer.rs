@@ -8,7 +8,9 @@ use logic_ir::*;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 enum Obligation<I: Interner> {
-    Prove(Goal<I>),
+    Prove(InEnvironment<Goal<I>>),
+    /// the goal must have no solution
+    Refute(InEnvironment<Goal<I>>),
 }
 
 #[derive(Debug)]
@@ -17,6 +19,8 @@ pub struct InferCtxt<'a, I: Interner> {
     obligations: Vec<Obligation<I>>,
     solver: &'a RecursiveSolver<I>,
     subst: Subst<I>,
+    /// the region constraints that came up while solving, in terms of the inference variables
+    constraints: Vec<Constraint<I>>,
 }
 
 impl<'a, I: Interner> Deref for InferCtxt<'a, I> {
@@ -38,38 +42,182 @@ pub struct InferenceTable<I: Interner> {
     pub interner: I,
     pub(super) unify: ena::unify::InPlaceUnificationTable<InferVar<I>>,
     vars: Vec<InferVar<I>>,
+    /// the innermost universe that has been created
+    max_universe: UniverseIdx,
 }
 
 impl<I: Interner> InferenceTable<I> {
     pub fn new(interner: I) -> Self {
-        Self { interner, unify: Default::default(), vars: Default::default() }
+        Self {
+            interner,
+            unify: Default::default(),
+            vars: Default::default(),
+            max_universe: UniverseIdx::ROOT,
+        }
     }
 
     pub(super) fn new_infer_var(&mut self) -> InferVar<I> {
-        self.unify.new_key(InferenceValue::Unknown)
+        self.new_infer_var_in(self.max_universe)
+    }
+
+    pub(super) fn new_infer_var_in(&mut self, universe: UniverseIdx) -> InferVar<I> {
+        self.unify.new_key(InferenceValue::Unknown(universe, None))
+    }
+
+    /// a new inference variable for a variable of the given kind, a const keeps its type
+    pub(super) fn new_infer_var_of(
+        &mut self,
+        kind: VariableKind,
+        universe: UniverseIdx,
+    ) -> InferVar<I> {
+        let const_ty = match kind {
+            VariableKind::Const(const_ty) => const_ty,
+            VariableKind::Ty | VariableKind::Lifetime => None,
+        };
+        self.unify.new_key(InferenceValue::Unknown(universe, const_ty))
+    }
+
+    pub(super) fn new_universe(&mut self) -> UniverseIdx {
+        self.max_universe = self.max_universe.next();
+        self.max_universe
+    }
+
+    pub fn probe_var(&mut self, infer: InferVar<I>) -> Option<GenericArg<I>> {
+        match self.unify.probe_value(infer) {
+            InferenceValue::Known(arg) => Some(arg),
+            InferenceValue::Unknown(..) => None,
+        }
+    }
+
+    pub fn probe_ty(&mut self, infer: InferVar<I>) -> Option<Ty<I>> {
+        let interner = self.interner;
+        self.probe_var(infer).map(|arg| arg.assert_ty_ref(interner).clone())
     }
 
-    pub fn probe_var(&mut self, infer: InferVar<I>) -> Option<Ty<I>> {
+    pub fn probe_lifetime(&mut self, infer: InferVar<I>) -> Option<Lifetime<I>> {
+        let interner = self.interner;
+        self.probe_var(infer).map(|arg| arg.assert_lifetime_ref(interner).clone())
+    }
+
+    pub fn probe_const(&mut self, infer: InferVar<I>) -> Option<Const<I>> {
+        let interner = self.interner;
+        self.probe_var(infer).map(|arg| arg.assert_const_ref(interner).clone())
+    }
+
+    /// the universe of an inference variable that is not yet known
+    pub(super) fn universe_of_unbound_var(&mut self, infer: InferVar<I>) -> UniverseIdx {
         match self.unify.probe_value(infer) {
-            InferenceValue::Known(ty) => Some(ty),
-            InferenceValue::Unknown => None,
+            InferenceValue::Unknown(universe, _) => universe,
+            InferenceValue::Known(..) => panic!("`{:?}` is already known", infer),
         }
     }
 
+    /// the type of a const inference variable that is not yet known, if it has one
+    pub(super) fn const_ty_of_unbound_var(&mut self, infer: InferVar<I>) -> Option<ConstTy> {
+        match self.unify.probe_value(infer) {
+            InferenceValue::Unknown(_, const_ty) => const_ty,
+            InferenceValue::Known(..) => panic!("`{:?}` is already known", infer),
+        }
+    }
+
+    /// replaces `ty` by its value while it is a known inference variable
+    pub fn normalize_shallow(&mut self, ty: &Ty<I>) -> Ty<I> {
+        let mut ty = ty.clone();
+        while let &TyKind::Infer(var) = ty.kind(self.interner) {
+            match self.probe_ty(var) {
+                Some(value) => ty = value,
+                None => break,
+            }
+        }
+        ty
+    }
+
+    /// replaces `lifetime` by its value while it is a known inference variable
+    pub fn normalize_lifetime_shallow(&mut self, lifetime: &Lifetime<I>) -> Lifetime<I> {
+        let mut lifetime = lifetime.clone();
+        while let &LifetimeData::Infer(var) = lifetime.data(self.interner) {
+            match self.probe_lifetime(var) {
+                Some(value) => lifetime = value,
+                None => break,
+            }
+        }
+        lifetime
+    }
+
+    /// replaces `constant` by its value while it is a known inference variable
+    pub fn normalize_const_shallow(&mut self, constant: &Const<I>) -> Const<I> {
+        let mut constant = constant.clone();
+        while let &ConstData::Infer(var) = constant.data(self.interner) {
+            match self.probe_const(var) {
+                Some(value) => constant = value,
+                None => break,
+            }
+        }
+        constant
+    }
+
     pub fn from_canonical<T>(interner: I, canonical: Canonical<T>) -> (Self, Subst<I>, T)
     where
         T: Fold<I, Folded = T>,
         T::Folded: HasInterner<Interner = I>,
     {
         let mut table = Self::new(interner);
+        // the universes that are named in the value must exist in the new table too
+        let max_universe = canonical.binders.iter().map(|var| var.universe);
+        let mut max_placeholder = MaxPlaceholderUniverse { interner, max: UniverseIdx::ROOT };
+        let value = max_placeholder.fold(canonical.value).unwrap();
+        table.max_universe = max_universe.chain(Some(max_placeholder.max)).max().unwrap();
 
         let fresh_subst = table.fresh_subst(canonical.binders.as_slice());
-        let value = fresh_subst.apply(interner, canonical.value);
+        let value = fresh_subst.apply(interner, value);
 
         (table, fresh_subst, value)
     }
 }
 
+struct MaxPlaceholderUniverse<I: Interner> {
+    interner: I,
+    max: UniverseIdx,
+}
+
+impl<I: Interner> Folder<I> for MaxPlaceholderUniverse<I> {
+    fn interner(&self) -> I {
+        self.interner
+    }
+
+    fn fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIdx) -> LogicResult<Ty<I>> {
+        if !ty.flags(self.interner).has_placeholder {
+            return Ok(ty);
+        }
+        if let TyKind::Placeholder(placeholder) = ty.kind(self.interner) {
+            self.max = self.max.max(placeholder.universe);
+        }
+        ty.fold_inner_with(self, outer_binder)
+    }
+
+    fn fold_lifetime(
+        &mut self,
+        lifetime: Lifetime<I>,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Lifetime<I>> {
+        if let LifetimeData::Placeholder(placeholder) = lifetime.data(self.interner) {
+            self.max = self.max.max(placeholder.universe);
+        }
+        lifetime.fold_inner_with(self, outer_binder)
+    }
+
+    fn fold_const(
+        &mut self,
+        constant: Const<I>,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Const<I>> {
+        if let ConstData::Placeholder(placeholder) = constant.data(self.interner) {
+            self.max = self.max.max(placeholder.universe);
+        }
+        constant.fold_inner_with(self, outer_binder)
+    }
+}
+
 pub struct InferCtxtSnapshot<I: Interner> {
     table_snapshot: ena::unify::Snapshot<ena::unify::InPlace<InferVar<I>>>,
 }
@@ -80,19 +228,24 @@ struct PositiveSolution<I: Interner> {
 }
 
 impl<'a, I: Interner> InferCtxt<'a, I> {
+    pub fn new(solver: &'a RecursiveSolver<I>, table: InferenceTable<I>, subst: Subst<I>) -> Self {
+        Self { solver, subst, table, obligations: vec![], constraints: vec![] }
+    }
+
     pub fn from_implication(
         solver: &'a RecursiveSolver<I>,
         table: InferenceTable<I>,
         subst: Subst<I>,
-        domain_goal: DomainGoal<I>,
+        domain_goal: InEnvironment<DomainGoal<I>>,
         implication: Binders<Implication<I>>,
     ) -> LogicResult<Self> {
-        let mut infcx = Self { solver, subst, table, obligations: vec![] };
+        let InEnvironment { environment, goal: domain_goal } = domain_goal;
+        let mut infcx = Self::new(solver, table, subst);
         debug!(implication = ?implication);
         let instantiated = infcx.instantiate(implication);
         debug!(instantiated = ?instantiated);
         debug!("try unify `{:?}` with `{:?}`", instantiated.consequent, domain_goal);
-        match infcx.unify(&domain_goal, &instantiated.consequent) {
+        match infcx.unify(&environment, &domain_goal, &instantiated.consequent) {
             Ok(()) => {
                 debug!("unified successfully");
             }
@@ -101,7 +254,7 @@ impl<'a, I: Interner> InferCtxt<'a, I> {
                 return Err(err);
             }
         }
-        infcx.add_goal(instantiated.condition)?;
+        infcx.add_goal(&environment, instantiated.condition)?;
         Ok(infcx)
     }
 
@@ -109,35 +262,64 @@ impl<'a, I: Interner> InferCtxt<'a, I> {
         solver: &'a RecursiveSolver<I>,
         table: InferenceTable<I>,
         subst: Subst<I>,
-        goal: Goal<I>,
+        goal: InEnvironment<Goal<I>>,
     ) -> LogicResult<Self> {
-        let mut infcx = Self { solver, subst, table, obligations: vec![] };
-        infcx.add_goal(goal)?;
+        let mut infcx = Self::new(solver, table, subst);
+        infcx.add_goal(&goal.environment, goal.goal)?;
         Ok(infcx)
     }
 
+    /// Proves the obligations until none remain, or until none of the remaining obligations
+    /// make progress. Ambiguous obligations are retried after the others as the inference
+    /// variables they constrain may since have been resolved.
     pub fn solve(mut self) -> LogicResult<Solution<I>> {
-        while let Some(obligation) = self.obligations.pop() {
-            debug!(remaining_obligation = ?self.obligations);
-            debug!(obligation = ?obligation);
-            match obligation {
-                Obligation::Prove(goal) => {
-                    let PositiveSolution { original_vars, solution } = self.prove(goal)?;
-                    match solution {
-                        Solution::Unique(subst) => self.apply_solution(original_vars, subst)?,
-                        Solution::Ambiguous => todo!(),
+        loop {
+            let mut progress = false;
+            let mut ambiguous = vec![];
+            while let Some(obligation) = self.obligations.pop() {
+                debug!(remaining_obligation = ?self.obligations);
+                debug!(obligation = ?obligation);
+                match obligation {
+                    Obligation::Prove(goal) => {
+                        let PositiveSolution { original_vars, solution } =
+                            self.prove(goal.clone())?;
+                        match solution {
+                            Solution::Unique(subst) => {
+                                self.apply_solution(&goal.environment, original_vars, subst)?;
+                                progress = true;
+                            }
+                            Solution::Ambiguous => ambiguous.push(Obligation::Prove(goal)),
+                        }
                     }
-                }
-            };
+                    Obligation::Refute(goal) =>
+                        if self.refute(goal.clone())? {
+                            progress = true;
+                        } else {
+                            ambiguous.push(Obligation::Refute(goal));
+                        },
+                };
+            }
+
+            if ambiguous.is_empty() {
+                break;
+            }
+            if !progress {
+                debug!(ambiguous_obligations = ?ambiguous);
+                return Ok(Solution::Ambiguous);
+            }
+            // keep the original order, the obligations are popped from the end
+            ambiguous.reverse();
+            self.obligations = ambiguous;
         }
 
-        let subst = self.subst.clone();
-        let canonical_subst = self.canonicalize(subst);
+        let constraints = std::mem::take(&mut self.constraints);
+        let constrained_subst = ConstrainedSubst { subst: self.subst.clone(), constraints };
+        let canonical_subst = self.canonicalize(constrained_subst);
         debug!(solution_subst = ?canonical_subst);
         Ok(Solution::Unique(canonical_subst))
     }
 
-    fn prove(&mut self, subgoal: Goal<I>) -> LogicResult<PositiveSolution<I>> {
+    fn prove(&mut self, subgoal: InEnvironment<Goal<I>>) -> LogicResult<PositiveSolution<I>> {
         debug!(subgoal = ?subgoal);
         let Canonicalized { canonical, original_vars } = self.canonicalized(subgoal);
         let solution = self.solver.solve(&canonical)?;
@@ -145,38 +327,117 @@ impl<'a, I: Interner> InferCtxt<'a, I> {
         Ok(PositiveSolution { solution, original_vars })
     }
 
+    /// Negation as failure, returns `Ok(true)` if the goal has no solution, `Ok(false)` if that
+    /// is unknown, and an error if the goal holds. Only a ground goal can be refuted, a goal
+    /// with inference variables may hold for some of their values and not others, and a goal
+    /// with placeholders may hold for some of the types they stand for, so these flounder.
+    /// So does a goal whose solution depends on a goal it is nested in, i.e. in a cycle.
+    fn refute(&mut self, subgoal: InEnvironment<Goal<I>>) -> LogicResult<bool> {
+        debug!(refuted_subgoal = ?subgoal);
+        let Canonicalized { canonical, .. } = self.canonicalized(subgoal);
+        let mut max_placeholder =
+            MaxPlaceholderUniverse { interner: self.interner, max: UniverseIdx::ROOT };
+        max_placeholder.fold(canonical.value.goal.clone()).unwrap();
+        if !canonical.binders.is_empty() || max_placeholder.max != UniverseIdx::ROOT {
+            debug!("negated goal flounders");
+            return Ok(false);
+        }
+        match self.solver.solve_isolated(&canonical) {
+            Some(Err(LogicError::NoSolution)) => Ok(true),
+            // a goal that only holds subject to region constraints may not hold after all
+            Some(Ok(Solution::Unique(subst))) if subst.value.constraints.is_empty() =>
+                Err(LogicError::NoSolution),
+            Some(Ok(..)) | None => Ok(false),
+        }
+    }
+
     // add and simplify goal
-    fn add_goal(&mut self, goal: Goal<I>) -> LogicResult<()> {
+    pub(crate) fn add_goal(
+        &mut self,
+        environment: &Environment<I>,
+        goal: Goal<I>,
+    ) -> LogicResult<()> {
         match goal.data(self.interner) {
-            GoalData::DomainGoal(..) => self.obligations.push(Obligation::Prove(goal)),
+            GoalData::DomainGoal(DomainGoal::Holds(Constraint::Eq(EqConstraint { lhs, rhs }))) =>
+                self.unify(environment, lhs, rhs)?,
+            GoalData::DomainGoal(DomainGoal::Holds(constraint))
+                if constraint.is_region_constraint() =>
+                if !self.is_assumed(environment, constraint) {
+                    self.add_region_constraint(constraint.clone())
+                },
+            GoalData::DomainGoal(..) => self
+                .obligations
+                .push(Obligation::Prove(InEnvironment::new(environment.clone(), goal))),
             GoalData::Quantified(Quantifier::Exists, quantified_goal) => {
                 let instantiated_goal = self.instantiate(quantified_goal.clone());
-                self.add_goal(instantiated_goal)?;
+                self.add_goal(environment, instantiated_goal)?;
+            }
+            GoalData::Quantified(Quantifier::ForAll, quantified_goal) => {
+                let instantiated_goal = self.instantiate_universally(quantified_goal.clone());
+                self.add_goal(environment, instantiated_goal)?;
             }
-            GoalData::Quantified(Quantifier::ForAll, _) => todo!(),
             GoalData::And(lhs, rhs) => {
-                self.add_goal(lhs.clone())?;
-                self.add_goal(rhs.clone())?;
+                self.add_goal(environment, lhs.clone())?;
+                self.add_goal(environment, rhs.clone())?;
             }
-            GoalData::Implies(_, _) => todo!(),
-            GoalData::Or(_, _) => todo!(),
+            // the hypothesis is assumed as written, and as `FromEnv` so that the bounds it
+            // implies can be used too, which only the traits that are declared have clauses for
+            GoalData::Implies(clause, goal) => {
+                let mut hypotheses = vec![clause.clone()];
+                let from_env = clause.clone().into_from_env_clause(self.interner);
+                if &from_env != clause {
+                    hypotheses.push(from_env);
+                }
+                let environment = environment.add_clauses(self.interner, hypotheses);
+                self.add_goal(&environment, goal.clone())?;
+            }
+            GoalData::Not(goal) => self
+                .obligations
+                .push(Obligation::Refute(InEnvironment::new(environment.clone(), goal.clone()))),
+            // the branches may constrain the inference variables differently, so the disjunction
+            // is solved as a whole once they are known as well as they can be
+            GoalData::Or(..) => self
+                .obligations
+                .push(Obligation::Prove(InEnvironment::new(environment.clone(), goal))),
             GoalData::True => {}
         };
         Ok(())
     }
 
+    /// whether a hypothesis assumes the region constraint once the inference variables within
+    /// both are replaced by their values
+    fn is_assumed(&mut self, environment: &Environment<I>, constraint: &Constraint<I>) -> bool {
+        let goal = DomainGoal::Holds(constraint.clone());
+        let canonical = self.canonicalize(InEnvironment::new(environment.clone(), goal));
+        match canonical.value {
+            InEnvironment { environment, goal: DomainGoal::Holds(constraint) } =>
+                environment.assumes(self.interner, &constraint),
+            _ => unreachable!(),
+        }
+    }
+
+    /// the solver doesn't decide region constraints, they are returned with the solution
+    fn add_region_constraint(&mut self, constraint: Constraint<I>) {
+        if !self.constraints.contains(&constraint) {
+            self.constraints.push(constraint);
+        }
+    }
+
     fn apply_solution(
         &mut self,
+        environment: &Environment<I>,
         original_vars: Vec<InferVar<I>>,
-        canonical_subst: Canonical<Subst<I>>,
+        canonical_subst: Canonical<ConstrainedSubst<I>>,
     ) -> LogicResult<()> {
-        assert!(
-            canonical_subst.binders.is_empty(),
-            "not sure when this will occur, panic for curiosity"
-        );
-        let subst = self.instantiate_canonical(canonical_subst);
-        for (var, ty) in original_vars.into_iter().zip(subst.as_slice()) {
-            self.unify.unify_var_value(var, InferenceValue::Known(ty.clone())).unwrap();
+        // the subst has binders if the solution leaves some variables unconstrained,
+        // these are instantiated with fresh inference variables
+        let ConstrainedSubst { subst, constraints } = self.instantiate_canonical(canonical_subst);
+        for (var, arg) in original_vars.into_iter().zip(subst.as_slice()) {
+            let var = var.to_generic_arg(self.interner, arg.kind(self.interner));
+            self.unify(environment, &var, arg)?;
+        }
+        for constraint in constraints {
+            self.add_region_constraint(constraint);
         }
         Ok(())
     }
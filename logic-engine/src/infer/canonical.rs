@@ -12,7 +12,7 @@ impl<I: Interner> InferenceTable<I> {
         T::Folded: HasInterner<Interner = I>,
     {
         let mut canonicalizer = Canonicalizer::new(self);
-        let canonicalized = value.fold_with(&mut canonicalizer).unwrap();
+        let canonicalized = canonicalizer.fold(value).unwrap();
         let binders = canonicalizer.binders();
         let canonical = Canonical { value: canonicalized, binders };
         Canonicalized { canonical, original_vars: canonicalizer.original_vars }
@@ -31,23 +31,39 @@ impl<I: Interner> InferenceTable<I> {
 pub struct Canonicalizer<'a, I: Interner> {
     table: &'a mut InferenceTable<I>,
     original_vars: Vec<InferVar<I>>,
+    /// the kind of each of the original variables
+    kinds: Vec<VariableKind>,
 }
 
 impl<'a, I: Interner> Canonicalizer<'a, I> {
     pub fn new(table: &'a mut InferenceTable<I>) -> Self {
-        Self { table, original_vars: Default::default() }
+        Self { table, original_vars: Default::default(), kinds: Default::default() }
     }
 
-    fn binders(&self) -> Variables<I> {
-        Variables::intern(self.interner(), self.original_vars.iter().map(|_| Variable::new()))
+    fn binders(&mut self) -> Variables<I> {
+        let interner = self.interner();
+        let table = &mut self.table;
+        let vars = self.original_vars.iter().zip(&self.kinds).map(|(&var, &kind)| {
+            Variable::in_universe(kind, table.universe_of_unbound_var(var))
+        });
+        Variables::intern(interner, vars.collect::<Vec<_>>())
     }
 
-    fn add_var(&mut self, var: InferVar<I>) -> usize {
-        self.original_vars.iter().position(|v| v == &var).unwrap_or_else(|| {
-            let next = self.original_vars.len();
-            self.original_vars.push(var);
-            next
-        })
+    /// the bound variable that replaces the unbound inference variable, which is bound by the
+    /// binder of the canonical value outside of the `outer_binder` binders within it
+    fn bound_var(
+        &mut self,
+        var: InferVar<I>,
+        kind: VariableKind,
+        outer_binder: DebruijnIdx,
+    ) -> BoundVar {
+        let root = self.table.unify.find(var);
+        let index = self.original_vars.iter().position(|v| v == &root).unwrap_or_else(|| {
+            self.original_vars.push(root);
+            self.kinds.push(kind);
+            self.original_vars.len() - 1
+        });
+        BoundVar::new(outer_binder, index)
     }
 }
 
@@ -57,13 +73,52 @@ impl<'a, I: Interner> Folder<I> for Canonicalizer<'a, I> {
         self.table.interner
     }
 
-    fn fold_infer_var(&mut self, infer: InferVar<I>) -> LogicResult<Ty<I>> {
-        match self.table.probe_var(infer) {
-            Some(ty) => ty.fold_with(self),
+    fn fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIdx) -> LogicResult<Ty<I>> {
+        if !ty.flags(self.interner()).has_infer {
+            return Ok(ty);
+        }
+        ty.fold_inner_with(self, outer_binder)
+    }
+
+    fn fold_infer_var(
+        &mut self,
+        infer: InferVar<I>,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Ty<I>> {
+        match self.table.probe_ty(infer) {
+            Some(ty) => ty.fold_with(self, outer_binder),
+            None => {
+                let bound = self.bound_var(infer, VariableKind::Ty, outer_binder);
+                Ok(bound.to_ty(self.interner()))
+            }
+        }
+    }
+
+    fn fold_infer_lifetime_var(
+        &mut self,
+        infer: InferVar<I>,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Lifetime<I>> {
+        match self.table.probe_lifetime(infer) {
+            Some(lifetime) => lifetime.fold_with(self, outer_binder),
+            None => {
+                let bound = self.bound_var(infer, VariableKind::Lifetime, outer_binder);
+                Ok(bound.to_lifetime(self.interner()))
+            }
+        }
+    }
+
+    fn fold_infer_const_var(
+        &mut self,
+        infer: InferVar<I>,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Const<I>> {
+        match self.table.probe_const(infer) {
+            Some(constant) => constant.fold_with(self, outer_binder),
             None => {
-                let root = self.table.unify.find(infer);
-                let index = self.add_var(root);
-                Ok(BoundVar::new(DebruijnIdx::ZERO, index).to_ty(self.interner()))
+                let kind = VariableKind::Const(self.table.const_ty_of_unbound_var(infer));
+                let bound = self.bound_var(infer, kind, outer_binder);
+                Ok(bound.to_const(self.interner()))
             }
         }
     }
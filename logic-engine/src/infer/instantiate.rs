@@ -2,22 +2,19 @@ use super::InferenceTable;
 use logic_ir::*;
 
 impl<I: Interner> InferenceTable<I> {
+    /// fresh inference variables for the variables of a `Canonical`, in their original universes
     pub(super) fn fresh_subst(&mut self, binders: &[Variable<I>]) -> Subst<I> {
         let interner = self.interner;
         Subst::intern(
             interner,
-            binders.iter().map(|_| TyKind::Infer(self.new_infer_var()).intern(interner)),
+            binders.iter().map(|var| {
+                // the universe may not exist here if the variable was created by a nested solve
+                let universe = var.universe.min(self.max_universe);
+                self.new_infer_var_of(var.kind, universe).to_generic_arg(interner, var.kind)
+            }),
         )
     }
 
-    fn instantiate_binders<T>(&mut self, binders: Variables<I>, value: T) -> T::Folded
-    where
-        T: Fold<I>,
-    {
-        let subst = self.fresh_subst(binders.as_slice());
-        subst.apply(self.interner, value)
-    }
-
     pub fn instantiate_canonical<T: HasInterner<Interner = I>>(
         &mut self,
         canonical: Canonical<T>,
@@ -26,7 +23,8 @@ impl<I: Interner> InferenceTable<I> {
         T: Fold<I> + HasInterner<Interner = I>,
     {
         let Canonical { binders, value } = canonical;
-        self.instantiate_binders(binders, value)
+        let subst = self.fresh_subst(binders.as_slice());
+        subst.apply(self.interner, value)
     }
 
     /// instantiate bound value existentially
@@ -34,7 +32,26 @@ impl<I: Interner> InferenceTable<I> {
     where
         T: Fold<I> + HasInterner<Interner = I>,
     {
+        let interner = self.interner;
+        let (binders, value) = bound.split();
+        let universe = self.max_universe;
+        let vars = binders.iter().map(|var| {
+            self.new_infer_var_of(var.kind, universe).to_generic_arg(interner, var.kind)
+        });
+        Subst::intern(interner, vars).apply(interner, value)
+    }
+
+    /// instantiate bound value universally, each variable becomes a placeholder in a new universe
+    pub fn instantiate_universally<T>(&mut self, bound: Binders<T>) -> T::Folded
+    where
+        T: Fold<I> + HasInterner<Interner = I>,
+    {
+        let interner = self.interner;
+        let universe = self.new_universe();
         let (binders, value) = bound.split();
-        self.instantiate_binders(binders, value)
+        let placeholders = binders.iter().enumerate().map(|(index, var)| {
+            PlaceholderVar { universe, index }.to_generic_arg(interner, var.kind)
+        });
+        Subst::intern(interner, placeholders).apply(interner, value)
     }
 }
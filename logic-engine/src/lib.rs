@@ -1,8 +1,10 @@
 mod can_unify;
+mod coherence;
 pub mod db;
 mod infer;
 mod peel;
 mod solve;
+mod specialization;
 mod substs;
 mod unify;
 
@@ -13,5 +15,7 @@ extern crate logic_ir;
 extern crate tracing;
 
 pub use can_unify::*;
+pub use coherence::ImplRule;
 pub use peel::GoalExt;
 pub use solve::*;
+pub use specialization::SpecializationGraph;
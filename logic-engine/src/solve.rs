@@ -1,76 +1,243 @@
 use crate::infer::{InferCtxt, InferenceTable};
+use crate::SpecializationGraph;
 use logic_ir::*;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 
 #[derive(Debug)]
 pub struct RecursiveSolver<I: Interner> {
     pub interner: I,
     pub env: Environment<I>,
+    /// the goals currently being solved, outermost first
+    stack: RefCell<Vec<StackEntry<I>>>,
+    /// the current approximation of the solution of each goal that is the head of a cycle
+    provisional: RefCell<HashMap<Canonical<InEnvironment<Goal<I>>>, SolutionResult<I>>>,
+    /// the shallowest depth of the stack that a cycle has reached back to,
+    /// see [`RecursiveSolver::solve_isolated`]
+    cycle_depth: Cell<usize>,
+    pub(crate) specialization_graph: SpecializationGraph<I>,
+    /// the traits whose impls may overlap, any one of them proves a goal
+    pub(crate) marker_traits: Vec<Ident>,
+}
+
+#[derive(Debug)]
+struct StackEntry<I: Interner> {
+    goal: Canonical<InEnvironment<Goal<I>>>,
+    /// whether the goal was required (directly or indirectly) to solve itself
+    cycle: bool,
 }
 
 type SolutionResult<I> = LogicResult<Solution<I>>;
 
 impl<I: Interner> RecursiveSolver<I> {
     pub fn new(interner: I, env: Environment<I>) -> Self {
-        Self { interner, env }
+        Self {
+            interner,
+            env,
+            stack: Default::default(),
+            provisional: Default::default(),
+            cycle_depth: Cell::new(usize::MAX),
+            specialization_graph: SpecializationGraph::empty(),
+            marker_traits: vec![],
+        }
     }
 
-    pub fn solve(&self, canonical_goal: &Canonical<Goal<I>>) -> SolutionResult<I> {
+    pub fn with_marker_traits(mut self, marker_traits: Vec<Ident>) -> Self {
+        self.marker_traits = marker_traits;
+        self
+    }
+
+    fn is_marker_goal(&self, domain_goal: &DomainGoal<I>) -> bool {
+        match domain_goal {
+            DomainGoal::Holds(Constraint::Implemented(ImplConstraint { trait_ref, .. })) =>
+                self.marker_traits.contains(&trait_ref.trait_name),
+            _ => false,
+        }
+    }
+
+    /// Solves the goal, recursive goals are solved by iterating to a fixed point.
+    /// A goal that is encountered again while solving it uses the provisional solution
+    /// (initially no solution), and then the goal is re-solved until the solution stops changing.
+    pub fn solve(&self, canonical_goal: &Canonical<InEnvironment<Goal<I>>>) -> SolutionResult<I> {
         debug!(canonical_goal = ?canonical_goal);
-        let Canonical { value: goal, binders } = canonical_goal.clone();
+        let mut stack = self.stack.borrow_mut();
+        if let Some(depth) = stack.iter().position(|entry| &entry.goal == canonical_goal) {
+            debug!("cycle detected");
+            stack[depth].cycle = true;
+            self.cycle_depth.set(self.cycle_depth.get().min(depth));
+            let provisional = self.provisional.borrow().get(canonical_goal).cloned();
+            return provisional.unwrap_or(Err(LogicError::NoSolution));
+        }
+        stack.push(StackEntry { goal: canonical_goal.clone(), cycle: false });
+        drop(stack);
+
+        let mut provisional = Err(LogicError::NoSolution);
+        let solution = loop {
+            let solution = self.solve_new(canonical_goal);
+            let cycle = std::mem::take(&mut self.stack.borrow_mut().last_mut().unwrap().cycle);
+            if !cycle {
+                break solution;
+            }
+
+            // the solution only ever moves up the chain `NoSolution -> Unique -> Ambiguous`
+            // so this always terminates
+            let next = match (&provisional, solution) {
+                (Err(..), solution) => solution,
+                (Ok(prev), Ok(solution)) if prev == &solution => Ok(solution),
+                (Ok(..), _) => Ok(Solution::Ambiguous),
+            };
+            if next == provisional {
+                break next;
+            }
+            debug!(provisional_solution = ?next);
+            self.provisional.borrow_mut().insert(canonical_goal.clone(), next.clone());
+            provisional = next;
+        };
+
+        self.stack.borrow_mut().pop();
+        self.provisional.borrow_mut().remove(canonical_goal);
+        solution
+    }
+
+    /// Solves the goal, or returns `None` if its solution depends on a goal that is still being
+    /// solved. The provisional solution of such a goal may still change, which is fine for
+    /// goals that only use it positively as they are re-solved until it stops changing,
+    /// but the solution can't be relied on by a negation which would then be unsound.
+    pub fn solve_isolated(
+        &self,
+        canonical_goal: &Canonical<InEnvironment<Goal<I>>>,
+    ) -> Option<SolutionResult<I>> {
+        let depth = self.stack.borrow().len();
+        let outer = self.cycle_depth.replace(usize::MAX);
+        let solution = self.solve(canonical_goal);
+        let cycle_depth = self.cycle_depth.get();
+        self.cycle_depth.set(outer.min(cycle_depth));
+        (cycle_depth >= depth).then_some(solution)
+    }
+
+    fn solve_new(&self, canonical_goal: &Canonical<InEnvironment<Goal<I>>>) -> SolutionResult<I> {
+        let Canonical { value: InEnvironment { environment, goal }, binders } =
+            canonical_goal.clone();
         match goal.data(self.interner) {
+            GoalData::DomainGoal(DomainGoal::Holds(constraint))
+                if matches!(constraint, Constraint::Eq(..)) || constraint.is_region_constraint() =>
+                self.simplify(canonical_goal),
             GoalData::DomainGoal(domain_goal) => {
-                let canonical_domain_goal = Canonical { binders, value: domain_goal.clone() };
-                self.solve_from_clauses(&canonical_domain_goal)
+                let value = InEnvironment::new(environment, domain_goal.clone());
+                self.solve_from_clauses(&Canonical { binders, value })
+            }
+            GoalData::Or(lhs, rhs) => {
+                let branch = |goal: &Goal<I>| Canonical {
+                    binders: binders.clone(),
+                    value: InEnvironment::new(environment.clone(), goal.clone()),
+                };
+                self.solve_disjunction(&branch(lhs), &branch(rhs))
             }
             _ => self.simplify(canonical_goal),
         }
     }
 
+    /// A disjunction holds if either branch does, like a goal with a clause for each branch.
+    /// The branches share the variables of the goal, so their solutions can be combined.
+    fn solve_disjunction(
+        &self,
+        lhs: &Canonical<InEnvironment<Goal<I>>>,
+        rhs: &Canonical<InEnvironment<Goal<I>>>,
+    ) -> SolutionResult<I> {
+        match (self.solve(lhs), self.solve(rhs)) {
+            (Ok(lhs), Ok(rhs)) => Ok(lhs.combine(rhs)),
+            (Ok(solution), Err(..)) | (Err(..), Ok(solution)) => Ok(solution),
+            (Err(err), Err(..)) => Err(err),
+        }
+    }
+
     pub fn solve_from_clauses(
         &self,
-        canonical_domain_goal: &Canonical<DomainGoal<I>>,
+        canonical_domain_goal: &Canonical<InEnvironment<DomainGoal<I>>>,
     ) -> SolutionResult<I> {
         let interner = self.interner;
         let mut current_solution: Option<Solution<I>> = None;
-        for clause in &self.env.clauses {
+        let combine = |current_solution: Option<Solution<I>>, solution| match current_solution {
+            Some(curr_sol) if self.is_marker_goal(&canonical_domain_goal.value.goal) =>
+                Solution::combine_marker(curr_sol, solution),
+            Some(curr_sol) => curr_sol.combine(solution),
+            None => solution,
+        };
+        let program_clauses = self.env.clauses.as_slice();
+        let hypotheses = canonical_domain_goal.value.environment.clauses.len();
+        let specialized = self.specialized_clauses(canonical_domain_goal);
+        for i in 0..program_clauses.len() + hypotheses {
+            if program_clauses.get(i).is_some_and(|clause| specialized.contains(clause)) {
+                continue;
+            }
+            let (infer, subst, goal) =
+                InferenceTable::from_canonical(interner, canonical_domain_goal.clone());
+            // the hypotheses are taken from the instantiated goal so that they refer to the
+            // same inference variables
+            let clause = match i.checked_sub(program_clauses.len()) {
+                None => program_clauses[i].clone(),
+                Some(i) => goal.environment.clauses.as_slice()[i].clone(),
+            };
             match clause.data(interner) {
                 ClauseData::Implies(implication) => {
-                    // println!("can unify {:?} and {:?}", goal, consequent);
-                    // if !goal.can_unify(self.interner, consequent) {
-                    //     println!("no");
-                    //     continue;
-                    // }
-
-                    // temporary stuff to make stuff compile
-
-                    let (infer, subst, goal) =
-                        InferenceTable::from_canonical(interner, canonical_domain_goal.clone());
-
                     if let Ok(solution) =
                         InferCtxt::from_implication(self, infer, subst, goal, implication.clone())
                             .and_then(|infcx| infcx.solve())
                     {
-                        match solution {
-                            Solution::Unique(..) => match &current_solution {
-                                // found two different solutions
-                                Some(curr_sol) if &solution != curr_sol =>
-                                    return Ok(Solution::Ambiguous),
-                                _ => current_solution = Some(solution),
-                            },
-                            Solution::Ambiguous => continue,
-                        }
+                        current_solution = Some(combine(current_solution, solution));
                     }
                 }
             }
         }
 
+        for goal in self.object_bound_goals(canonical_domain_goal) {
+            if let Ok(solution) = self.solve_from_clauses(&goal) {
+                current_solution = Some(combine(current_solution, solution));
+            }
+        }
+
         match current_solution {
             Some(solution) => Ok(solution),
             None => Err(LogicError::NoSolution),
         }
     }
 
-    pub fn simplify(&self, canonical_goal: &Canonical<Goal<I>>) -> SolutionResult<I> {
+    /// The clauses of a trait only apply to `dyn` types with that trait as their only bound,
+    /// so `dyn A + B: Trait` is split into `dyn A: Trait` and `dyn B: Trait`, either of which
+    /// is enough for it to hold.
+    fn object_bound_goals(
+        &self,
+        canonical_domain_goal: &Canonical<InEnvironment<DomainGoal<I>>>,
+    ) -> Vec<Canonical<InEnvironment<DomainGoal<I>>>> {
+        let interner = self.interner;
+        let Canonical { binders, value: InEnvironment { environment, goal } } =
+            canonical_domain_goal;
+        let (bounds, trait_ref) = match goal {
+            DomainGoal::Holds(Constraint::Implemented(ImplConstraint { ty, trait_ref })) =>
+                match ty.kind(interner) {
+                    TyKind::Dyn(DynTy { bounds }) if bounds.len() > 1 => (bounds, trait_ref),
+                    _ => return vec![],
+                },
+            _ => return vec![],
+        };
+        bounds
+            .iter()
+            .map(|bound| {
+                let ty = TyKind::Dyn(DynTy { bounds: vec![bound.clone()] }).intern(interner);
+                let impl_constraint = ImplConstraint { ty, trait_ref: trait_ref.clone() };
+                let goal = DomainGoal::Holds(Constraint::Implemented(impl_constraint));
+                Canonical {
+                    binders: binders.clone(),
+                    value: InEnvironment::new(environment.clone(), goal),
+                }
+            })
+            .collect()
+    }
+
+    pub fn simplify(
+        &self,
+        canonical_goal: &Canonical<InEnvironment<Goal<I>>>,
+    ) -> SolutionResult<I> {
         let (infer, subst, goal) =
             InferenceTable::from_canonical(self.interner, canonical_goal.clone());
         InferCtxt::from_goal(self, infer, subst, goal)?.solve()
@@ -79,25 +246,56 @@ impl<I: Interner> RecursiveSolver<I> {
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Solution<I: Interner> {
-    Unique(Canonical<Subst<I>>),
+    Unique(Canonical<ConstrainedSubst<I>>),
     Ambiguous,
 }
 
 impl<I: Interner> Solution<I> {
+    /// the solution of a goal that holds by either of two solutions
+    pub fn combine(self, other: Self) -> Self {
+        if self == other { self } else { Self::Ambiguous }
+    }
+
+    /// The solution of a marker trait goal that holds by either of two solutions. Any impl of a
+    /// marker trait is as good as another, so of two solutions choosing the same values for the
+    /// inference variables the one whose region constraints are all required by the other is
+    /// kept. Otherwise neither requirement is weaker and the solution is ambiguous.
+    pub fn combine_marker(self, other: Self) -> Self {
+        let weaker = |a: &Canonical<ConstrainedSubst<I>>, b: &Canonical<ConstrainedSubst<I>>| {
+            a.binders == b.binders
+                && a.value.subst == b.value.subst
+                && a.value.constraints.iter().all(|c| b.value.constraints.contains(c))
+        };
+        match (&self, &other) {
+            (Self::Unique(a), Self::Unique(b)) if weaker(a, b) => self,
+            (Self::Unique(a), Self::Unique(b)) if weaker(b, a) => other,
+            _ => self.combine(other),
+        }
+    }
+
     /// Returns `true` if the solution is [`Unique`].
     pub fn is_unique(&self) -> bool {
         matches!(self, Self::Unique(..))
     }
 
-    pub fn into_unique(self) -> Canonical<Subst<I>> {
+    /// Returns `true` if the solution is [`Ambiguous`].
+    pub fn is_ambiguous(&self) -> bool {
+        matches!(self, Self::Ambiguous)
+    }
+
+    pub fn into_unique(self) -> Canonical<ConstrainedSubst<I>> {
         if let Self::Unique(v) = self { v } else { panic!() }
     }
 
-    pub fn into_subst(self) -> Subst<I> {
+    pub fn into_constrained_subst(self) -> ConstrainedSubst<I> {
         let canonical_subst = self.into_unique();
         assert!(canonical_subst.binders.is_empty());
         canonical_subst.value
     }
+
+    pub fn into_subst(self) -> Subst<I> {
+        self.into_constrained_subst().subst
+    }
 }
 
 #[cfg(test)]
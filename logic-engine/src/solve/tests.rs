@@ -42,7 +42,10 @@ fn test_solve_multistep_goal() {
     for<T> { Vec<T>: Eq :- T: Eq }.
     for<T> { T: PartialEq :- T: Eq }.
     ";
+    // `T` may be any of `i32`, `Vec<i32>`, `Vec<Vec<i32>>`, ...
     let solution = query!(program: "exists<T> { Vec<T> : PartialEq }");
+    assert!(solution.is_ambiguous());
+    let solution = query!(program: "Vec<Vec<i32>> : PartialEq");
     assert_eq!(solution.into_subst(), subst![]);
 }
 
@@ -57,3 +60,535 @@ fn test_solve_goal_chalk_example() {
     let solution = query!(program: "exists<T> { Vec<T>: A }");
     assert_eq!(solution.into_subst(), subst![ty!(u32)]);
 }
+
+#[test]
+fn test_solve_predicate_facts() {
+    use logic_driver::LoweringDatabase;
+    let program = include_str!("../../../examples/test.logic");
+    assert_eq!(query!(program: "cool(jen)").into_subst(), subst![]);
+    assert!(query!(program: "exists<X> { cool(X) }").is_ambiguous());
+    let db = logic_driver::Database::new(program);
+    assert!(db.query(std::sync::Arc::new("cool(alice)".to_owned())).is_err());
+}
+
+#[test]
+fn test_solve_recursive_predicate() {
+    let program = r"
+    parent(alice, bob).
+    parent(bob, carol).
+    for<X, Y> { ancestor(X, Y) :- parent(X, Y) }.
+    for<X, Y, Z> { ancestor(X, Z) :- parent(X, Y), ancestor(Y, Z) }.
+    ";
+    assert_eq!(query!(program: "ancestor(alice, carol)").into_subst(), subst![]);
+    assert!(query!(program: "exists<X> { ancestor(alice, X) }").is_ambiguous());
+    let solution = query!(program: "exists<X> { parent(X, bob), ancestor(X, carol) }");
+    assert_eq!(solution.into_subst(), subst![ty!(alice)]);
+}
+
+#[test]
+fn test_solve_left_recursive_predicate() {
+    use logic_driver::LoweringDatabase;
+    let program = r"
+    edge(a, b).
+    edge(b, c).
+    for<X, Y> { path(X, Y) :- edge(X, Y) }.
+    for<X, Y, Z> { path(X, Z) :- path(X, Y), path(Y, Z) }.
+    ";
+    assert_eq!(query!(program: "path(a, b)").into_subst(), subst![]);
+    assert!(query!(program: "exists<X> { path(X, c) }").is_ambiguous());
+    let db = logic_driver::Database::new(program);
+    assert!(db.query(std::sync::Arc::new("path(c, a)".to_owned())).is_err());
+}
+
+#[test]
+fn test_solve_eq_goal() {
+    use logic_driver::LoweringDatabase;
+    let program = r"
+    i32: Copy.
+    for<T, U> { same(T, U) :- T = U }.
+    ";
+    let solution = query!(program: "exists<T> { Vec<T> = Vec<i32> }");
+    assert_eq!(solution.into_subst(), subst![ty!(i32)]);
+    let solution = query!(program: "exists<T> { same(T, i32), T: Copy }");
+    assert_eq!(solution.into_subst(), subst![ty!(i32)]);
+
+    let db = logic_driver::Database::new(program);
+    for goal in ["i32 = u32", "exists<T> { T = Vec<T> }", "exists<T> { T = u32, T: Copy }"] {
+        assert!(db.query(std::sync::Arc::new(goal.to_owned())).is_err());
+    }
+}
+
+#[test]
+fn test_solve_with_impls() {
+    use logic_driver::LoweringDatabase;
+    let program = r"
+    struct Vec<T> {}
+    trait Clone {}
+    impl<T> Clone for Vec<T> where T: Clone {}
+    impl Clone for i32 {}
+    ";
+    assert_eq!(query!(program: "Vec<Vec<i32>>: Clone").into_subst(), subst![]);
+    let solution = query!(program: "exists<T> { Vec<T>: Clone, T = i32 }");
+    assert_eq!(solution.into_subst(), subst![ty!(i32)]);
+    let db = logic_driver::Database::new(program);
+    assert!(db.query(std::sync::Arc::new("Vec<u32>: Clone".to_owned())).is_err());
+}
+
+#[test]
+fn test_solve_forall_and_implies() {
+    use logic_driver::LoweringDatabase;
+    let program = r"
+    for<T> { Vec<T>: Clone :- T: Clone }.
+    ";
+    let solution = query!(program: "forall<T> { if (T: Clone) { Vec<T>: Clone } }");
+    assert_eq!(solution.into_subst(), subst![]);
+    let solution = query!(program: "forall<U> { exists<T> { T = U } }");
+    assert_eq!(solution.into_subst(), subst![]);
+
+    let db = logic_driver::Database::new(program);
+    for goal in [
+        "forall<T> { Vec<T>: Clone }",
+        "forall<T> { T = i32 }",
+        "exists<T> { forall<U> { T = U } }",
+        "exists<T> { forall<U> { T = Vec<U> } }",
+        "forall<T, U> { if (T: Clone) { Vec<U>: Clone } }",
+    ] {
+        assert!(db.query(std::sync::Arc::new(goal.to_owned())).is_err(), "{}", goal);
+    }
+}
+
+#[test]
+fn test_solve_disjunction() {
+    use logic_driver::LoweringDatabase;
+    let program = r"
+    i32: A.
+    u32: B.
+    ";
+    assert_eq!(query!(program: "i32: A; u32: A").into_subst(), subst![]);
+    assert_eq!(query!(program: "u32: A; u32: B").into_subst(), subst![]);
+    assert_eq!(query!(program: "exists<T> { T: A; T: C }").into_subst(), subst![ty!(i32)]);
+    // either branch holds but for different types
+    assert!(query!(program: "exists<T> { T: A; T: B }").is_ambiguous());
+
+    let db = logic_driver::Database::new(program);
+    assert!(db.query(std::sync::Arc::new("u32: A; i32: B".to_owned())).is_err());
+}
+
+#[test]
+fn test_solve_well_formed() {
+    use logic_driver::LoweringDatabase;
+    let program = r"
+    struct i32 {}
+    struct Set<T> where T: Ord {}
+    trait Eq {}
+    trait Ord where Self: Eq {}
+    impl Eq for i32 {}
+    impl Ord for i32 {}
+    impl Ord for u32 {}
+    ";
+    assert_eq!(query!(program: "WellFormed(Set<i32>)").into_subst(), subst![]);
+    assert_eq!(query!(program: "WellFormed(i32: Ord)").into_subst(), subst![]);
+    let solution = query!(program: "forall<T> { if (T: Ord) { WellFormed(Set<T>) } }");
+    assert_eq!(solution.into_subst(), subst![]);
+
+    let db = logic_driver::Database::new(program);
+    for goal in ["WellFormed(u32)", "WellFormed(Set<Set<i32>>)", "WellFormed(u32: Ord)"] {
+        assert!(db.query(std::sync::Arc::new(goal.to_owned())).is_err(), "{}", goal);
+    }
+}
+
+#[test]
+fn test_ill_formed_impls() {
+    use logic_driver::LoweringDatabase;
+    let program = r"
+    struct i32 {}
+    struct Set<T> where T: Ord {}
+    trait Clone {}
+    trait Eq {}
+    trait Ord where Self: Eq {}
+    impl Clone for i32 {}
+    impl Ord for i32 {}
+    impl<T> Clone for Set<T> where T: Ord, T: Clone {}
+    impl<T> Clone for Set<T> where T: Clone {}
+    impl Clone for Foo {}
+    ";
+    let ill_formed = logic_driver::Database::new(program).ill_formed_impls().unwrap();
+    let printed = ill_formed.iter().map(|impl_datum| print(LogicInterner, impl_datum));
+    let expected = [
+        "impl Ord for i32 {}",
+        "impl<T> Clone for Set<T> where T: Clone {}",
+        "impl Clone for Foo {}",
+    ];
+    assert_eq!(printed.collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn test_solve_implied_bounds() {
+    use logic_driver::LoweringDatabase;
+    let program = r"
+    struct Set<T> where T: Ord {}
+    trait PartialOrd {}
+    trait Ord where Self: PartialOrd {}
+    ";
+    for goal in [
+        "forall<T> { if (T: Ord) { T: PartialOrd } }",
+        "forall<T> { if (T: Ord) { WellFormed(T: Ord) } }",
+        "forall<T> { if (WellFormed(Set<T>)) { T: Ord, T: PartialOrd } }",
+        // the hypotheses of undeclared traits hold as written
+        "if (i32: Foo) { i32: Foo }",
+    ] {
+        assert_eq!(query!(program: goal).into_subst(), subst![], "{}", goal);
+    }
+
+    // the supertrait doesn't imply the subtrait
+    let db = logic_driver::Database::new(program);
+    let goal = "forall<T> { if (T: PartialOrd) { T: Ord } }";
+    assert!(db.query(std::sync::Arc::new(goal.to_owned())).is_err());
+}
+
+#[test]
+fn test_solve_projections() {
+    use logic_driver::LoweringDatabase;
+    let program = r"
+    trait Clone {}
+    trait Iterator { type Item; }
+    impl Clone for i32 {}
+    impl<T> Iterator for Vec<T> { type Item = T; }
+    impl<T> Iterator for Map<T> where T: Iterator { type Item = <T as Iterator>::Item; }
+    ";
+    assert_eq!(query!(program: "<Vec<i32> as Iterator>::Item: Clone").into_subst(), subst![]);
+    assert_eq!(query!(program: "<Map<Vec<i32>> as Iterator>::Item = i32").into_subst(), subst![]);
+    let solution = query!(program: "exists<T> { Vec<<Vec<T> as Iterator>::Item> = Vec<u32> }");
+    assert_eq!(solution.into_subst(), subst![ty!(u32)]);
+    let solution = query!(program: "exists<T> { Normalize(<Vec<i32> as Iterator>::Item -> T) }");
+    assert_eq!(solution.into_subst(), subst![ty!(i32)]);
+    // the variable is bound to what the projection normalizes to
+    let solution = query!(program: "exists<U> { <Vec<i32> as Iterator>::Item = U }");
+    assert_eq!(solution.into_subst(), subst![ty!(i32)]);
+    let solution = query!(program: "exists<U> { U = <Map<Vec<u32>> as Iterator>::Item }");
+    assert_eq!(solution.into_subst(), subst![ty!(u32)]);
+    // projections of type parameters are only equal to themselves
+    let goal = "forall<T> { if (T: Iterator) { <T as Iterator>::Item = <T as Iterator>::Item } }";
+    assert_eq!(query!(program: goal).into_subst(), subst![]);
+
+    let db = logic_driver::Database::new(program);
+    for goal in [
+        "<Vec<u32> as Iterator>::Item: Clone",
+        "<Vec<i32> as Iterator>::Item = u32",
+        "forall<T> { if (T: Iterator) { <T as Iterator>::Item = i32 } }",
+    ] {
+        assert!(db.query(std::sync::Arc::new(goal.to_owned())).is_err(), "{}", goal);
+    }
+}
+
+#[test]
+fn test_solve_region_constraints() {
+    let program = r"
+    trait Foo {}
+    impl<'a, T> Foo for Ref<'a, T> where T: 'a {}
+    impl Foo for Str<'static> {}
+    ";
+    let constraints = |goal: &str| {
+        let solution = query!(program: goal).into_constrained_subst();
+        solution.constraints.iter().map(|c| format!("{:?}", c)).collect::<Vec<_>>()
+    };
+    let solution = query!(program: "exists<'a> { Str<'a>: Foo }").into_constrained_subst();
+    assert_eq!(solution.subst, subst![LifetimeData::Static.intern(LogicInterner)]);
+    assert!(solution.constraints.is_empty());
+    // the solver doesn't decide whether a placeholder is `'static`, it just says it must be
+    assert_eq!(constraints("forall<'a> { Str<'a>: Foo }"), ["'!1.0: 'static", "'static: '!1.0"]);
+    assert_eq!(constraints("forall<'a, T> { Ref<'a, T>: Foo }"), ["!1.1: '!1.0"]);
+    assert_eq!(
+        constraints("forall<'a, 'b> { 'a: 'b, Ref<'b, i32>: Foo }"),
+        ["'!1.0: '!1.1", "i32: '!1.1"]
+    );
+    // a region constraint that is assumed holds, the others are still returned
+    assert_eq!(constraints("forall<'a, T> { if (T: 'a) { Ref<'a, T>: Foo } }"), [""; 0]);
+    assert_eq!(constraints("forall<'a, 'b> { if ('a: 'b) { 'a: 'b } }"), [""; 0]);
+    assert_eq!(
+        constraints("forall<'a, 'b, T> { if ('a: 'b) { Ref<'a, T>: Foo } }"),
+        ["!1.2: '!1.0"]
+    );
+}
+
+#[test]
+fn test_solve_const_generics() {
+    let program = r"
+    trait Default {}
+    trait Small {}
+    impl Default for i32 {}
+    impl<T, const N> Default for Array<T, N> where T: Default {}
+    impl Small for Array<u8, 4> {}
+    impl Small for Flag<true> {}
+    impl<const N: usize> Small for Bits<N> {}
+    impl<const B: bool> Default for Flag<B> {}
+    ";
+    let constant = |value| ConstData::Value(value).intern(LogicInterner);
+    let solution = query!(program: "forall<const N> { Array<i32, N>: Default }");
+    assert_eq!(solution.into_subst(), subst![]);
+    let solution = query!(program: "exists<T, const N> { Array<T, N>: Small }");
+    assert_eq!(solution.into_subst(), subst![ty!(u8), constant(ConstValue::Int(4))]);
+    let solution = query!(program: "exists<const B> { Flag<B>: Small }");
+    assert_eq!(solution.into_subst(), subst![constant(ConstValue::Bool(true))]);
+    let goal = "forall<const N> { exists<const M> { Array<i32, M> = Array<i32, N> } }";
+    assert_eq!(query!(program: goal).into_subst(), subst![]);
+    assert_eq!(query!(program: "Bits<8>: Small").into_subst(), subst![]);
+    assert_eq!(query!(program: "Flag<false>: Default").into_subst(), subst![]);
+
+    use logic_driver::LoweringDatabase;
+    let db = logic_driver::Database::new(program);
+    for goal in [
+        "Array<u8, 5>: Small",
+        "Flag<1>: Small",
+        "forall<const N> { Array<u8, N>: Small }",
+        "exists<const M> { forall<const N> { Array<i32, M> = Array<i32, N> } }",
+        // a const of one type can't have a value of another
+        "Bits<true>: Small",
+        "Flag<1>: Default",
+        "exists<const N: usize> { Bits<N> = Bits<false> }",
+        "exists<const N: usize, const B: bool> { Bits<N> = Bits<B> }",
+    ] {
+        assert!(db.query(std::sync::Arc::new(goal.to_owned())).is_err(), "{}", goal);
+    }
+}
+
+#[test]
+fn test_solve_builtin_tys() {
+    let program = r"
+    trait Copy {}
+    impl Copy for i32 {}
+    impl Copy for () {}
+    impl<A, B> Copy for (A, B) where A: Copy, B: Copy {}
+    impl<'a, T> Copy for &'a T {}
+    impl<T, const N> Copy for [T; N] where T: Copy {}
+    impl<T> Copy for fn(T) -> T {}
+    ";
+    assert_eq!(query!(program: "((i32, ()), [&'static u32; 4]): Copy").into_subst(), subst![]);
+    assert_eq!(query!(program: "forall<A> { fn(A) -> A: Copy }").into_subst(), subst![]);
+    let solution = query!(program: "exists<T, U> { (T, [U; 2]) = ((), [i32; 2]) }");
+    let unit = TyKind::unit(LogicInterner).intern(LogicInterner);
+    assert_eq!(solution.into_subst(), subst![unit, ty!(i32)]);
+
+    use logic_driver::LoweringDatabase;
+    let db = logic_driver::Database::new(program);
+    for goal in [
+        "(i32, i32, i32): Copy",
+        "forall<'a> { &'a mut i32: Copy }",
+        "[u32; 2]: Copy",
+        "fn(i32) -> u32: Copy",
+        "(i32,) = i32",
+        "[i32] = [i32; 1]",
+        "exists<'a> { &'a i32 = &'a mut i32 }",
+        "! = ()",
+    ] {
+        assert!(db.query(std::sync::Arc::new(goal.to_owned())).is_err(), "{}", goal);
+    }
+}
+
+#[test]
+fn test_solve_trait_objects() {
+    let program = r"
+    trait Send {}
+    trait Any {}
+    trait Debug where Self: Any {}
+    trait Iterator<T> where Self: Debug {}
+    impl<T> Send for Vec<T> where T: Send {}
+    impl Send for dyn Iterator<i32> + Send {}
+    ";
+    assert_eq!(query!(program: "dyn Iterator<i32>: Iterator<i32>").into_subst(), subst![]);
+    assert_eq!(query!(program: "forall<T> { dyn Iterator<T>: Any }").into_subst(), subst![]);
+    assert_eq!(query!(program: "dyn Debug + Send: Send").into_subst(), subst![]);
+    assert_eq!(query!(program: "dyn Send + Iterator<u32>: Debug").into_subst(), subst![]);
+    assert_eq!(
+        query!(program: "exists<T> { dyn Iterator<T>: Iterator<u32> }").into_subst(),
+        subst![ty!(u32)]
+    );
+    assert_eq!(query!(program: "Vec<dyn Iterator<i32> + Send>: Send").into_subst(), subst![]);
+
+    use logic_driver::LoweringDatabase;
+    let db = logic_driver::Database::new(program);
+    for goal in [
+        "dyn Any: Debug",
+        "dyn Iterator<i32>: Iterator<u32>",
+        "dyn Iterator<i32>: Send",
+        "dyn Send + Any = dyn Any + Send",
+    ] {
+        assert!(db.query(std::sync::Arc::new(goal.to_owned())).is_err(), "{}", goal);
+    }
+}
+
+#[test]
+fn test_solve_opaque_tys() {
+    let program = r"
+    trait Iterator<T> {}
+    trait Clone {}
+    opaque type Iter<T>: Iterator<T> = Vec<T>;
+    impl<T> Iterator<T> for Vec<T> {}
+    impl<T> Clone for Vec<T> {}
+    ";
+    assert_eq!(query!(program: "Iter<i32>: Iterator<i32>").into_subst(), subst![]);
+    assert_eq!(query!(program: "forall<T> { Iter<T> = Iter<T> }").into_subst(), subst![]);
+    assert_eq!(
+        query!(program: "exists<T> { Iter<T>: Iterator<u32> }").into_subst(),
+        subst![ty!(u32)]
+    );
+    // the hidden type is only visible where it is revealed
+    assert_eq!(query!(program: "if (Reveal) { Iter<i32> = Vec<i32> }").into_subst(), subst![]);
+    assert_eq!(
+        query!(program: "forall<T> { if (Reveal) { Iter<T>: Clone } }").into_subst(),
+        subst![]
+    );
+    assert_eq!(
+        query!(program: "exists<T> { if (Reveal) { Normalize(Iter<i32> -> Vec<T>) } }")
+            .into_subst(),
+        subst![ty!(i32)]
+    );
+
+    use logic_driver::LoweringDatabase;
+    let db = logic_driver::Database::new(program);
+    for goal in [
+        "Reveal",
+        "Iter<i32>: Clone",
+        "Iter<i32> = Vec<i32>",
+        "Iter<i32> = Iter<u32>",
+        "exists<T> { Normalize(Iter<i32> -> T) }",
+    ] {
+        assert!(db.query(std::sync::Arc::new(goal.to_owned())).is_err(), "{}", goal);
+    }
+}
+
+#[test]
+fn test_solve_negation_as_failure() {
+    let program = r"
+    i32: Primary.
+    for<T> { T: Fallback :- not { T: Primary } }.
+    for<T> { Vec<T>: Primary :- T: Primary }.
+    ";
+    assert_eq!(query!(program: "u32: Fallback").into_subst(), subst![]);
+    assert_eq!(query!(program: "Vec<u32>: Fallback").into_subst(), subst![]);
+    assert_eq!(query!(program: "not { Vec<i32>: Fallback }").into_subst(), subst![]);
+    // the variable may be resolved by the goals that come after the negation
+    assert_eq!(
+        query!(program: "exists<T> { not { T: Primary }, T = u32 }").into_subst(),
+        subst![ty!(u32)]
+    );
+    // the goals aren't ground so the negation flounders
+    assert!(query!(program: "exists<T> { T: Fallback }").is_ambiguous());
+    assert!(query!(program: "forall<T> { T: Fallback }").is_ambiguous());
+
+    use logic_driver::LoweringDatabase;
+    let db = logic_driver::Database::new(program);
+    for goal in ["i32: Fallback", "Vec<i32>: Fallback", "not { u32: Fallback }"] {
+        assert!(db.query(std::sync::Arc::new(goal.to_owned())).is_err(), "{}", goal);
+    }
+}
+
+#[test]
+fn test_solve_negation_in_cycles_is_ambiguous() {
+    let program = r"
+    a() :- not { b() }.
+    b() :- not { a() }.
+    c() :- not { c() }.
+    d() :- e().
+    e() :- d().
+    f() :- not { d() }.
+    ";
+    assert!(query!(program: "a()").is_ambiguous());
+    assert!(query!(program: "b()").is_ambiguous());
+    assert!(query!(program: "c()").is_ambiguous());
+    // a cycle entirely within the negated goal is fine
+    assert_eq!(query!(program: "f()").into_subst(), subst![]);
+}
+
+#[test]
+fn test_solve_specialized_impls() {
+    let program = r"
+    trait Iterator { type Item; }
+    trait Copy {}
+    impl Copy for u8 {}
+    default impl<T> Iterator for Vec<T> { type Item = T; }
+    impl<T> Iterator for Vec<T> where T: Copy { type Item = u32; }
+    impl Iterator for Vec<i32> { type Item = i32; }
+    ";
+    assert_eq!(query!(program: "Vec<i32>: Iterator").into_subst(), subst![]);
+    assert_eq!(
+        query!(program: "exists<T> { Normalize(<Vec<bool> as Iterator>::Item -> T) }").into_subst(),
+        subst![ty!(bool)]
+    );
+    assert_eq!(
+        query!(program: "exists<T> { Normalize(<Vec<u8> as Iterator>::Item -> T) }").into_subst(),
+        subst![ty!(u32)]
+    );
+    assert_eq!(
+        query!(program: "exists<T> { Normalize(<Vec<i32> as Iterator>::Item -> T) }").into_subst(),
+        subst![ty!(i32)]
+    );
+    let goal = "exists<T> { forall<U> { if (U: Copy) { \
+        Normalize(<Vec<U> as Iterator>::Item -> T) } } }";
+    assert_eq!(query!(program: goal).into_subst(), subst![ty!(u32)]);
+    // either impl may apply depending on the type
+    assert!(query!(program: "exists<T, U> { Normalize(<Vec<U> as Iterator>::Item -> T) }")
+        .is_ambiguous());
+}
+
+#[test]
+fn test_solve_marker_traits() {
+    let program = r"
+    #[marker] trait Eq {}
+    trait Ord {}
+    impl<'a> Eq for Ref<'a> where 'a: 'static {}
+    impl<'a> Eq for Ref<'a> {}
+    impl<'a> Ord for Ref<'a> where 'a: 'static {}
+    impl<'a> Ord for Ref<'a> {}
+    impl<T> Eq for Vec<T> {}
+    impl Eq for Vec<i32> {}
+    ";
+    // either impl proves the goal, the one without any region constraints is used
+    assert_eq!(
+        query!(program: "forall<'a> { Ref<'a>: Eq }").into_constrained_subst().constraints,
+        []
+    );
+    assert!(query!(program: "forall<'a> { Ref<'a>: Ord }").is_ambiguous());
+    assert_eq!(query!(program: "Vec<i32>: Eq").into_subst(), subst![]);
+    // the impls would choose different types
+    assert!(query!(program: "exists<T> { Vec<T>: Eq }").is_ambiguous());
+
+    // neither impl requires less than the other, whichever order they are declared in
+    let outlives = r"
+    impl<'a, 'b> Eq for Pair<'a, 'b> where 'a: 'b {}
+    impl<'a, 'b> Eq for Pair<'a, 'b> where 'b: 'a {}
+    ";
+    let reversed = r"
+    impl<'a, 'b> Eq for Pair<'a, 'b> where 'b: 'a {}
+    impl<'a, 'b> Eq for Pair<'a, 'b> where 'a: 'b {}
+    ";
+    for impls in [outlives, reversed] {
+        let program = format!("#[marker] trait Eq {{}} {}", impls);
+        let program = program.as_str();
+        assert!(query!(program: "forall<'a, 'b> { Pair<'a, 'b>: Eq }").is_ambiguous());
+    }
+}
+
+#[test]
+fn test_solve_with_arena_interner() {
+    use crate::{GoalExt, RecursiveSolver};
+    use logic_driver::LoweringDatabase;
+    let program = r"
+    trait Clone {}
+    impl<T> Clone for Vec<T> where T: Clone {}
+    impl Clone for i32 {}
+    impl Clone for u32 {}
+    ";
+    let ast = logic_parse::parse_program(program).unwrap();
+    let arenas = Arenas::default();
+    let interner = ArenaInterner::new(&arenas);
+    let ir = lower_ast_with(interner, &ast).unwrap();
+    let solver = RecursiveSolver::new(interner, Environment::new(ir.program_clauses()));
+    for goal in ["Vec<Vec<i32>>: Clone", "exists<T> { Vec<T>: Clone }", "Vec<String>: Clone"] {
+        let parsed = logic_parse::parse_goal(goal).unwrap();
+        let peeled = lower_goal_in_with(interner, &ast, &parsed).unwrap().peel(interner);
+        let expected =
+            logic_driver::Database::new(program).query(std::sync::Arc::new(goal.to_owned()));
+        assert_eq!(format!("{:?}", solver.solve(&peeled)), format!("{:?}", expected), "{}", goal);
+    }
+}
@@ -0,0 +1,126 @@
+//! specialization, an impl may overlap a `default` impl that is strictly less specific than it,
+//! in which case the more specific impl is used wherever it applies
+use crate::infer::{InferCtxt, InferenceTable};
+use crate::RecursiveSolver;
+use logic_ir::*;
+
+/// The impls of a program along with which of them specialize which.
+/// There is an edge from an impl to each `default` impl that it is strictly more specific than.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecializationGraph<I: Interner> {
+    pub impls: Vec<ImplDatum<I>>,
+    /// `(specializing, specialized)` pairs of indices into `impls`
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl<I: Interner> SpecializationGraph<I> {
+    pub fn empty() -> Self {
+        Self { impls: vec![], edges: vec![] }
+    }
+
+    pub fn specializes(&self, specializing: usize, specialized: usize) -> bool {
+        self.edges.contains(&(specializing, specialized))
+    }
+}
+
+impl<I: Interner> RecursiveSolver<I> {
+    /// Solves goals that more than one impl applies to with the most specific of them, see
+    /// [`RecursiveSolver::specialization_graph`].
+    pub fn with_specialization_graph(mut self, graph: SpecializationGraph<I>) -> Self {
+        self.specialization_graph = graph;
+        self
+    }
+
+    pub fn specialization_graph(&self, impls: &[ImplDatum<I>]) -> SpecializationGraph<I> {
+        let mut edges = vec![];
+        for (i, a) in impls.iter().enumerate() {
+            for (j, b) in impls.iter().enumerate() {
+                if i != j && b.default && self.specializes(a, b) && !self.specializes(b, a) {
+                    edges.push((i, j));
+                }
+            }
+        }
+        SpecializationGraph { impls: impls.to_vec(), edges }
+    }
+
+    /// `a` specializes `b` if `b` applies to every type `a` applies to, that is, for all
+    /// parameters of `a` the headers unify and the where clauses of `b` follow from those of `a`
+    fn specializes(&self, a: &ImplDatum<I>, b: &ImplDatum<I>) -> bool {
+        let interner = self.interner;
+        if a.binders.skip_binders().header.trait_ref.trait_name
+            != b.binders.skip_binders().header.trait_ref.trait_name
+        {
+            return false;
+        }
+
+        let mut table = InferenceTable::new(interner);
+        let a = table.instantiate_universally(a.binders.clone());
+        let hypotheses = a.where_clauses.into_iter().map(|wc| {
+            let implication = Implication {
+                consequent: DomainGoal::Holds(wc),
+                condition: Goal::intern(interner, GoalData::True),
+            };
+            Clause::intern(interner, ClauseData::Implies(Binders::empty(interner, implication)))
+        });
+        let environment = Environment::empty(interner).add_clauses(interner, hypotheses);
+        self.impl_applies(table, &environment, &a.header, b)
+    }
+
+    /// Whether the impl holds for the header without constraining the header any further.
+    /// The header must not contain any inference variables from outside of `table`.
+    fn impl_applies(
+        &self,
+        mut table: InferenceTable<I>,
+        environment: &Environment<I>,
+        header: &ImplConstraint<I>,
+        impl_datum: &ImplDatum<I>,
+    ) -> bool {
+        let interner = self.interner;
+        let instantiated = table.instantiate(impl_datum.binders.clone());
+        let mut infcx = InferCtxt::new(self, table, Subst::empty(interner));
+        if infcx.unify(environment, header, &instantiated.header).is_err() {
+            return false;
+        }
+        for wc in instantiated.where_clauses {
+            let goal = Goal::intern(interner, GoalData::DomainGoal(DomainGoal::Holds(wc)));
+            if infcx.add_goal(environment, goal).is_err() {
+                return false;
+            }
+        }
+        infcx.solve().is_ok_and(|solution| solution.is_unique())
+    }
+
+    /// The clauses of the impls that are specialized by an impl applying to the goal,
+    /// which are not used to solve it. An impl only applies if it does for every value of the
+    /// variables of the goal, otherwise either impl may be the one that is used.
+    pub(crate) fn specialized_clauses(
+        &self,
+        canonical_domain_goal: &Canonical<InEnvironment<DomainGoal<I>>>,
+    ) -> Vec<Clause<I>> {
+        let interner = self.interner;
+        let graph = &self.specialization_graph;
+        let mut clauses = vec![];
+        for &(specializing, specialized) in &graph.edges {
+            let impl_datum = &graph.impls[specializing];
+            let Canonical { binders, value } = canonical_domain_goal.clone();
+            let mut table = InferenceTable::new(interner);
+            let InEnvironment { environment, goal } =
+                table.instantiate_universally(Binders::new(binders, value));
+            let header = match goal {
+                DomainGoal::Holds(Constraint::Implemented(header)) => header,
+                DomainGoal::Normalize(Normalize {
+                    alias: AliasTy::Projection(ProjectionTy { ty, trait_ref, .. }),
+                    ..
+                }) => ImplConstraint { ty, trait_ref },
+                _ => return vec![],
+            };
+            if header.trait_ref.trait_name
+                == impl_datum.binders.skip_binders().header.trait_ref.trait_name
+                && self.impl_applies(table, &environment, &header, impl_datum)
+            {
+                graph.impls[specialized].to_program_clauses(interner, &mut clauses);
+            }
+        }
+        clauses
+    }
+}
@@ -2,33 +2,170 @@ use crate::infer::{InferCtxt, InferenceTable};
 use logic_ir::*;
 
 impl<I: Interner> InferCtxt<'_, I> {
-    pub fn unify<T: Zip<I>>(&mut self, a: &T, b: &T) -> LogicResult<()> {
-        self.with_snapsnot(|infcx| Unifier { table: &mut infcx.table }.zip(a, b))
+    /// unifies `a` and `b`, the normalization of any associated types that were unified
+    /// is left as obligations to be proven in `environment`
+    pub fn unify<T: Zip<I>>(
+        &mut self,
+        environment: &Environment<I>,
+        a: &T,
+        b: &T,
+    ) -> LogicResult<()> {
+        let goals = self.with_snapsnot(|infcx| {
+            let mut unifier = Unifier { table: &mut infcx.table, goals: vec![] };
+            unifier.zip(a, b)?;
+            Ok(unifier.goals)
+        })?;
+        for goal in goals {
+            self.add_goal(environment, goal)?;
+        }
+        Ok(())
     }
 }
 
 pub struct Unifier<'i, I: Interner> {
     table: &'i mut InferenceTable<I>,
+    /// the goals that must hold for the unification to be valid
+    goals: Vec<Goal<I>>,
 }
 
 impl<I: Interner> Unifier<'_, I> {
     fn unify_ty_ty(&mut self, t: &Ty<I>, u: &Ty<I>) -> LogicResult<()> {
         let interner = self.interner();
+        let t = &self.table.normalize_shallow(t);
+        let u = &self.table.normalize_shallow(u);
         match (t.kind(interner), u.kind(interner)) {
             (TyKind::Structure(f, xs), TyKind::Structure(g, ys)) if f == g => self.zip(xs, ys),
+            (TyKind::Tuple(xs), TyKind::Tuple(ys)) => self.zip(xs, ys),
+            (TyKind::Ref(m, a, t), TyKind::Ref(n, b, u)) if m == n => {
+                self.zip(a, b)?;
+                self.zip(t, u)
+            }
+            (TyKind::Slice(t), TyKind::Slice(u)) => self.zip(t, u),
+            (TyKind::Array(t, n), TyKind::Array(u, m)) => {
+                self.zip(t, u)?;
+                self.zip(n, m)
+            }
+            (TyKind::Fn(f), TyKind::Fn(g)) => self.zip(f, g),
+            (TyKind::Never, TyKind::Never) => Ok(()),
+            (TyKind::Dyn(a), TyKind::Dyn(b)) => self.zip(a, b),
+            (TyKind::Placeholder(p), TyKind::Placeholder(q)) if p == q => Ok(()),
+            (TyKind::Projection(p), TyKind::Projection(q)) if p == q => Ok(()),
+            (TyKind::Opaque(p), TyKind::Opaque(q)) if p.name == q.name =>
+                self.zip(&p.args, &q.args),
+            // a variable is bound to what the projection normalizes to, not to the projection
+            (TyKind::Projection(projection), ..) =>
+                self.unify_alias_ty(AliasTy::Projection(projection.clone()), u),
+            (.., TyKind::Projection(projection)) =>
+                self.unify_alias_ty(AliasTy::Projection(projection.clone()), t),
             (&TyKind::Infer(i), &TyKind::Infer(j)) => Ok(self.unify_var_var(i, j)),
             (&TyKind::Infer(var), ..) => Ok(self.unify_var_ty(var, u.clone())?),
             (.., &TyKind::Infer(var)) => Ok(self.unify_var_ty(var, t.clone())?),
+            (TyKind::Opaque(opaque), ..) => self.unify_alias_ty(AliasTy::Opaque(opaque.clone()), u),
+            (.., TyKind::Opaque(opaque)) => self.unify_alias_ty(AliasTy::Opaque(opaque.clone()), t),
             _ => Err(LogicError::NoSolution),
         }
     }
 
+    /// The alias is replaced by a new variable that it must normalize to,
+    /// so whether they are equal is only known once the normalization is proven.
+    /// An opaque type only normalizes where `Reveal` holds, elsewhere it is only equal to itself.
+    fn unify_alias_ty(&mut self, alias: AliasTy<I>, ty: &Ty<I>) -> LogicResult<()> {
+        let interner = self.interner();
+        let var = self.table.new_infer_var();
+        let normalize = Normalize { alias, ty: var.to_ty(interner) };
+        let goal = GoalData::DomainGoal(DomainGoal::Normalize(normalize));
+        self.goals.push(Goal::intern(interner, goal));
+        self.unify_var_ty(var, ty.clone())
+    }
+
     pub fn unify_var_ty(&mut self, var: InferVar<I>, ty: Ty<I>) -> LogicResult<()> {
-        OccursCheck { interner: self.interner(), var }.fold(ty.clone())?;
-        self.table
-            .unify
-            .unify_var_value(var, InferenceValue::Known(ty))
-            .expect("should never fail");
+        let universe = self.table.universe_of_unbound_var(var);
+        let mut occurs_check = OccursCheck { table: self.table, var, universe };
+        if occurs_check.visit(&ty).is_break() {
+            return Err(LogicError::NoSolution);
+        }
+        let value = InferenceValue::Known(ty.into_generic_arg(self.interner()));
+        self.table.unify.unify_var_value(var, value).expect("should never fail");
+        Ok(())
+    }
+
+    /// Lifetimes that aren't syntactically equal may still be equal, which is left to
+    /// whoever checks the region constraints that are returned with the solution.
+    fn unify_lifetime_lifetime(&mut self, a: &Lifetime<I>, b: &Lifetime<I>) -> LogicResult<()> {
+        let interner = self.interner();
+        let a = &self.table.normalize_lifetime_shallow(a);
+        let b = &self.table.normalize_lifetime_shallow(b);
+        match (a.data(interner), b.data(interner)) {
+            (&LifetimeData::Infer(i), &LifetimeData::Infer(j)) => self.unify_var_var(i, j),
+            (&LifetimeData::Infer(var), ..) => self.unify_var_lifetime(var, b),
+            (.., &LifetimeData::Infer(var)) => self.unify_var_lifetime(var, a),
+            _ if a == b => {}
+            _ => self.push_lifetime_eq(a, b),
+        }
+        Ok(())
+    }
+
+    /// a variable that can't name the lifetime is only required to be equal to it
+    fn unify_var_lifetime(&mut self, var: InferVar<I>, lifetime: &Lifetime<I>) {
+        let interner = self.interner();
+        let universe = self.table.universe_of_unbound_var(var);
+        match lifetime.data(interner) {
+            LifetimeData::Placeholder(placeholder) if !universe.can_see(placeholder.universe) =>
+                self.push_lifetime_eq(&var.to_lifetime(interner), lifetime),
+            _ => {
+                let value = InferenceValue::Known(lifetime.clone().into_generic_arg(interner));
+                self.table.unify.unify_var_value(var, value).expect("should never fail")
+            }
+        }
+    }
+
+    /// two lifetimes are equal if each outlives the other
+    fn push_lifetime_eq(&mut self, a: &Lifetime<I>, b: &Lifetime<I>) {
+        let interner = self.interner();
+        for (a, b) in [(a, b), (b, a)] {
+            let outlives = LifetimeOutlives { a: a.clone(), b: b.clone() };
+            let constraint = Constraint::LifetimeOutlives(outlives);
+            let goal = GoalData::DomainGoal(DomainGoal::Holds(constraint));
+            self.goals.push(Goal::intern(interner, goal));
+        }
+    }
+
+    /// consts are equal only if they have the same value or are the same placeholder
+    fn unify_const_const(&mut self, a: &Const<I>, b: &Const<I>) -> LogicResult<()> {
+        let interner = self.interner();
+        let a = &self.table.normalize_const_shallow(a);
+        let b = &self.table.normalize_const_shallow(b);
+        match (a.data(interner), b.data(interner)) {
+            (&ConstData::Infer(i), &ConstData::Infer(j)) => {
+                // consts of different types are never equal
+                let s = self.table.const_ty_of_unbound_var(i);
+                let t = self.table.const_ty_of_unbound_var(j);
+                if matches!((s, t), (Some(s), Some(t)) if s != t) {
+                    return Err(LogicError::NoSolution);
+                }
+                self.unify_var_var(i, j);
+                Ok(())
+            }
+            (&ConstData::Infer(var), ..) => self.unify_var_const(var, b),
+            (.., &ConstData::Infer(var)) => self.unify_var_const(var, a),
+            _ if a == b => Ok(()),
+            _ => Err(LogicError::NoSolution),
+        }
+    }
+
+    fn unify_var_const(&mut self, var: InferVar<I>, constant: &Const<I>) -> LogicResult<()> {
+        let interner = self.interner();
+        let universe = self.table.universe_of_unbound_var(var);
+        match (constant.data(interner), self.table.const_ty_of_unbound_var(var)) {
+            (ConstData::Placeholder(placeholder), _) if !universe.can_see(placeholder.universe) =>
+                return Err(LogicError::NoSolution),
+            // a const of one type can't have a value of another
+            (&ConstData::Value(value), Some(ty)) if ConstTy::of(value) != ty =>
+                return Err(LogicError::NoSolution),
+            _ => {}
+        }
+        let value = InferenceValue::Known(constant.clone().into_generic_arg(interner));
+        self.table.unify.unify_var_value(var, value).expect("should never fail");
         Ok(())
     }
 
@@ -37,18 +174,86 @@ impl<I: Interner> Unifier<'_, I> {
     }
 }
 
-struct OccursCheck<I: Interner> {
-    interner: I,
+/// Checks that `var` can be bound to the type, i.e. the type doesn't contain `var` itself
+/// nor any placeholders that aren't visible from `universe`.
+/// Any unknown variables in the type are moved into `universe` if they are in an inner one
+/// as they may only be bound to types that `var` can be bound to.
+struct OccursCheck<'i, I: Interner> {
+    table: &'i mut InferenceTable<I>,
     var: InferVar<I>,
+    universe: UniverseIdx,
 }
 
-impl<I: Interner> Folder<I> for OccursCheck<I> {
+impl<I: Interner> Visitor<I> for OccursCheck<'_, I> {
+    type BreakTy = ();
+
     fn interner(&self) -> I {
-        self.interner
+        self.table.interner
+    }
+
+    fn visit_ty(&mut self, ty: &Ty<I>) -> ControlFlow<()> {
+        let flags = ty.flags(self.interner());
+        if !flags.has_infer && !flags.has_placeholder {
+            return ControlFlow::Continue(());
+        }
+        match ty.kind(self.interner()) {
+            TyKind::Placeholder(placeholder) if !self.universe.can_see(placeholder.universe) =>
+                ControlFlow::Break(()),
+            _ => ty.super_visit_with(self),
+        }
     }
 
-    fn fold_infer_var(&mut self, infer: InferVar<I>) -> LogicResult<Ty<I>> {
-        if self.var == infer { Err(LogicError::NoSolution) } else { Ok(infer.to_ty(self.interner)) }
+    fn visit_lifetime(&mut self, lifetime: &Lifetime<I>) -> ControlFlow<()> {
+        match lifetime.data(self.interner()) {
+            LifetimeData::Placeholder(placeholder)
+                if !self.universe.can_see(placeholder.universe) =>
+                ControlFlow::Break(()),
+            _ => lifetime.super_visit_with(self),
+        }
+    }
+
+    fn visit_const(&mut self, constant: &Const<I>) -> ControlFlow<()> {
+        match constant.data(self.interner()) {
+            ConstData::Placeholder(placeholder) if !self.universe.can_see(placeholder.universe) =>
+                ControlFlow::Break(()),
+            _ => constant.super_visit_with(self),
+        }
+    }
+
+    fn visit_infer_var(&mut self, infer: InferVar<I>) -> ControlFlow<()> {
+        if let Some(ty) = self.table.probe_ty(infer) {
+            return ty.visit_with(self);
+        }
+        if self.table.unify.find(infer) == self.table.unify.find(self.var) {
+            return ControlFlow::Break(());
+        }
+        self.move_into_universe(infer);
+        ControlFlow::Continue(())
+    }
+
+    fn visit_infer_lifetime_var(&mut self, infer: InferVar<I>) -> ControlFlow<()> {
+        if let Some(lifetime) = self.table.probe_lifetime(infer) {
+            return lifetime.visit_with(self);
+        }
+        self.move_into_universe(infer);
+        ControlFlow::Continue(())
+    }
+
+    fn visit_infer_const_var(&mut self, infer: InferVar<I>) -> ControlFlow<()> {
+        if let Some(constant) = self.table.probe_const(infer) {
+            return constant.visit_with(self);
+        }
+        self.move_into_universe(infer);
+        ControlFlow::Continue(())
+    }
+}
+
+impl<I: Interner> OccursCheck<'_, I> {
+    fn move_into_universe(&mut self, infer: InferVar<I>) {
+        if !self.universe.can_see(self.table.universe_of_unbound_var(infer)) {
+            let unknown = InferenceValue::Unknown(self.universe, None);
+            self.table.unify.unify_var_value(infer, unknown).expect("should never fail");
+        }
     }
 }
 
@@ -61,6 +266,14 @@ impl<I: Interner> Zipper<I> for Unifier<'_, I> {
         self.unify_ty_ty(t, u)
     }
 
+    fn zip_lifetimes(&mut self, a: &Lifetime<I>, b: &Lifetime<I>) -> LogicResult<()> {
+        self.unify_lifetime_lifetime(a, b)
+    }
+
+    fn zip_consts(&mut self, a: &Const<I>, b: &Const<I>) -> LogicResult<()> {
+        self.unify_const_const(a, b)
+    }
+
     fn zip_binders<T>(&mut self, a: &Binders<T>, b: &Binders<T>) -> LogicResult<()>
     where
         T: HasInterner<Interner = I> + Zip<I>,
@@ -0,0 +1,166 @@
+//! comparing and hashing values up to the names of their variables, i.e. the order of the
+//! variables of their outermost binder and the identities of their inference variables
+use crate::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+pub trait AlphaEq<I: Interner>: Sized + Eq + Hash + StructuralHash<I> {
+    /// The value with the variables of its outermost binder and its inference variables
+    /// renumbered in the order they first occur. Values that only differ in the names of
+    /// their variables have the same normal form.
+    fn alpha_normalized(&self, interner: I) -> Self;
+
+    /// whether the values are equal up to the names of their variables
+    fn alpha_eq(&self, interner: I, other: &Self) -> bool {
+        self.alpha_normalized(interner) == other.alpha_normalized(interner)
+    }
+
+    /// A hash that is the same for values that are `alpha_eq`, whatever the interner is.
+    fn alpha_hash(&self, interner: I) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.alpha_normalized(interner).structural_hash(interner, &mut hasher);
+        hasher.finish()
+    }
+}
+
+impl<I, T> AlphaEq<I> for Binders<T>
+where
+    I: Interner,
+    T: HasInterner<Interner = I> + Fold<I, Folded = T> + StructuralHash<I> + Clone + Eq + Hash,
+{
+    fn alpha_normalized(&self, interner: I) -> Self {
+        let (binders, value) = alpha_normalize(interner, &self.binders, self.skip_binders());
+        Binders::new(binders, value)
+    }
+}
+
+impl<I, T> AlphaEq<I> for Canonical<T>
+where
+    I: Interner,
+    T: HasInterner<Interner = I> + Fold<I, Folded = T> + StructuralHash<I> + Clone + Eq + Hash,
+{
+    fn alpha_normalized(&self, interner: I) -> Self {
+        let (binders, value) = alpha_normalize(interner, &self.binders, &self.value);
+        Canonical { binders, value }
+    }
+}
+
+/// renumbers the variables bound by `binders` in `value`, along with its inference variables
+fn alpha_normalize<I, T>(interner: I, binders: &Variables<I>, value: &T) -> (Variables<I>, T)
+where
+    I: Interner,
+    T: Fold<I, Folded = T> + Clone,
+{
+    let mut renamer = AlphaRenamer { interner, bound: vec![], infer: vec![] };
+    // the value is under `binders`, so its variables are bound at the innermost binder
+    let value = value.clone().fold_with(&mut renamer, DebruijnIdx::ZERO).unwrap();
+    // the unused variables keep their order after the used ones
+    let unused = (0..binders.len()).filter(|index| !renamer.bound.contains(index));
+    let order = renamer.bound.iter().copied().chain(unused).collect::<Vec<_>>();
+    let binders = Variables::intern(interner, order.iter().map(|&index| binders.at(index).clone()));
+    (binders, value)
+}
+
+struct AlphaRenamer<I: Interner> {
+    interner: I,
+    /// the original indices of the variables of the outermost binder, in order of occurrence
+    bound: Vec<usize>,
+    /// the original inference variables, in order of occurrence
+    infer: Vec<InferVar<I>>,
+}
+
+impl<I: Interner> AlphaRenamer<I> {
+    fn bound_var(&mut self, bound: BoundVar, outer_binder: DebruijnIdx) -> BoundVar {
+        if bound.debruijn != outer_binder {
+            return bound;
+        }
+        let index =
+            self.bound.iter().position(|&index| index == bound.index).unwrap_or_else(|| {
+                self.bound.push(bound.index);
+                self.bound.len() - 1
+            });
+        BoundVar::new(bound.debruijn, index)
+    }
+
+    fn infer_var(&mut self, infer: InferVar<I>) -> InferVar<I> {
+        let index = self.infer.iter().position(|&var| var == infer).unwrap_or_else(|| {
+            self.infer.push(infer);
+            self.infer.len() - 1
+        });
+        InferVar::new(InferIdx::new(index))
+    }
+}
+
+impl<I: Interner> Folder<I> for AlphaRenamer<I> {
+    fn interner(&self) -> I {
+        self.interner
+    }
+
+    fn fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIdx) -> LogicResult<Ty<I>> {
+        let flags = ty.flags(self.interner);
+        if !flags.has_infer && !flags.has_bound_above(outer_binder.index()) {
+            return Ok(ty);
+        }
+        match ty.kind(self.interner) {
+            &TyKind::Bound(bound) => Ok(self.bound_var(bound, outer_binder).to_ty(self.interner)),
+            _ => ty.fold_inner_with(self, outer_binder),
+        }
+    }
+
+    fn fold_infer_var(&mut self, infer: InferVar<I>, _: DebruijnIdx) -> LogicResult<Ty<I>> {
+        Ok(self.infer_var(infer).to_ty(self.interner))
+    }
+
+    fn fold_lifetime(
+        &mut self,
+        lifetime: Lifetime<I>,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Lifetime<I>> {
+        match lifetime.data(self.interner) {
+            &LifetimeData::Bound(bound) =>
+                Ok(self.bound_var(bound, outer_binder).to_lifetime(self.interner)),
+            _ => lifetime.fold_inner_with(self, outer_binder),
+        }
+    }
+
+    fn fold_infer_lifetime_var(
+        &mut self,
+        infer: InferVar<I>,
+        _: DebruijnIdx,
+    ) -> LogicResult<Lifetime<I>> {
+        Ok(self.infer_var(infer).to_lifetime(self.interner))
+    }
+
+    fn fold_const(
+        &mut self,
+        constant: Const<I>,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Const<I>> {
+        match constant.data(self.interner) {
+            &ConstData::Bound(bound) =>
+                Ok(self.bound_var(bound, outer_binder).to_const(self.interner)),
+            _ => constant.fold_inner_with(self, outer_binder),
+        }
+    }
+
+    fn fold_infer_const_var(
+        &mut self,
+        infer: InferVar<I>,
+        _: DebruijnIdx,
+    ) -> LogicResult<Const<I>> {
+        Ok(self.infer_var(infer).to_const(self.interner))
+    }
+}
+
+impl<I: Interner> Program<I> {
+    /// removes the clauses that are alpha-equivalent to an earlier clause
+    pub fn dedup_clauses(&mut self) {
+        let interner = self.interner;
+        let mut seen = HashSet::new();
+        let clauses = self.clauses.iter().filter(|clause| match clause.data(interner) {
+            ClauseData::Implies(implication) => seen.insert(implication.alpha_normalized(interner)),
+        });
+        self.clauses = Clauses::intern(interner, clauses.cloned().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,241 @@
+//! a hash-consing interner, every distinct value is allocated once in an arena so interned
+//! values are `Copy` and compared and hashed by address rather than by walking the whole tree
+use crate::*;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt::{self, Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use typed_arena::Arena;
+
+/// A value allocated in [`Arenas`]. Equal values share an allocation, so two handles are equal
+/// exactly when they point to the same place.
+pub struct Interned<'a, T: ?Sized>(&'a T);
+
+impl<'a, T: ?Sized> Interned<'a, T> {
+    pub fn get(self) -> &'a T {
+        self.0
+    }
+}
+
+impl<'a, T: ?Sized> Copy for Interned<'a, T> {}
+
+impl<'a, T: ?Sized> Clone for Interned<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T: ?Sized> PartialEq for Interned<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0)
+    }
+}
+
+impl<'a, T: ?Sized> Eq for Interned<'a, T> {}
+
+impl<'a, T: ?Sized> Hash for Interned<'a, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::ptr::hash(self.0, state)
+    }
+}
+
+impl<'a, T: ?Sized + Debug> Debug for Interned<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+struct InternSet<'a, T> {
+    arena: Arena<T>,
+    set: RefCell<HashSet<&'a T>>,
+}
+
+impl<'a, T> Default for InternSet<'a, T> {
+    fn default() -> Self {
+        Self { arena: Arena::new(), set: Default::default() }
+    }
+}
+
+impl<'a, T: Eq + Hash> InternSet<'a, T> {
+    // the children of `value` are interned already, so hashing and comparing it is shallow
+    fn intern(&'a self, value: T) -> Interned<'a, T> {
+        if let Some(&interned) = self.set.borrow().get(&value) {
+            return Interned(interned);
+        }
+        let interned = &*self.arena.alloc(value);
+        self.set.borrow_mut().insert(interned);
+        Interned(interned)
+    }
+}
+
+struct SliceInternSet<'a, T> {
+    arena: Arena<T>,
+    set: RefCell<HashSet<&'a [T]>>,
+}
+
+impl<'a, T> Default for SliceInternSet<'a, T> {
+    fn default() -> Self {
+        Self { arena: Arena::new(), set: Default::default() }
+    }
+}
+
+impl<'a, T: Eq + Hash> SliceInternSet<'a, T> {
+    fn intern(&'a self, values: impl IntoIterator<Item = T>) -> Interned<'a, [T]> {
+        let values = values.into_iter().collect::<Vec<_>>();
+        if let Some(&interned) = self.set.borrow().get(values.as_slice()) {
+            return Interned(interned);
+        }
+        let interned = &*self.arena.alloc_extend(values);
+        self.set.borrow_mut().insert(interned);
+        Interned(interned)
+    }
+}
+
+/// The storage of an [`ArenaInterner`], everything interned lives as long as this does.
+#[derive(Default)]
+pub struct Arenas<'a> {
+    goals: InternSet<'a, GoalData<ArenaInterner<'a>>>,
+    goal_lists: SliceInternSet<'a, Goal<ArenaInterner<'a>>>,
+    clauses: InternSet<'a, ClauseData<ArenaInterner<'a>>>,
+    clause_lists: SliceInternSet<'a, Clause<ArenaInterner<'a>>>,
+    tys: InternSet<'a, TyData<ArenaInterner<'a>>>,
+    lifetimes: InternSet<'a, LifetimeData<ArenaInterner<'a>>>,
+    consts: InternSet<'a, ConstData<ArenaInterner<'a>>>,
+    generic_args: InternSet<'a, GenericArgData<ArenaInterner<'a>>>,
+    substs: SliceInternSet<'a, GenericArg<ArenaInterner<'a>>>,
+    variables: SliceInternSet<'a, Variable<ArenaInterner<'a>>>,
+}
+
+/// an interner that hash-conses everything into [`Arenas`]
+#[derive(Copy, Clone)]
+pub struct ArenaInterner<'a> {
+    arenas: &'a Arenas<'a>,
+}
+
+impl<'a> ArenaInterner<'a> {
+    pub fn new(arenas: &'a Arenas<'a>) -> Self {
+        Self { arenas }
+    }
+}
+
+impl<'a> PartialEq for ArenaInterner<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.arenas, other.arenas)
+    }
+}
+
+impl<'a> Eq for ArenaInterner<'a> {}
+
+impl<'a> Hash for ArenaInterner<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::ptr::hash(self.arenas, state)
+    }
+}
+
+impl<'a> Debug for ArenaInterner<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "ArenaInterner({:p})", self.arenas)
+    }
+}
+
+impl<'a> Interner for ArenaInterner<'a> {
+    type InternedClause = Interned<'a, ClauseData<Self>>;
+    type InternedClauses = Interned<'a, [Clause<Self>]>;
+    type InternedGoal = Interned<'a, GoalData<Self>>;
+    type InternedGoals = Interned<'a, [Goal<Self>]>;
+    type InternedConst = Interned<'a, ConstData<Self>>;
+    type InternedGenericArg = Interned<'a, GenericArgData<Self>>;
+    type InternedLifetime = Interned<'a, LifetimeData<Self>>;
+    type InternedSubst = Interned<'a, [GenericArg<Self>]>;
+    type InternedTy = Interned<'a, TyData<Self>>;
+    type InternedVariables = Interned<'a, [Variable<Self>]>;
+
+    fn goal_data(self, goal: &Self::InternedGoal) -> &GoalData<Self> {
+        goal.get()
+    }
+
+    fn goals(self, goals: &Self::InternedGoals) -> &[Goal<Self>] {
+        goals.get()
+    }
+
+    fn intern_goal(self, goal: GoalData<Self>) -> Self::InternedGoal {
+        self.arenas.goals.intern(goal)
+    }
+
+    fn intern_goals(self, goals: impl IntoIterator<Item = Goal<Self>>) -> Self::InternedGoals {
+        self.arenas.goal_lists.intern(goals)
+    }
+
+    fn clause_data(self, clause: &Self::InternedClause) -> &ClauseData<Self> {
+        clause.get()
+    }
+
+    fn clauses(self, clauses: &Self::InternedClauses) -> &[Clause<Self>] {
+        clauses.get()
+    }
+
+    fn intern_clause(self, clause: ClauseData<Self>) -> Self::InternedClause {
+        self.arenas.clauses.intern(clause)
+    }
+
+    fn intern_clauses(
+        self,
+        clauses: impl IntoIterator<Item = Clause<Self>>,
+    ) -> Self::InternedClauses {
+        self.arenas.clause_lists.intern(clauses)
+    }
+
+    fn ty_data(self, ty: &Self::InternedTy) -> &TyData<Self> {
+        ty.get()
+    }
+
+    fn intern_ty(self, ty: TyData<Self>) -> Self::InternedTy {
+        self.arenas.tys.intern(ty)
+    }
+
+    fn lifetime_data(self, lifetime: &Self::InternedLifetime) -> &LifetimeData<Self> {
+        lifetime.get()
+    }
+
+    fn intern_lifetime(self, lifetime: LifetimeData<Self>) -> Self::InternedLifetime {
+        self.arenas.lifetimes.intern(lifetime)
+    }
+
+    fn const_data(self, constant: &Self::InternedConst) -> &ConstData<Self> {
+        constant.get()
+    }
+
+    fn intern_const(self, constant: ConstData<Self>) -> Self::InternedConst {
+        self.arenas.consts.intern(constant)
+    }
+
+    fn generic_arg_data(self, arg: &Self::InternedGenericArg) -> &GenericArgData<Self> {
+        arg.get()
+    }
+
+    fn intern_generic_arg(self, arg: GenericArgData<Self>) -> Self::InternedGenericArg {
+        self.arenas.generic_args.intern(arg)
+    }
+
+    fn subst_data(self, subst: &Self::InternedSubst) -> &[GenericArg<Self>] {
+        subst.get()
+    }
+
+    fn intern_subst(
+        self,
+        subst: impl IntoIterator<Item = GenericArg<Self>>,
+    ) -> Self::InternedSubst {
+        self.arenas.substs.intern(subst)
+    }
+
+    fn variables(self, vars: &Self::InternedVariables) -> &[Variable<Self>] {
+        vars.get()
+    }
+
+    fn intern_variables(
+        self,
+        vars: impl IntoIterator<Item = Variable<Self>>,
+    ) -> Self::InternedVariables {
+        self.arenas.variables.intern(vars)
+    }
+}
@@ -0,0 +1,107 @@
+//! flags summarizing what a type contains, computed once when the type is interned so that
+//! folders can skip the types that contain nothing for them to fold
+use crate::*;
+
+#[derive(Hash, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TyFlags {
+    /// whether the type contains an inference variable of any kind
+    pub has_infer: bool,
+    /// whether the type contains a placeholder of any kind
+    pub has_placeholder: bool,
+    /// one more than the largest debruijn index of the bound variables of the type, or zero if
+    /// it has none. Types don't bind variables themselves, so these all refer to outer binders
+    pub outer_binder: usize,
+    /// the number of types, lifetimes and consts in the type, including itself
+    pub size: usize,
+}
+
+impl TyFlags {
+    const LEAF: Self = Self { has_infer: false, has_placeholder: false, outer_binder: 0, size: 1 };
+    const INFER: Self = Self { has_infer: true, ..Self::LEAF };
+    const PLACEHOLDER: Self = Self { has_placeholder: true, ..Self::LEAF };
+
+    fn bound(bound: BoundVar) -> Self {
+        Self { outer_binder: bound.debruijn.index() + 1, ..Self::LEAF }
+    }
+
+    /// whether any bound variable refers to a binder outside of the innermost `depth` binders
+    pub fn has_bound_above(self, depth: usize) -> bool {
+        self.outer_binder > depth
+    }
+
+    pub(crate) fn of_kind<I: Interner>(interner: I, kind: &TyKind<I>) -> Self {
+        let leaf = Self::LEAF;
+        match kind {
+            &TyKind::Bound(bound) => Self::bound(bound),
+            TyKind::Infer(..) => Self::INFER,
+            TyKind::Placeholder(..) => Self::PLACEHOLDER,
+            TyKind::Never => leaf,
+            TyKind::Structure(_, args)
+            | TyKind::Tuple(args)
+            | TyKind::Opaque(OpaqueTy { args, .. }) => leaf.with_args(interner, args),
+            TyKind::Projection(ProjectionTy { ty, trait_ref, .. }) =>
+                leaf.with(ty.flags(interner)).with_args(interner, &trait_ref.args),
+            TyKind::Ref(_, lifetime, ty) =>
+                leaf.with(lifetime.flags(interner)).with(ty.flags(interner)),
+            TyKind::Slice(ty) => leaf.with(ty.flags(interner)),
+            TyKind::Array(ty, len) => leaf.with(ty.flags(interner)).with(len.flags(interner)),
+            TyKind::Fn(FnSig { params, ret }) =>
+                leaf.with_args(interner, params).with(ret.flags(interner)),
+            TyKind::Dyn(DynTy { bounds }) =>
+                bounds.iter().fold(leaf, |flags, bound| flags.with_args(interner, &bound.args)),
+        }
+    }
+
+    /// the flags of a type that contains what both `self` and `other` describe
+    fn with(self, other: Self) -> Self {
+        Self {
+            has_infer: self.has_infer || other.has_infer,
+            has_placeholder: self.has_placeholder || other.has_placeholder,
+            outer_binder: self.outer_binder.max(other.outer_binder),
+            size: self.size + other.size,
+        }
+    }
+
+    fn with_args<I: Interner>(self, interner: I, args: &Subst<I>) -> Self {
+        args.iter().fold(self, |flags, arg| flags.with(arg.flags(interner)))
+    }
+}
+
+impl<I: Interner> Ty<I> {
+    pub fn flags(&self, interner: I) -> TyFlags {
+        interner.ty_data(self).flags
+    }
+}
+
+// lifetimes and consts have no children, so their flags are cheap enough to not be cached
+impl<I: Interner> Lifetime<I> {
+    pub fn flags(&self, interner: I) -> TyFlags {
+        match self.data(interner) {
+            &LifetimeData::Bound(bound) => TyFlags::bound(bound),
+            LifetimeData::Infer(..) => TyFlags::INFER,
+            LifetimeData::Placeholder(..) => TyFlags::PLACEHOLDER,
+            LifetimeData::Static => TyFlags::LEAF,
+        }
+    }
+}
+
+impl<I: Interner> Const<I> {
+    pub fn flags(&self, interner: I) -> TyFlags {
+        match self.data(interner) {
+            &ConstData::Bound(bound) => TyFlags::bound(bound),
+            ConstData::Infer(..) => TyFlags::INFER,
+            ConstData::Placeholder(..) => TyFlags::PLACEHOLDER,
+            ConstData::Value(..) => TyFlags::LEAF,
+        }
+    }
+}
+
+impl<I: Interner> GenericArg<I> {
+    pub fn flags(&self, interner: I) -> TyFlags {
+        match self.data(interner) {
+            GenericArgData::Ty(ty) => ty.flags(interner),
+            GenericArgData::Lifetime(lifetime) => lifetime.flags(interner),
+            GenericArgData::Const(constant) => constant.flags(interner),
+        }
+    }
+}
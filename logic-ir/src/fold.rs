@@ -1,50 +1,172 @@
 use crate::{Interner, LogicResult};
 use logic_ir::*;
 
+/// A value that can be rebuilt by a [`Folder`]. `outer_binder` is the number of binders that
+/// have been entered within the value being folded, so bound variables with a smaller debruijn
+/// index are bound within it and the others are free.
 pub trait Fold<I: Interner>: Sized + Debug {
     type Folded: Debug = Self;
-    fn fold_with<F: Folder<I>>(self, folder: &mut F) -> LogicResult<Self::Folded>;
+    fn fold_with<F: Folder<I>>(
+        self,
+        folder: &mut F,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Self::Folded>;
 }
 
 pub trait FoldInner<I: Interner>: Fold<I> {
-    fn fold_inner_with<F: Folder<I>>(self, folder: &mut F) -> LogicResult<Self::Folded>;
+    fn fold_inner_with<F: Folder<I>>(
+        self,
+        folder: &mut F,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Self::Folded>;
 }
 
 pub trait Folder<I: Interner>: Sized {
     fn interner(&self) -> I;
 
-    fn fold_ty(&mut self, ty: Ty<I>) -> LogicResult<Ty<I>> {
-        ty.fold_inner_with(self)
+    fn fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIdx) -> LogicResult<Ty<I>> {
+        ty.fold_inner_with(self, outer_binder)
     }
 
-    fn fold_infer_var(&mut self, infer: InferVar<I>) -> LogicResult<Ty<I>> {
+    fn fold_infer_var(
+        &mut self,
+        infer: InferVar<I>,
+        _outer_binder: DebruijnIdx,
+    ) -> LogicResult<Ty<I>> {
         Ok(infer.to_ty(self.interner()))
     }
 
+    fn fold_lifetime(
+        &mut self,
+        lifetime: Lifetime<I>,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Lifetime<I>> {
+        lifetime.fold_inner_with(self, outer_binder)
+    }
+
+    fn fold_infer_lifetime_var(
+        &mut self,
+        infer: InferVar<I>,
+        _outer_binder: DebruijnIdx,
+    ) -> LogicResult<Lifetime<I>> {
+        Ok(infer.to_lifetime(self.interner()))
+    }
+
+    fn fold_const(
+        &mut self,
+        constant: Const<I>,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Const<I>> {
+        constant.fold_inner_with(self, outer_binder)
+    }
+
+    fn fold_infer_const_var(
+        &mut self,
+        infer: InferVar<I>,
+        _outer_binder: DebruijnIdx,
+    ) -> LogicResult<Const<I>> {
+        Ok(infer.to_const(self.interner()))
+    }
+
+    /// folds a value that isn't under any binders
     fn fold<F: Fold<I>>(&mut self, foldable: F) -> LogicResult<F::Folded> {
-        foldable.fold_with(self)
+        foldable.fold_with(self, DebruijnIdx::ZERO)
     }
 }
 
 impl<I: Interner> Fold<I> for Ty<I> {
-    fn fold_with<F: Folder<I>>(self, folder: &mut F) -> LogicResult<Self::Folded> {
-        folder.fold_ty(self)
+    fn fold_with<F: Folder<I>>(
+        self,
+        folder: &mut F,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Self::Folded> {
+        folder.fold_ty(self, outer_binder)
     }
 }
 
 impl<I: Interner> FoldInner<I> for Ty<I> {
-    fn fold_inner_with<F: Folder<I>>(self, folder: &mut F) -> LogicResult<Self::Folded> {
+    fn fold_inner_with<F: Folder<I>>(
+        self,
+        folder: &mut F,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Self::Folded> {
         let interner = folder.interner();
         let kind = match self.kind(interner) {
-            // TODO bound properly
-            TyKind::Infer(infer) => return folder.fold_infer_var(infer.clone()),
-            TyKind::Bound(bound) => TyKind::Bound(bound.clone()),
-            TyKind::Structure(f, xs) => TyKind::Structure(f.clone(), xs.clone().fold_with(folder)?),
+            TyKind::Infer(infer) => return folder.fold_infer_var(*infer, outer_binder),
+            TyKind::Bound(bound) => TyKind::Bound(*bound),
+            TyKind::Placeholder(placeholder) => TyKind::Placeholder(*placeholder),
+            TyKind::Structure(f, xs) =>
+                TyKind::Structure(f.clone(), xs.clone().fold_with(folder, outer_binder)?),
+            TyKind::Projection(projection) =>
+                TyKind::Projection(projection.clone().fold_with(folder, outer_binder)?),
+            TyKind::Opaque(opaque) =>
+                TyKind::Opaque(opaque.clone().fold_with(folder, outer_binder)?),
+            TyKind::Tuple(tys) => TyKind::Tuple(tys.clone().fold_with(folder, outer_binder)?),
+            &TyKind::Ref(mutability, ref lifetime, ref ty) => TyKind::Ref(
+                mutability,
+                lifetime.clone().fold_with(folder, outer_binder)?,
+                ty.clone().fold_with(folder, outer_binder)?,
+            ),
+            TyKind::Slice(ty) => TyKind::Slice(ty.clone().fold_with(folder, outer_binder)?),
+            TyKind::Array(ty, len) => TyKind::Array(
+                ty.clone().fold_with(folder, outer_binder)?,
+                len.clone().fold_with(folder, outer_binder)?,
+            ),
+            TyKind::Fn(sig) => TyKind::Fn(sig.clone().fold_with(folder, outer_binder)?),
+            TyKind::Never => TyKind::Never,
+            TyKind::Dyn(dyn_ty) => TyKind::Dyn(dyn_ty.clone().fold_with(folder, outer_binder)?),
         };
         Ok(kind.intern(interner))
     }
 }
 
+impl<I: Interner> Fold<I> for Lifetime<I> {
+    fn fold_with<F: Folder<I>>(
+        self,
+        folder: &mut F,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Self::Folded> {
+        folder.fold_lifetime(self, outer_binder)
+    }
+}
+
+impl<I: Interner> FoldInner<I> for Lifetime<I> {
+    fn fold_inner_with<F: Folder<I>>(
+        self,
+        folder: &mut F,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Self::Folded> {
+        match self.data(folder.interner()) {
+            &LifetimeData::Infer(infer) => folder.fold_infer_lifetime_var(infer, outer_binder),
+            LifetimeData::Bound(..) | LifetimeData::Placeholder(..) | LifetimeData::Static =>
+                Ok(self),
+        }
+    }
+}
+
+impl<I: Interner> Fold<I> for Const<I> {
+    fn fold_with<F: Folder<I>>(
+        self,
+        folder: &mut F,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Self::Folded> {
+        folder.fold_const(self, outer_binder)
+    }
+}
+
+impl<I: Interner> FoldInner<I> for Const<I> {
+    fn fold_inner_with<F: Folder<I>>(
+        self,
+        folder: &mut F,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Self::Folded> {
+        match self.data(folder.interner()) {
+            &ConstData::Infer(infer) => folder.fold_infer_const_var(infer, outer_binder),
+            ConstData::Bound(..) | ConstData::Placeholder(..) | ConstData::Value(..) => Ok(self),
+        }
+    }
+}
+
 impl<I, T> Fold<I> for Canonical<T>
 where
     I: Interner,
@@ -53,22 +175,94 @@ where
 {
     type Folded = Canonical<T::Folded>;
 
-    fn fold_with<F: Folder<I>>(self, folder: &mut F) -> LogicResult<Self::Folded> {
-        Ok(Canonical { value: self.value.fold_with(folder)?, binders: self.binders })
+    fn fold_with<F: Folder<I>>(
+        self,
+        folder: &mut F,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Self::Folded> {
+        let value = self.value.fold_with(folder, outer_binder.shifted_in())?;
+        Ok(Canonical { value, binders: self.binders })
     }
 }
 
 impl<I: Interner> Fold<I> for Subst<I> {
     type Folded = Self;
 
-    fn fold_with<F: Folder<I>>(self, folder: &mut F) -> LogicResult<Self::Folded> {
+    fn fold_with<F: Folder<I>>(
+        self,
+        folder: &mut F,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Self::Folded> {
         let interner = folder.interner();
-        let new_subst =
-            self.iter().cloned().map(|arg| arg.fold_with(folder)).collect::<Result<Vec<_>, _>>()?;
+        let new_subst = self
+            .iter()
+            .cloned()
+            .map(|arg| arg.fold_with(folder, outer_binder))
+            .collect::<Result<Vec<_>, _>>()?;
         Ok(Subst::intern(interner, new_subst))
     }
 }
 
+impl<I: Interner, T: Fold<I>> Fold<I> for Vec<T> {
+    type Folded = Vec<T::Folded>;
+
+    fn fold_with<F: Folder<I>>(
+        self,
+        folder: &mut F,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Self::Folded> {
+        self.into_iter().map(|value| value.fold_with(folder, outer_binder)).collect()
+    }
+}
+
+impl<I: Interner> Fold<I> for Clauses<I> {
+    type Folded = Self;
+
+    fn fold_with<F: Folder<I>>(
+        self,
+        folder: &mut F,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Self::Folded> {
+        let interner = folder.interner();
+        let clauses = self
+            .iter()
+            .cloned()
+            .map(|clause| clause.fold_with(folder, outer_binder))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Clauses::intern(interner, clauses))
+    }
+}
+
+impl<I: Interner> Fold<I> for Environment<I> {
+    fn fold_with<F: Folder<I>>(
+        self,
+        folder: &mut F,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Self::Folded> {
+        Ok(Environment::new(self.clauses.fold_with(folder, outer_binder)?))
+    }
+}
+
+impl<I, G> Fold<I> for InEnvironment<G>
+where
+    I: Interner,
+    G: Fold<I> + HasInterner<Interner = I>,
+    G::Folded: HasInterner<Interner = I>,
+{
+    type Folded = InEnvironment<G::Folded>;
+
+    fn fold_with<F: Folder<I>>(
+        self,
+        folder: &mut F,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Self::Folded> {
+        Ok(InEnvironment::new(
+            self.environment.fold_with(folder, outer_binder)?,
+            self.goal.fold_with(folder, outer_binder)?,
+        ))
+    }
+}
+
 impl<I: Interner, T> Fold<I> for Binders<T>
 where
     T: HasInterner<Interner = I> + Fold<I>,
@@ -76,17 +270,26 @@ where
 {
     type Folded = Binders<T::Folded>;
 
-    fn fold_with<F: Folder<I>>(self, folder: &mut F) -> LogicResult<Self::Folded> {
-        Ok(Binders::new(self.binders, self.quantified.fold_with(folder)?))
+    fn fold_with<F: Folder<I>>(
+        self,
+        folder: &mut F,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Self::Folded> {
+        let (binders, value) = self.split();
+        Ok(Binders::new(binders, value.fold_with(folder, outer_binder.shifted_in())?))
     }
 }
 
 macro_rules! fold_interned {
     ($interned:ty) => {
         impl<I: Interner> Fold<I> for $interned {
-            fn fold_with<F: Folder<I>>(self, folder: &mut F) -> LogicResult<Self::Folded> {
+            fn fold_with<F: Folder<I>>(
+                self,
+                folder: &mut F,
+                outer_binder: DebruijnIdx,
+            ) -> LogicResult<Self::Folded> {
                 let interner = folder.interner();
-                let folded = self.data(interner).clone().fold_with(folder)?;
+                let folded = self.data(interner).clone().fold_with(folder, outer_binder)?;
                 Ok(Self::intern(interner, folded))
             }
         }
@@ -95,6 +298,7 @@ macro_rules! fold_interned {
 
 fold_interned!(Clause<I>);
 fold_interned!(Goal<I>);
+fold_interned!(GenericArg<I>);
 
 macro_rules! copy_fold {
     ($t:ty) => {
@@ -104,6 +308,7 @@ macro_rules! copy_fold {
             fn fold_with<F: $crate::fold::Folder<I>>(
                 self,
                 _folder: &mut F,
+                _outer_binder: $crate::DebruijnIdx,
             ) -> $crate::LogicResult<Self::Folded> {
                 Ok(self)
             }
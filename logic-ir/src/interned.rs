@@ -138,10 +138,23 @@ impl<I: Interner> Ty<I> {
 interned!(goal_data => GoalData, intern_goal => Goal, InternedGoal, dbg_goal);
 interned!(clause_data => ClauseData, intern_clause => Clause, InternedClause, dbg_clause);
 interned!(ty_data => TyData, intern_ty => Ty, InternedTy, dbg_ty);
+interned!(
+    lifetime_data => LifetimeData,
+    intern_lifetime => Lifetime,
+    InternedLifetime,
+    dbg_lifetime
+);
+interned!(const_data => ConstData, intern_const => Const, InternedConst, dbg_const);
+interned!(
+    generic_arg_data => GenericArgData,
+    intern_generic_arg => GenericArg,
+    InternedGenericArg,
+    dbg_generic_arg
+);
 
 interned_slice!(
     Subst,
-    subst_data => Ty<I>,
+    subst_data => GenericArg<I>,
     intern_subst => InternedSubst
 );
 
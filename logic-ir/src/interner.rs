@@ -16,6 +16,9 @@ pub trait Interner: Copy + Eq + Hash + Debug {
     type InternedClause: Internable;
     type InternedClauses: Internable;
     type InternedTy: Internable;
+    type InternedLifetime: Internable;
+    type InternedConst: Internable;
+    type InternedGenericArg: Internable;
     type InternedSubst: Internable;
     type InternedVariables: Internable;
 
@@ -33,9 +36,22 @@ pub trait Interner: Copy + Eq + Hash + Debug {
     ) -> Self::InternedClauses;
 
     fn ty_data<'a>(self, ty: &'a Self::InternedTy) -> &'a TyData<Self>;
-    fn subst_data<'a>(self, tys: &'a Self::InternedSubst) -> &'a [Ty<Self>];
     fn intern_ty(self, ty: TyData<Self>) -> Self::InternedTy;
-    fn intern_subst(self, tys: impl IntoIterator<Item = Ty<Self>>) -> Self::InternedSubst;
+
+    fn lifetime_data<'a>(self, lifetime: &'a Self::InternedLifetime) -> &'a LifetimeData<Self>;
+    fn intern_lifetime(self, lifetime: LifetimeData<Self>) -> Self::InternedLifetime;
+
+    fn const_data<'a>(self, constant: &'a Self::InternedConst) -> &'a ConstData<Self>;
+    fn intern_const(self, constant: ConstData<Self>) -> Self::InternedConst;
+
+    fn generic_arg_data<'a>(self, arg: &'a Self::InternedGenericArg) -> &'a GenericArgData<Self>;
+    fn intern_generic_arg(self, arg: GenericArgData<Self>) -> Self::InternedGenericArg;
+
+    fn subst_data<'a>(self, args: &'a Self::InternedSubst) -> &'a [GenericArg<Self>];
+    fn intern_subst(
+        self,
+        args: impl IntoIterator<Item = GenericArg<Self>>,
+    ) -> Self::InternedSubst;
 
     fn variables<'a>(self, vars: &'a Self::InternedVariables) -> &'a [Variable<Self>];
     fn intern_variables<'a>(
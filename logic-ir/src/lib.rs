@@ -11,30 +11,47 @@ mod macros;
 extern crate self as logic_ir;
 
 // https://www.youtube.com/watch?v=RwBiHLoQ3E4&ab_channel=PapersWeLove
+mod alpha;
+mod arena;
 mod debug;
+mod flags;
 mod interned;
 mod interner;
 mod lowering;
+mod orphan;
+mod trait_lowering;
 
 mod fold;
+mod print;
+pub mod structural_hash;
 mod subst;
 pub mod tls;
+mod visit;
 pub mod zip;
 
 pub use fold::*;
+pub use print::*;
+pub use structural_hash::StructuralHash;
 pub use subst::*;
+pub use trait_lowering::*;
+pub use visit::*;
 pub use zip::*;
 
+pub use alpha::AlphaEq;
+pub use arena::{ArenaInterner, Arenas, Interned};
 pub use debug::DebugCtxt;
+pub use flags::TyFlags;
 use indexed_vec::{newtype_index, Idx};
 pub use interned::*;
 pub use interner::*;
-pub use logic_parse::{Ident, Symbol, Var};
-pub use lowering::{lower_ast, lower_goal};
+pub use logic_parse::{ConstValue, Ident, Mutability, Symbol, Var};
+pub use lowering::{
+    lower_ast, lower_ast_with, lower_goal, lower_goal_in, lower_goal_in_with, LoweringError,
+};
 use std::fmt::{self, Debug, Display, Formatter};
 use std::marker::PhantomData;
 pub use std::ops::{Deref, DerefMut};
-use std::rc::Rc;
+use std::sync::Arc;
 
 /// an interner that doesn't really intern anything
 // the default "interner" for internal use
@@ -43,14 +60,17 @@ pub struct LogicInterner;
 
 impl Interner for LogicInterner {
     // type DomainGoal = GenericTerm<Self>;
-    // wrapped in `Rc` to make it cheaply cloneable
-    // a proper interner should probably use copyable references
-    type InternedClause = Rc<ClauseData<Self>>;
+    // wrapped in `Arc` to make it cheaply cloneable and shareable across threads
+    // see `ArenaInterner` for one that uses copyable references
+    type InternedClause = Arc<ClauseData<Self>>;
     type InternedClauses = Vec<Clause<Self>>;
-    type InternedGoal = Rc<GoalData<Self>>;
+    type InternedGoal = Arc<GoalData<Self>>;
     type InternedGoals = Vec<Goal<Self>>;
-    type InternedSubst = Vec<Ty<Self>>;
-    type InternedTy = Rc<TyData<Self>>;
+    type InternedConst = Arc<ConstData<Self>>;
+    type InternedGenericArg = Arc<GenericArgData<Self>>;
+    type InternedLifetime = Arc<LifetimeData<Self>>;
+    type InternedSubst = Vec<GenericArg<Self>>;
+    type InternedTy = Arc<TyData<Self>>;
     type InternedVariables = Vec<Variable<Self>>;
 
     fn goal_data<'a>(self, goal: &'a Self::InternedGoal) -> &'a GoalData<Self> {
@@ -62,7 +82,7 @@ impl Interner for LogicInterner {
     }
 
     fn intern_goal(self, goal: GoalData<Self>) -> Self::InternedGoal {
-        Rc::new(goal)
+        Arc::new(goal)
     }
 
     fn intern_goals(self, goals: impl IntoIterator<Item = Goal<Self>>) -> Self::InternedGoals {
@@ -78,7 +98,7 @@ impl Interner for LogicInterner {
     }
 
     fn intern_clause(self, clause: ClauseData<Self>) -> Self::InternedClause {
-        Rc::new(clause)
+        Arc::new(clause)
     }
 
     fn intern_clauses(
@@ -92,15 +112,42 @@ impl Interner for LogicInterner {
         tys
     }
 
-    fn subst_data<'a>(self, subst: &'a Self::InternedSubst) -> &'a [Ty<Self>] {
-        subst.as_slice()
+    fn intern_ty(self, ty: TyData<Self>) -> Self::InternedTy {
+        Arc::new(ty)
     }
 
-    fn intern_ty(self, ty: TyData<Self>) -> Self::InternedTy {
-        Rc::new(ty)
+    fn lifetime_data<'a>(self, lifetime: &'a Self::InternedLifetime) -> &'a LifetimeData<Self> {
+        lifetime
+    }
+
+    fn intern_lifetime(self, lifetime: LifetimeData<Self>) -> Self::InternedLifetime {
+        Arc::new(lifetime)
     }
 
-    fn intern_subst(self, subst: impl IntoIterator<Item = Ty<Self>>) -> Self::InternedSubst {
+    fn const_data<'a>(self, constant: &'a Self::InternedConst) -> &'a ConstData<Self> {
+        constant
+    }
+
+    fn intern_const(self, constant: ConstData<Self>) -> Self::InternedConst {
+        Arc::new(constant)
+    }
+
+    fn generic_arg_data<'a>(self, arg: &'a Self::InternedGenericArg) -> &'a GenericArgData<Self> {
+        arg
+    }
+
+    fn intern_generic_arg(self, arg: GenericArgData<Self>) -> Self::InternedGenericArg {
+        Arc::new(arg)
+    }
+
+    fn subst_data<'a>(self, subst: &'a Self::InternedSubst) -> &'a [GenericArg<Self>] {
+        subst.as_slice()
+    }
+
+    fn intern_subst(
+        self,
+        subst: impl IntoIterator<Item = GenericArg<Self>>,
+    ) -> Self::InternedSubst {
         subst.into_iter().collect()
     }
 
@@ -118,6 +165,22 @@ impl Interner for LogicInterner {
 
 newtype_index!(InferIdx);
 newtype_index!(DebruijnIdx);
+newtype_index!(UniverseIdx);
+
+impl UniverseIdx {
+    /// the universe of the types that exist outside of any `forall`
+    pub const ROOT: Self = UniverseIdx(0);
+
+    #[must_use]
+    pub fn next(self) -> Self {
+        Self::new(self.index() + 1)
+    }
+
+    /// whether the names in universe `other` are visible from this universe
+    pub fn can_see(self, other: Self) -> bool {
+        self >= other
+    }
+}
 
 impl DebruijnIdx {
     pub const ONE: Self = DebruijnIdx(1);
@@ -136,18 +199,28 @@ impl DebruijnIdx {
     pub fn shifted_in_by(self, by: Self) -> Self {
         Self::new(self.index() + by.index())
     }
+
+    /// the inverse of `shifted_in_by`, `None` if this refers to one of the binders removed
+    pub fn shifted_out_by(self, by: Self) -> Option<Self> {
+        self.index().checked_sub(by.index()).map(Self::new)
+    }
 }
 
 /// top level program
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct Program<I: Interner> {
     pub clauses: Clauses<I>,
+    pub structs: Vec<StructDatum<I>>,
+    pub traits: Vec<TraitDatum<I>>,
+    pub impls: Vec<ImplDatum<I>>,
+    pub opaque_tys: Vec<OpaqueTyDatum<I>>,
     pub interner: I,
 }
 
 impl<I: Interner> Program<I> {
     pub fn new(interner: I, clauses: Clauses<I>) -> Self {
-        Self { interner, clauses }
+        let (structs, traits, impls, opaque_tys) = (vec![], vec![], vec![], vec![]);
+        Self { interner, clauses, structs, traits, impls, opaque_tys }
     }
 
     pub fn first_clause(&self) -> Clause<I> {
@@ -155,6 +228,80 @@ impl<I: Interner> Program<I> {
     }
 }
 
+/// `struct Name<T, ...> where ... {}`, the binders are the parameters of the struct
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StructDatum<I: Interner> {
+    /// the crate owning the struct, `None` for the local crate
+    pub krate: Option<Ident>,
+    /// whether the struct is transparent to the orphan rule, like `Box`
+    pub fundamental: bool,
+    pub name: Ident,
+    pub binders: Binders<StructDatumBound<I>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, HasInterner, Visit, StructuralHash)]
+pub struct StructDatumBound<I: Interner> {
+    pub where_clauses: Vec<Constraint<I>>,
+}
+
+/// `trait Name<T, ...> where ... { type Assoc; ... }`,
+/// the binders are `Self` followed by the trait's parameters
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TraitDatum<I: Interner> {
+    pub krate: Option<Ident>,
+    /// whether the impls of the trait may overlap
+    pub marker: bool,
+    pub name: Ident,
+    pub assoc_tys: Vec<Ident>,
+    /// whether the trait can be used in a `dyn` type
+    pub object_safe: bool,
+    pub binders: Binders<TraitDatumBound<I>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, HasInterner, Visit, StructuralHash)]
+pub struct TraitDatumBound<I: Interner> {
+    pub where_clauses: Vec<Constraint<I>>,
+}
+
+/// `impl<T, ...> Trait<...> for Ty where ... { type Assoc = Ty; ... }`,
+/// the binders are the parameters of the impl
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ImplDatum<I: Interner> {
+    pub krate: Option<Ident>,
+    /// whether more specific impls may specialize this one
+    pub default: bool,
+    pub binders: Binders<ImplDatumBound<I>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, HasInterner, Fold, Visit, StructuralHash)]
+pub struct ImplDatumBound<I: Interner> {
+    /// `Ty: Trait<...>`
+    pub header: ImplConstraint<I>,
+    pub where_clauses: Vec<Constraint<I>>,
+    pub assoc_ty_values: Vec<AssocTyValue<I>>,
+}
+
+/// `opaque type Name<T, ...>: Trait + ... = Ty;`, the binders are the parameters of the type
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OpaqueTyDatum<I: Interner> {
+    pub name: Ident,
+    pub binders: Binders<OpaqueTyDatumBound<I>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, HasInterner, Visit, StructuralHash)]
+pub struct OpaqueTyDatumBound<I: Interner> {
+    /// the traits the opaque type implements, its self type is implicit
+    pub bounds: Vec<TraitRef<I>>,
+    pub hidden_ty: Ty<I>,
+}
+
+/// `type Name = Ty;`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, HasInterner, Fold, Visit, StructuralHash)]
+pub struct AssocTyValue<I: Interner> {
+    pub name: Ident,
+    pub ty: Ty<I>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Quantifier {
     Exists,
@@ -162,13 +309,15 @@ pub enum Quantifier {
 }
 
 // intuitively "things we want to prove"
-#[derive(Clone, PartialEq, Eq, Hash, HasInterner, Zip, Fold)]
+#[derive(Clone, PartialEq, Eq, Hash, HasInterner, Zip, Fold, Visit, StructuralHash)]
 pub enum GoalData<I: Interner> {
     Quantified(Quantifier, Binders<Goal<I>>),
     DomainGoal(DomainGoal<I>),
     And(Goal<I>, Goal<I>),
     Or(Goal<I>, Goal<I>),
     Implies(Clause<I>, Goal<I>),
+    /// holds if the goal has no solution, which is only decided for ground goals
+    Not(Goal<I>),
     True,
 }
 
@@ -179,22 +328,23 @@ impl<I: Interner> Debug for GoalData<I> {
             GoalData::Quantified(quantifier, bound_goal) =>
                 write!(f, "{:?}{:?}", quantifier, bound_goal),
             GoalData::True => write!(f, "⊤"),
-            GoalData::And(_, _) => todo!(),
-            GoalData::Or(_, _) => todo!(),
-            GoalData::Implies(_, _) => todo!(),
+            GoalData::And(lhs, rhs) => write!(f, "{:?} ∧ {:?}", lhs, rhs),
+            GoalData::Or(lhs, rhs) => write!(f, "({:?} ∨ {:?})", lhs, rhs),
+            GoalData::Implies(clause, goal) => write!(f, "({:?}) => {:?}", clause, goal),
+            GoalData::Not(goal) => write!(f, "¬({:?})", goal),
         }
     }
 }
 
 #[derive(Hash, Clone, PartialEq, Eq)]
 pub struct TyData<I: Interner> {
-    // todo tyflags
     kind: TyKind<I>,
+    flags: TyFlags,
 }
 
 impl<I: Interner> TyData<I> {
-    pub fn new(kind: TyKind<I>) -> Self {
-        Self { kind }
+    pub fn new(interner: I, kind: TyKind<I>) -> Self {
+        Self { flags: TyFlags::of_kind(interner, &kind), kind }
     }
 }
 
@@ -204,11 +354,137 @@ impl<I: Interner> Debug for TyData<I> {
     }
 }
 
-#[derive(Hash, Clone, PartialEq, Eq)]
+#[derive(Hash, Clone, PartialEq, Eq, StructuralHash)]
 pub enum TyKind<I: Interner> {
     Bound(BoundVar),
     Infer(InferVar<I>),
+    /// a universally quantified type, which is only equal to itself
+    Placeholder(PlaceholderVar),
     Structure(Ident, Subst<I>),
+    /// an associated type, which is equal to whatever it normalizes to
+    Projection(ProjectionTy<I>),
+    /// an `impl Trait` type, which is only equal to itself unless `Reveal` holds
+    Opaque(OpaqueTy<I>),
+    /// the arguments are all types
+    Tuple(Subst<I>),
+    Ref(Mutability, Lifetime<I>, Ty<I>),
+    Slice(Ty<I>),
+    Array(Ty<I>, Const<I>),
+    Fn(FnSig<I>),
+    Never,
+    Dyn(DynTy<I>),
+}
+
+impl<I: Interner> TyKind<I> {
+    pub fn unit(interner: I) -> Self {
+        TyKind::Tuple(Subst::empty(interner))
+    }
+}
+
+/// the type of a function pointer, `fn(A, B) -> R`
+#[derive(Hash, Clone, PartialEq, Eq, Zip, Fold, Visit, HasInterner, StructuralHash)]
+pub struct FnSig<I: Interner> {
+    /// the arguments are all types
+    pub params: Subst<I>,
+    pub ret: Ty<I>,
+}
+
+impl<I: Interner> Debug for FnSig<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "fn({}) -> {:?}", util::join_dbg(self.params.as_slice(), ", "), self.ret)
+    }
+}
+
+/// a trait object, `dyn Trait + ...`,
+/// the erased self type is implicit so the bounds never mention it
+#[derive(Hash, Clone, PartialEq, Eq, Zip, Fold, Visit, HasInterner, StructuralHash)]
+pub struct DynTy<I: Interner> {
+    pub bounds: Vec<TraitRef<I>>,
+}
+
+impl<I: Interner> Debug for DynTy<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "dyn {}", util::join_dbg(&self.bounds, " + "))
+    }
+}
+
+/// `<Ty as Trait>::Name`
+#[derive(Hash, Clone, PartialEq, Eq, Zip, Fold, Visit, HasInterner, StructuralHash)]
+pub struct ProjectionTy<I: Interner> {
+    pub ty: Ty<I>,
+    pub trait_ref: TraitRef<I>,
+    pub name: Ident,
+}
+
+impl<I: Interner> Debug for ProjectionTy<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "<{:?} as {:?}>::{}", self.ty, self.trait_ref, self.name)
+    }
+}
+
+/// `Name<...>` of an `opaque type` declaration
+#[derive(Hash, Clone, PartialEq, Eq, Zip, Fold, Visit, HasInterner, StructuralHash)]
+pub struct OpaqueTy<I: Interner> {
+    pub name: Ident,
+    pub args: Subst<I>,
+}
+
+impl<I: Interner> Debug for OpaqueTy<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.name, util::fmt_generic_args(self.args.as_slice()))
+    }
+}
+
+/// a type that may be normalized to another type
+#[derive(Hash, Clone, PartialEq, Eq, Zip, Fold, Visit, HasInterner, StructuralHash)]
+pub enum AliasTy<I: Interner> {
+    Projection(ProjectionTy<I>),
+    Opaque(OpaqueTy<I>),
+}
+
+impl<I: Interner> Debug for AliasTy<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AliasTy::Projection(projection) => write!(f, "{:?}", projection),
+            AliasTy::Opaque(opaque) => write!(f, "{:?}", opaque),
+        }
+    }
+}
+
+/// The variable of a `forall` that has been instantiated in `universe`,
+/// each `forall` that is entered creates a new universe.
+#[derive(Hash, Clone, PartialEq, Eq, Copy)]
+pub struct PlaceholderVar {
+    pub universe: UniverseIdx,
+    pub index: usize,
+}
+
+impl PlaceholderVar {
+    pub fn to_ty<I: Interner>(self, interner: I) -> Ty<I> {
+        TyKind::Placeholder(self).intern(interner)
+    }
+
+    pub fn to_lifetime<I: Interner>(self, interner: I) -> Lifetime<I> {
+        LifetimeData::Placeholder(self).intern(interner)
+    }
+
+    pub fn to_const<I: Interner>(self, interner: I) -> Const<I> {
+        ConstData::Placeholder(self).intern(interner)
+    }
+
+    pub fn to_generic_arg<I: Interner>(self, interner: I, kind: VariableKind) -> GenericArg<I> {
+        match kind {
+            VariableKind::Ty => self.to_ty(interner).into_generic_arg(interner),
+            VariableKind::Lifetime => self.to_lifetime(interner).into_generic_arg(interner),
+            VariableKind::Const(..) => self.to_const(interner).into_generic_arg(interner),
+        }
+    }
+}
+
+impl Debug for PlaceholderVar {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "!{:?}.{:?}", self.universe, self.index)
+    }
 }
 
 #[derive(Hash, Clone, PartialEq, Eq, Copy)]
@@ -226,9 +502,33 @@ impl BoundVar {
         Self { debruijn: self.debruijn.shifted_in(), ..self }
     }
 
+    pub fn shifted_in_by(self, by: DebruijnIdx) -> Self {
+        Self { debruijn: self.debruijn.shifted_in_by(by), ..self }
+    }
+
+    pub fn shifted_out_by(self, by: DebruijnIdx) -> Option<Self> {
+        Some(Self { debruijn: self.debruijn.shifted_out_by(by)?, ..self })
+    }
+
     pub fn to_ty<I: Interner>(self, interner: I) -> Ty<I> {
         TyKind::Bound(self).intern(interner)
     }
+
+    pub fn to_lifetime<I: Interner>(self, interner: I) -> Lifetime<I> {
+        LifetimeData::Bound(self).intern(interner)
+    }
+
+    pub fn to_const<I: Interner>(self, interner: I) -> Const<I> {
+        ConstData::Bound(self).intern(interner)
+    }
+
+    pub fn to_generic_arg<I: Interner>(self, interner: I, kind: VariableKind) -> GenericArg<I> {
+        match kind {
+            VariableKind::Ty => self.to_ty(interner).into_generic_arg(interner),
+            VariableKind::Lifetime => self.to_lifetime(interner).into_generic_arg(interner),
+            VariableKind::Const(..) => self.to_const(interner).into_generic_arg(interner),
+        }
+    }
 }
 
 impl Debug for BoundVar {
@@ -247,6 +547,22 @@ impl<I: Interner> InferVar<I> {
     pub fn to_ty(self, interner: I) -> Ty<I> {
         TyKind::Infer(self).intern(interner)
     }
+
+    pub fn to_lifetime(self, interner: I) -> Lifetime<I> {
+        LifetimeData::Infer(self).intern(interner)
+    }
+
+    pub fn to_const(self, interner: I) -> Const<I> {
+        ConstData::Infer(self).intern(interner)
+    }
+
+    pub fn to_generic_arg(self, interner: I, kind: VariableKind) -> GenericArg<I> {
+        match kind {
+            VariableKind::Ty => self.to_ty(interner).into_generic_arg(interner),
+            VariableKind::Lifetime => self.to_lifetime(interner).into_generic_arg(interner),
+            VariableKind::Const(..) => self.to_const(interner).into_generic_arg(interner),
+        }
+    }
 }
 
 impl<I: Interner> ena::unify::UnifyKey for InferVar<I> {
@@ -267,8 +583,10 @@ impl<I: Interner> ena::unify::UnifyKey for InferVar<I> {
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum InferenceValue<I: Interner> {
-    Known(Ty<I>),
-    Unknown,
+    Known(GenericArg<I>),
+    /// an unknown type that may only name placeholders that are visible from the universe,
+    /// and for a const its type if it has one
+    Unknown(UniverseIdx, Option<ConstTy>),
 }
 
 impl<I: Interner> ena::unify::UnifyValue for InferenceValue<I> {
@@ -279,9 +597,10 @@ impl<I: Interner> ena::unify::UnifyValue for InferenceValue<I> {
     fn unify_values(x: &Self, y: &Self) -> Result<Self, Self::Error> {
         Ok(match (x, y) {
             (Self::Known(..), Self::Known(..)) => panic!("unifying two known values"),
-            (Self::Known(..), Self::Unknown) => x.clone(),
-            (Self::Unknown, Self::Known(..)) => y.clone(),
-            (Self::Unknown, Self::Unknown) => Self::Unknown,
+            (Self::Known(..), Self::Unknown(..)) => x.clone(),
+            (Self::Unknown(..), Self::Known(..)) => y.clone(),
+            // the types of two consts are checked to agree before they are unified
+            (&Self::Unknown(a, s), &Self::Unknown(b, t)) => Self::Unknown(a.min(b), s.or(t)),
         })
     }
 }
@@ -300,13 +619,145 @@ impl<I: Interner> Debug for InferVar<I> {
 
 impl<I: Interner> TyKind<I> {
     pub fn intern(self, interner: I) -> Ty<I> {
-        Ty::intern(interner, TyData::new(self))
+        Ty::intern(interner, TyData::new(interner, self))
+    }
+}
+
+#[derive(Hash, Clone, PartialEq, Eq, StructuralHash)]
+pub enum LifetimeData<I: Interner> {
+    Bound(BoundVar),
+    Infer(InferVar<I>),
+    /// a universally quantified lifetime, which is only known to be equal to itself
+    Placeholder(PlaceholderVar),
+    Static,
+}
+
+impl<I: Interner> LifetimeData<I> {
+    pub fn intern(self, interner: I) -> Lifetime<I> {
+        Lifetime::intern(interner, self)
+    }
+}
+
+impl<I: Interner> Debug for LifetimeData<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LifetimeData::Bound(bound) => write!(f, "'{:?}", bound),
+            LifetimeData::Infer(var) => write!(f, "'{:?}", var),
+            LifetimeData::Placeholder(placeholder) => write!(f, "'{:?}", placeholder),
+            LifetimeData::Static => write!(f, "'static"),
+        }
+    }
+}
+
+impl<I: Interner> Lifetime<I> {
+    pub fn into_generic_arg(self, interner: I) -> GenericArg<I> {
+        GenericArgData::Lifetime(self).intern(interner)
+    }
+}
+
+#[derive(Hash, Clone, PartialEq, Eq, StructuralHash)]
+pub enum ConstData<I: Interner> {
+    Bound(BoundVar),
+    Infer(InferVar<I>),
+    /// a universally quantified constant, which is only equal to itself
+    Placeholder(PlaceholderVar),
+    Value(ConstValue),
+}
+
+impl<I: Interner> ConstData<I> {
+    pub fn intern(self, interner: I) -> Const<I> {
+        Const::intern(interner, self)
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Fold, Zip)]
+impl<I: Interner> Debug for ConstData<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstData::Bound(bound) => write!(f, "const {:?}", bound),
+            ConstData::Infer(var) => write!(f, "const {:?}", var),
+            ConstData::Placeholder(placeholder) => write!(f, "const {:?}", placeholder),
+            ConstData::Value(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl<I: Interner> Const<I> {
+    pub fn into_generic_arg(self, interner: I) -> GenericArg<I> {
+        GenericArgData::Const(self).intern(interner)
+    }
+}
+
+impl<I: Interner> Ty<I> {
+    pub fn into_generic_arg(self, interner: I) -> GenericArg<I> {
+        GenericArgData::Ty(self).intern(interner)
+    }
+}
+
+/// an argument of a type, trait or predicate
+#[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, Zip, StructuralHash)]
 pub enum GenericArgData<I: Interner> {
     Ty(Ty<I>),
+    Lifetime(Lifetime<I>),
+    Const(Const<I>),
+}
+
+impl<I: Interner> GenericArgData<I> {
+    pub fn intern(self, interner: I) -> GenericArg<I> {
+        GenericArg::intern(interner, self)
+    }
+}
+
+impl<I: Interner> Debug for GenericArgData<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GenericArgData::Ty(ty) => write!(f, "{:?}", ty),
+            GenericArgData::Lifetime(lifetime) => write!(f, "{:?}", lifetime),
+            GenericArgData::Const(constant) => write!(f, "{:?}", constant),
+        }
+    }
+}
+
+impl<I: Interner> GenericArg<I> {
+    pub fn ty(&self, interner: I) -> Option<&Ty<I>> {
+        match self.data(interner) {
+            GenericArgData::Ty(ty) => Some(ty),
+            GenericArgData::Lifetime(..) | GenericArgData::Const(..) => None,
+        }
+    }
+
+    pub fn lifetime(&self, interner: I) -> Option<&Lifetime<I>> {
+        match self.data(interner) {
+            GenericArgData::Lifetime(lifetime) => Some(lifetime),
+            GenericArgData::Ty(..) | GenericArgData::Const(..) => None,
+        }
+    }
+
+    pub fn constant(&self, interner: I) -> Option<&Const<I>> {
+        match self.data(interner) {
+            GenericArgData::Const(constant) => Some(constant),
+            GenericArgData::Ty(..) | GenericArgData::Lifetime(..) => None,
+        }
+    }
+
+    pub fn assert_ty_ref(&self, interner: I) -> &Ty<I> {
+        self.ty(interner).unwrap_or_else(|| panic!("expected a type, found `{:?}`", self))
+    }
+
+    pub fn assert_lifetime_ref(&self, interner: I) -> &Lifetime<I> {
+        self.lifetime(interner).unwrap_or_else(|| panic!("expected a lifetime, found `{:?}`", self))
+    }
+
+    pub fn assert_const_ref(&self, interner: I) -> &Const<I> {
+        self.constant(interner).unwrap_or_else(|| panic!("expected a const, found `{:?}`", self))
+    }
+
+    pub fn kind(&self, interner: I) -> VariableKind {
+        match self.data(interner) {
+            GenericArgData::Ty(..) => VariableKind::Ty,
+            GenericArgData::Lifetime(..) => VariableKind::Lifetime,
+            GenericArgData::Const(..) => VariableKind::Const(None),
+        }
+    }
 }
 
 impl<I: Interner> Debug for TyKind<I> {
@@ -316,46 +767,207 @@ impl<I: Interner> Debug for TyKind<I> {
                 write!(f, "{}{}", functor, util::fmt_generic_args(args.as_slice())),
             TyKind::Infer(var) => write!(f, "{:?}", var),
             TyKind::Bound(bound) => write!(f, "{:?}", bound),
+            TyKind::Placeholder(placeholder) => write!(f, "{:?}", placeholder),
+            TyKind::Projection(projection) => write!(f, "{:?}", projection),
+            TyKind::Opaque(opaque) => write!(f, "{:?}", opaque),
+            TyKind::Tuple(tys) if tys.len() == 1 => write!(f, "({:?},)", tys.at(0)),
+            TyKind::Tuple(tys) => write!(f, "({})", util::join_dbg(tys.as_slice(), ", ")),
+            TyKind::Ref(mutability, lifetime, ty) =>
+                write!(f, "&{:?} {}{:?}", lifetime, mutability, ty),
+            TyKind::Slice(ty) => write!(f, "[{:?}]", ty),
+            TyKind::Array(ty, len) => write!(f, "[{:?}; {:?}]", ty, len),
+            TyKind::Fn(sig) => write!(f, "{:?}", sig),
+            TyKind::Never => write!(f, "!"),
+            TyKind::Dyn(dyn_ty) => write!(f, "{:?}", dyn_ty),
         }
     }
 }
 
+/// the values of the variables of a goal along with the region constraints
+/// that must also hold for the goal to hold, which the solver doesn't decide
+#[derive(Debug, Clone, PartialEq, Eq, Hash, HasInterner, Fold, Visit, StructuralHash)]
+pub struct ConstrainedSubst<I: Interner> {
+    pub subst: Subst<I>,
+    /// only ever outlives constraints
+    pub constraints: Vec<Constraint<I>>,
+}
+
 /// A value is canonicalized by relabelling unbound existential variables
 /// from zero in order of appearance.
 /// The relabelled variables are in the binders field in order of labelling.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, StructuralHash)]
 pub struct Canonical<T: HasInterner> {
     pub binders: Variables<T::Interner>,
     pub value: T,
 }
 
-#[derive(Clone, PartialEq, Eq, Hash, HasInterner, Zip, Fold)]
+#[derive(Clone, PartialEq, Eq, Hash, HasInterner, Zip, Fold, Visit, StructuralHash)]
 pub enum DomainGoal<I: Interner> {
     Holds(Constraint<I>),
+    WellFormed(WellFormed<I>),
+    FromEnv(FromEnv<I>),
+    Normalize(Normalize<I>),
+    /// holds where opaque types may be normalized to their hidden types,
+    /// only ever assumed by a hypothesis
+    Reveal,
 }
 
 impl<I: Interner> Debug for DomainGoal<I> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             DomainGoal::Holds(constraint) => write!(f, "{:?}", constraint),
+            DomainGoal::WellFormed(wf) => write!(f, "{:?}", wf),
+            DomainGoal::FromEnv(from_env) => write!(f, "{:?}", from_env),
+            DomainGoal::Normalize(normalize) => write!(f, "{:?}", normalize),
+            DomainGoal::Reveal => write!(f, "Reveal"),
         }
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Hash, Zip, Fold, HasInterner)]
+/// holds if the type or trait reference satisfies the where clauses of its declaration,
+/// proven by the clauses generated from the struct and trait declarations
+#[derive(Clone, PartialEq, Eq, Hash, Zip, Fold, Visit, HasInterner, StructuralHash)]
+pub enum WellFormed<I: Interner> {
+    Ty(Ty<I>),
+    Trait(ImplConstraint<I>),
+}
+
+impl<I: Interner> Debug for WellFormed<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WellFormed::Ty(ty) => write!(f, "WellFormed({:?})", ty),
+            WellFormed::Trait(impl_constraint) => write!(f, "WellFormed({:?})", impl_constraint),
+        }
+    }
+}
+
+/// holds if the alias normalizes to `ty`, given by the `type Name = Ty;` of the impls
+/// for associated types and by the hidden type of opaque types when revealed
+#[derive(Clone, PartialEq, Eq, Hash, Zip, Fold, Visit, HasInterner, StructuralHash)]
+pub struct Normalize<I: Interner> {
+    pub alias: AliasTy<I>,
+    pub ty: Ty<I>,
+}
+
+impl<I: Interner> Debug for Normalize<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Normalize({:?} -> {:?})", self.alias, self.ty)
+    }
+}
+
+/// holds if the type or trait reference is assumed to be well formed by the environment,
+/// what may then be implied from it is given by the clauses generated from the declarations
+#[derive(Clone, PartialEq, Eq, Hash, Zip, Fold, Visit, HasInterner, StructuralHash)]
+pub enum FromEnv<I: Interner> {
+    Ty(Ty<I>),
+    Trait(ImplConstraint<I>),
+}
+
+impl<I: Interner> Debug for FromEnv<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FromEnv::Ty(ty) => write!(f, "FromEnv({:?})", ty),
+            FromEnv::Trait(impl_constraint) => write!(f, "FromEnv({:?})", impl_constraint),
+        }
+    }
+}
+
+impl<I: Interner> DomainGoal<I> {
+    /// A hypothesis is assumed to be well formed, so a trait bound or well formedness in the
+    /// environment is turned into `FromEnv` to allow what it implies to be used.
+    pub fn into_from_env_goal(self) -> Self {
+        match self {
+            DomainGoal::Holds(Constraint::Implemented(impl_constraint))
+            | DomainGoal::WellFormed(WellFormed::Trait(impl_constraint)) =>
+                DomainGoal::FromEnv(FromEnv::Trait(impl_constraint)),
+            DomainGoal::WellFormed(WellFormed::Ty(ty)) => DomainGoal::FromEnv(FromEnv::Ty(ty)),
+            goal => goal,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Zip, Fold, Visit, HasInterner, StructuralHash)]
 pub enum Constraint<I: Interner> {
     Implemented(ImplConstraint<I>),
+    Predicate(Predicate<I>),
+    Eq(EqConstraint<I>),
+    LifetimeOutlives(LifetimeOutlives<I>),
+    TyOutlives(TyOutlives<I>),
 }
 
 impl<I: Interner> Debug for Constraint<I> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Constraint::Implemented(impl_constraint) => write!(f, "{:?}", impl_constraint),
+            Constraint::Predicate(predicate) => write!(f, "{:?}", predicate),
+            Constraint::Eq(eq) => write!(f, "{:?}", eq),
+            Constraint::LifetimeOutlives(outlives) => write!(f, "{:?}", outlives),
+            Constraint::TyOutlives(outlives) => write!(f, "{:?}", outlives),
         }
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Hash, Zip, Fold, HasInterner)]
+impl<I: Interner> Constraint<I> {
+    /// Whether this is a region constraint, which is never proven by clauses
+    /// but collected by the solver and returned along with the solution.
+    pub fn is_region_constraint(&self) -> bool {
+        matches!(self, Constraint::LifetimeOutlives(..) | Constraint::TyOutlives(..))
+    }
+}
+
+/// `'a: 'b`, `a` outlives `b`
+#[derive(Clone, PartialEq, Eq, Hash, Zip, Fold, Visit, HasInterner, StructuralHash)]
+pub struct LifetimeOutlives<I: Interner> {
+    pub a: Lifetime<I>,
+    pub b: Lifetime<I>,
+}
+
+impl<I: Interner> Debug for LifetimeOutlives<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {:?}", self.a, self.b)
+    }
+}
+
+/// `Ty: 'a`, the type outlives the lifetime
+#[derive(Clone, PartialEq, Eq, Hash, Zip, Fold, Visit, HasInterner, StructuralHash)]
+pub struct TyOutlives<I: Interner> {
+    pub ty: Ty<I>,
+    pub lifetime: Lifetime<I>,
+}
+
+impl<I: Interner> Debug for TyOutlives<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {:?}", self.ty, self.lifetime)
+    }
+}
+
+/// type equality, this is proven by unification and never by clauses
+#[derive(Clone, PartialEq, Eq, Hash, Zip, Fold, Visit, HasInterner, StructuralHash)]
+pub struct EqConstraint<I: Interner> {
+    pub lhs: Ty<I>,
+    pub rhs: Ty<I>,
+}
+
+impl<I: Interner> Debug for EqConstraint<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} = {:?}", self.lhs, self.rhs)
+    }
+}
+
+/// prolog style `name(arg, ...)`, which holds for exactly the arguments given by the clauses
+#[derive(Clone, PartialEq, Eq, Hash, Zip, Fold, Visit, HasInterner, StructuralHash)]
+pub struct Predicate<I: Interner> {
+    pub name: Ident,
+    pub args: Subst<I>,
+}
+
+impl<I: Interner> Debug for Predicate<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({})", self.name, util::join_dbg(self.args.as_slice(), ", "))
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Zip, Fold, Visit, HasInterner, StructuralHash)]
 pub struct ImplConstraint<I: Interner> {
     pub ty: Ty<I>,
     pub trait_ref: TraitRef<I>,
@@ -367,7 +979,7 @@ impl<I: Interner> Debug for ImplConstraint<I> {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Hash, HasInterner, Zip, Fold)]
+#[derive(Clone, PartialEq, Eq, Hash, HasInterner, Zip, Fold, Visit, StructuralHash)]
 pub struct TraitRef<I: Interner> {
     pub trait_name: Ident,
     pub args: Subst<I>,
@@ -379,9 +991,56 @@ impl<I: Interner> Debug for TraitRef<I> {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VariableKind {
+    Ty,
+    Lifetime,
+    /// a const with its type if it has one, otherwise it can have any value
+    Const(Option<ConstTy>),
+}
+
+/// The type of a const, the solver only tells integers from `bool`s so every integer type is
+/// the same and printed as `usize`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConstTy {
+    Int,
+    Bool,
+}
+
+impl ConstTy {
+    /// the integer types and `bool`, the types that a const can have
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64"
+            | "i128" | "isize" => Some(ConstTy::Int),
+            "bool" => Some(ConstTy::Bool),
+            _ => None,
+        }
+    }
+
+    pub fn of(value: ConstValue) -> Self {
+        match value {
+            ConstValue::Int(..) => ConstTy::Int,
+            ConstValue::Bool(..) => ConstTy::Bool,
+        }
+    }
+}
+
+impl Display for ConstTy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstTy::Int => write!(f, "usize"),
+            ConstTy::Bool => write!(f, "bool"),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, StructuralHash)]
 pub struct Variable<I: Interner> {
-    // nothing meaningful to store in here?
+    pub kind: VariableKind,
+    /// only meaningful for the variables of a `Canonical`,
+    /// where it is the universe of the inference variable that was canonicalized
+    pub universe: UniverseIdx,
     phantom: PhantomData<I>,
 }
 
@@ -389,17 +1048,26 @@ impl<I: Interner> Debug for Variable<I> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         // we don't really have a meaningful name to give each variable as they are referred
         // to by de Bruijn indices
-        write!(f, "_")
+        match self.kind {
+            VariableKind::Ty => write!(f, "_"),
+            VariableKind::Lifetime => write!(f, "'_"),
+            VariableKind::Const(Some(ty)) => write!(f, "const _: {}", ty),
+            VariableKind::Const(None) => write!(f, "const _"),
+        }
     }
 }
 
 impl<I: Interner> Variable<I> {
-    pub fn new() -> Self {
-        Self { phantom: PhantomData }
+    pub fn new(kind: VariableKind) -> Self {
+        Self::in_universe(kind, UniverseIdx::ROOT)
+    }
+
+    pub fn in_universe(kind: VariableKind, universe: UniverseIdx) -> Self {
+        Self { kind, universe, phantom: PhantomData }
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, StructuralHash)]
 pub struct Binders<T: HasInterner> {
     pub binders: Variables<T::Interner>,
     quantified: T,
@@ -417,6 +1085,18 @@ impl<T: HasInterner> Binders<T> {
     pub fn empty(interner: T::Interner, value: T) -> Self {
         Self::new(Variables::empty(interner), value)
     }
+
+    /// the bound value, any bound variables in it refer to these binders
+    pub fn skip_binders(&self) -> &T {
+        &self.quantified
+    }
+
+    pub fn map_ref<U: HasInterner<Interner = T::Interner>>(
+        &self,
+        f: impl FnOnce(&T) -> U,
+    ) -> Binders<U> {
+        Binders::new(self.binders.clone(), f(&self.quantified))
+    }
 }
 
 impl<T: HasInterner + Debug> Debug for Binders<T> {
@@ -425,11 +1105,26 @@ impl<T: HasInterner + Debug> Debug for Binders<T> {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Hash, Zip, Fold)]
+#[derive(Clone, PartialEq, Eq, Hash, Zip, Fold, Visit, StructuralHash)]
 pub enum ClauseData<I: Interner> {
     Implies(Binders<Implication<I>>),
 }
 
+impl<I: Interner> Clause<I> {
+    /// the clause with its consequent assumed as a hypothesis, see `DomainGoal::into_from_env_goal`
+    pub fn into_from_env_clause(self, interner: I) -> Self {
+        match self.data(interner) {
+            ClauseData::Implies(implication) => {
+                let implication = implication.map_ref(|implication| Implication {
+                    consequent: implication.consequent.clone().into_from_env_goal(),
+                    condition: implication.condition.clone(),
+                });
+                Clause::intern(interner, ClauseData::Implies(implication))
+            }
+        }
+    }
+}
+
 impl<I: Interner> Debug for ClauseData<I> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
@@ -438,7 +1133,7 @@ impl<I: Interner> Debug for ClauseData<I> {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Hash, Zip, Fold, HasInterner)]
+#[derive(Clone, PartialEq, Eq, Hash, Zip, Fold, Visit, HasInterner, StructuralHash)]
 pub struct Implication<I: Interner> {
     pub consequent: DomainGoal<I>,
     pub condition: Goal<I>,
@@ -450,7 +1145,7 @@ impl<I: Interner> Debug for Implication<I> {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, StructuralHash)]
 pub struct Environment<I: Interner> {
     pub clauses: Clauses<I>,
 }
@@ -459,6 +1154,56 @@ impl<I: Interner> Environment<I> {
     pub fn new(clauses: Clauses<I>) -> Self {
         Self { clauses }
     }
+
+    pub fn empty(interner: I) -> Self {
+        Self::new(Clauses::empty(interner))
+    }
+
+    pub fn add_clauses(&self, interner: I, clauses: impl IntoIterator<Item = Clause<I>>) -> Self {
+        let clauses = self.clauses.iter().cloned().chain(clauses);
+        Self::new(Clauses::intern(interner, clauses))
+    }
+
+    /// Whether a hypothesis assumes the region constraint, which is how a region constraint
+    /// holds in an environment as it is never proven by clauses
+    pub fn assumes(&self, interner: I, constraint: &Constraint<I>) -> bool {
+        // the consequent of a hypothesis is under the (empty) binder of its clause
+        let constraint = constraint.clone().shifted_in(interner);
+        self.clauses.iter().any(|clause| {
+            let ClauseData::Implies(implication) = clause.data(interner);
+            let Implication { consequent, condition } = implication.skip_binders();
+            implication.binders.is_empty()
+                && matches!(condition.data(interner), GoalData::True)
+                && matches!(consequent, DomainGoal::Holds(assumed) if assumed == &constraint)
+        })
+    }
+}
+
+impl<I: Interner> HasInterner for Environment<I> {
+    type Interner = I;
+}
+
+/// a goal together with the hypotheses it may assume
+#[derive(Clone, PartialEq, Eq, Hash, StructuralHash)]
+pub struct InEnvironment<G: HasInterner> {
+    pub environment: Environment<G::Interner>,
+    pub goal: G,
+}
+
+impl<G: HasInterner> InEnvironment<G> {
+    pub fn new(environment: Environment<G::Interner>, goal: G) -> Self {
+        Self { environment, goal }
+    }
+}
+
+impl<G: HasInterner> HasInterner for InEnvironment<G> {
+    type Interner = G::Interner;
+}
+
+impl<G: HasInterner + Debug> Debug for InEnvironment<G> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} ⊢ {:?}", self.environment.clauses.as_slice(), self.goal)
+    }
 }
 
 pub type LogicResult<T> = Result<T, LogicError>;
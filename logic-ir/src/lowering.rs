@@ -1,77 +1,271 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::*;
 use logic_parse::ast;
 
 pub fn lower_ast(ast: &ast::Program) -> LoweringResult<Program<LogicInterner>> {
-    AstLoweringCtx::default().lower_program(ast)
+    lower_ast_with(LogicInterner, ast)
+}
+
+/// lowers the ast into the ir of the given interner
+pub fn lower_ast_with<I: Interner>(interner: I, ast: &ast::Program) -> LoweringResult<Program<I>> {
+    AstLoweringCtx::new(interner, ast).lower_program(ast)
 }
 
 pub fn lower_goal(goal: &ast::Goal) -> LoweringResult<Goal<LogicInterner>> {
-    AstLoweringCtx::default().lower_goal(goal)
+    AstLoweringCtx::empty(LogicInterner).lower_goal(goal)
+}
+
+/// lowers a goal that may name the declarations of `program`, such as its opaque types
+pub fn lower_goal_in(
+    program: &ast::Program,
+    goal: &ast::Goal,
+) -> LoweringResult<Goal<LogicInterner>> {
+    lower_goal_in_with(LogicInterner, program, goal)
+}
+
+pub fn lower_goal_in_with<I: Interner>(
+    interner: I,
+    program: &ast::Program,
+    goal: &ast::Goal,
+) -> LoweringResult<Goal<I>> {
+    AstLoweringCtx::new(interner, program).lower_goal(goal)
 }
 
 /// lowers ast into ir form, not to be confused with trait lowering
-// this is not generic over the interner as we only lower the ast
-// which has `term` as its domain goal
-struct AstLoweringCtx {
-    interner: LogicInterner,
+struct AstLoweringCtx<I: Interner> {
+    interner: I,
     env: Env,
+    /// the traits of the program that can't be used in a `dyn` type
+    object_unsafe_traits: HashSet<Symbol>,
+    /// the names of the opaque types of the program, which are otherwise named like structs
+    opaque_tys: HashSet<Symbol>,
 }
 
 #[derive(Debug, Default)]
 struct Env {
-    variables: HashMap<Symbol, BoundVar>,
+    /// all kinds of parameters share the map, they can't clash as only the names of
+    /// lifetimes start with `'` and types and consts share a namespace
+    variables: HashMap<Symbol, (BoundVar, VariableKind)>,
 }
 
 pub type LoweringResult<T> = Result<T, LoweringError>;
 
 #[derive(Debug)]
-pub enum LoweringError {}
+pub enum LoweringError {
+    /// equality is builtin and can't be given by a clause
+    EqInConsequent(ast::DomainGoal),
+    /// region constraints are collected by the solver rather than proven,
+    /// so they can't be given by a clause either
+    OutlivesInConsequent(ast::DomainGoal),
+    /// lifetimes can only be named by the parameter that binds them
+    UnboundLifetime(Ident),
+    /// a const parameter named where a type is expected
+    ConstAsType(Ident),
+    /// the length of an array must be a literal or a const parameter
+    UnboundConst(Ident),
+    /// a const parameter can only be an integer or a `bool`
+    InvalidConstTy(ast::Ty),
+    /// a trait that isn't object safe named in a `dyn` type
+    NotObjectSafe(Ident),
+    /// the impls of a marker trait may overlap, so it can't have associated types
+    MarkerWithItems(Ident),
+    /// an attribute that the declaration can't have, has more than once or with the wrong
+    /// arguments
+    InvalidAttr(ast::Attr),
+}
+
+impl std::fmt::Display for LoweringError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoweringError::EqInConsequent(consequent) =>
+                write!(f, "`{}` can't be the consequent of a clause", consequent),
+            LoweringError::OutlivesInConsequent(consequent) =>
+                write!(f, "`{}` can't be the consequent of a clause", consequent),
+            LoweringError::UnboundLifetime(name) => write!(f, "unbound lifetime `{}`", name),
+            LoweringError::ConstAsType(name) => write!(f, "const `{}` used as a type", name),
+            LoweringError::UnboundConst(name) => write!(f, "unbound const `{}`", name),
+            LoweringError::InvalidConstTy(ty) => write!(f, "`{}` can't be the type of a const", ty),
+            LoweringError::NotObjectSafe(name) =>
+                write!(f, "trait `{}` is not object safe so can't be used in a `dyn` type", name),
+            LoweringError::MarkerWithItems(name) =>
+                write!(f, "marker trait `{}` can't have associated types", name),
+            LoweringError::InvalidAttr(attr) => write!(f, "invalid attribute `{}`", attr),
+        }
+    }
+}
 
 impl Env {
-    fn lookup_type(&self, symbol: &Symbol) -> LoweringResult<Option<Ty<LogicInterner>>> {
-        let ty = match self.variables.get(symbol) {
-            Some(&bound) => Some(TyKind::Bound(bound).intern(LogicInterner)),
-            None => None,
+    fn lookup_type<I: Interner>(&self, interner: I, name: &Ident) -> LoweringResult<Option<Ty<I>>> {
+        let ty = match self.variables.get(&name.symbol) {
+            Some(&(bound, VariableKind::Ty)) => Some(TyKind::Bound(bound).intern(interner)),
+            Some((_, VariableKind::Const(..))) =>
+                return Err(LoweringError::ConstAsType(name.clone())),
+            Some((_, VariableKind::Lifetime)) | None => None,
         };
         Ok(ty)
     }
-}
 
-impl Default for AstLoweringCtx {
-    fn default() -> Self {
-        Self { interner: LogicInterner, env: Default::default() }
+    fn lookup_lifetime<I: Interner>(
+        &self,
+        interner: I,
+        name: &Ident,
+    ) -> LoweringResult<Lifetime<I>> {
+        match self.variables.get(&name.symbol) {
+            Some(&(bound, _)) => Ok(LifetimeData::Bound(bound).intern(interner)),
+            None => Err(LoweringError::UnboundLifetime(name.clone())),
+        }
+    }
+
+    fn lookup_const<I: Interner>(&self, interner: I, name: &Ident) -> Option<Const<I>> {
+        match self.variables.get(&name.symbol) {
+            Some(&(bound, VariableKind::Const(..))) =>
+                Some(ConstData::Bound(bound).intern(interner)),
+            _ => None,
+        }
     }
 }
 
-impl AstLoweringCtx {
-    pub fn lower_program(
-        &mut self,
-        program: &ast::Program,
-    ) -> LoweringResult<Program<LogicInterner>> {
+impl<I: Interner> AstLoweringCtx<I> {
+    fn empty(interner: I) -> Self {
+        Self {
+            interner,
+            env: Default::default(),
+            object_unsafe_traits: Default::default(),
+            opaque_tys: Default::default(),
+        }
+    }
+
+    fn new(interner: I, program: &ast::Program) -> Self {
+        let opaque_tys = program
+            .items
+            .iter()
+            .filter_map(|item| match &item.kind {
+                ast::ItemKind::OpaqueTy(opaque_ty_def) => Some(opaque_ty_def.name.symbol.clone()),
+                _ => None,
+            })
+            .collect();
+        let object_unsafe_traits = object_unsafe_traits(program);
+        Self { object_unsafe_traits, opaque_tys, ..Self::empty(interner) }
+    }
+
+    pub fn lower_program(&mut self, program: &ast::Program) -> LoweringResult<Program<I>> {
+        let mut lowered = Program::new(self.interner, Clauses::empty(self.interner));
         let mut clauses = vec![];
         for item in &program.items {
-            match item {
-                ast::Item::Clause(clause) => clauses.push(self.lower_clause(clause)?),
+            match &item.kind {
+                ast::ItemKind::Clause(clause) => clauses.push(self.lower_clause(clause)?),
+                ast::ItemKind::Struct(struct_def) =>
+                    lowered.structs.push(self.lower_struct(struct_def)?),
+                ast::ItemKind::Trait(trait_def) =>
+                    lowered.traits.push(self.lower_trait(trait_def)?),
+                ast::ItemKind::Impl(impl_def) => lowered.impls.push(self.lower_impl(impl_def)?),
+                ast::ItemKind::OpaqueTy(opaque_ty_def) =>
+                    lowered.opaque_tys.push(self.lower_opaque_ty(opaque_ty_def)?),
             }
         }
 
-        Ok(Program { clauses: Clauses::intern(self.interner, clauses), interner: self.interner })
+        lowered.clauses = Clauses::intern(self.interner, clauses);
+        Ok(lowered)
+    }
+
+    pub fn lower_struct(&mut self, struct_def: &ast::StructDef) -> LoweringResult<StructDatum<I>> {
+        let binders = self.enter_binders(&struct_def.vars, |this| {
+            Ok(StructDatumBound {
+                where_clauses: this.lower_where_clauses(&struct_def.where_clauses)?,
+            })
+        })?;
+        let attrs = lower_attrs(&struct_def.attrs, &["crate", "fundamental"])?;
+        Ok(StructDatum {
+            krate: attrs.krate,
+            fundamental: attrs.fundamental,
+            name: struct_def.name.clone(),
+            binders,
+        })
+    }
+
+    pub fn lower_trait(&mut self, trait_def: &ast::TraitDef) -> LoweringResult<TraitDatum<I>> {
+        let self_var = ast::Var::new(Ident::unspanned("Self"));
+        let vars =
+            std::iter::once(self_var).chain(trait_def.vars.iter().cloned()).collect::<Vec<_>>();
+        let binders = self.enter_binders(&vars, |this| {
+            Ok(TraitDatumBound {
+                where_clauses: this.lower_where_clauses(&trait_def.where_clauses)?,
+            })
+        })?;
+        let assoc_tys = trait_def.assoc_tys.clone();
+        let object_safe = !self.object_unsafe_traits.contains(&trait_def.name.symbol);
+        let Attrs { krate, marker, .. } = lower_attrs(&trait_def.attrs, &["crate", "marker"])?;
+        if marker && !assoc_tys.is_empty() {
+            return Err(LoweringError::MarkerWithItems(trait_def.name.clone()));
+        }
+        let name = trait_def.name.clone();
+        Ok(TraitDatum { krate, marker, name, assoc_tys, object_safe, binders })
+    }
+
+    pub fn lower_impl(&mut self, impl_def: &ast::ImplDef) -> LoweringResult<ImplDatum<I>> {
+        let binders = self.enter_binders(&impl_def.vars, |this| {
+            Ok(ImplDatumBound {
+                header: ImplConstraint {
+                    ty: this.lower_ty(&impl_def.self_ty)?,
+                    trait_ref: this.lower_trait_ref(&impl_def.trait_ref)?,
+                },
+                where_clauses: this.lower_where_clauses(&impl_def.where_clauses)?,
+                assoc_ty_values: impl_def
+                    .assoc_ty_values
+                    .iter()
+                    .map(|value| {
+                        Ok(AssocTyValue { name: value.name.clone(), ty: this.lower_ty(&value.ty)? })
+                    })
+                    .collect::<LoweringResult<_>>()?,
+            })
+        })?;
+        let krate = lower_attrs(&impl_def.attrs, &["crate"])?.krate;
+        Ok(ImplDatum { krate, default: impl_def.default, binders })
+    }
+
+    pub fn lower_opaque_ty(
+        &mut self,
+        opaque_ty_def: &ast::OpaqueTyDef,
+    ) -> LoweringResult<OpaqueTyDatum<I>> {
+        let binders = self.enter_binders(&opaque_ty_def.vars, |this| {
+            Ok(OpaqueTyDatumBound {
+                bounds: opaque_ty_def
+                    .bounds
+                    .iter()
+                    .map(|bound| this.lower_trait_ref(bound))
+                    .collect::<LoweringResult<_>>()?,
+                hidden_ty: this.lower_ty(&opaque_ty_def.hidden_ty)?,
+            })
+        })?;
+        Ok(OpaqueTyDatum { name: opaque_ty_def.name.clone(), binders })
+    }
+
+    pub fn lower_where_clauses(
+        &mut self,
+        where_clauses: &[ast::Constraint],
+    ) -> LoweringResult<Vec<Constraint<I>>> {
+        where_clauses.iter().map(|constraint| self.lower_constraint(constraint)).collect()
     }
 
-    pub fn lower_goal(&mut self, goal: &ast::Goal) -> LoweringResult<Goal<LogicInterner>> {
+    pub fn lower_goal(&mut self, goal: &ast::Goal) -> LoweringResult<Goal<I>> {
         let goal_data = match goal {
             ast::Goal::DomainGoal(domain_goal) =>
                 GoalData::DomainGoal(self.lower_domain_goal(domain_goal)?),
             ast::Goal::Implies(clause, goal) =>
-                GoalData::Implies(self.lower_clause(clause)?, self.lower_goal(goal)?),
+                GoalData::Implies(self.lower_hypothesis(clause)?, self.lower_goal(goal)?),
             ast::Goal::And(lhs, rhs) => GoalData::And(self.lower_goal(lhs)?, self.lower_goal(rhs)?),
             ast::Goal::Or(lhs, rhs) => GoalData::Or(self.lower_goal(lhs)?, self.lower_goal(rhs)?),
             ast::Goal::Exists(vars, subgoal) => GoalData::Quantified(
                 Quantifier::Exists,
                 self.enter_binders(vars, |this| this.lower_goal(subgoal))?,
             ),
+            ast::Goal::ForAll(vars, subgoal) => GoalData::Quantified(
+                Quantifier::ForAll,
+                self.enter_binders(vars, |this| this.lower_goal(subgoal))?,
+            ),
+            ast::Goal::Not(goal) => GoalData::Not(self.lower_goal(goal)?),
+            ast::Goal::True => GoalData::True,
         };
         Ok(Goal::intern(self.interner, goal_data))
     }
@@ -79,70 +273,217 @@ impl AstLoweringCtx {
     pub fn lower_domain_goal(
         &mut self,
         domain_goal: &ast::DomainGoal,
-    ) -> LoweringResult<DomainGoal<LogicInterner>> {
+    ) -> LoweringResult<DomainGoal<I>> {
         match domain_goal {
             ast::DomainGoal::Holds(constraint) =>
                 self.lower_constraint(constraint).map(DomainGoal::Holds),
+            ast::DomainGoal::WellFormed(ast::WellFormed::Ty(ty)) =>
+                Ok(DomainGoal::WellFormed(WellFormed::Ty(self.lower_ty(ty)?))),
+            ast::DomainGoal::WellFormed(ast::WellFormed::Trait(impl_constraint)) => {
+                let impl_constraint = self.lower_impl_constraint(impl_constraint)?;
+                Ok(DomainGoal::WellFormed(WellFormed::Trait(impl_constraint)))
+            }
+            ast::DomainGoal::Normalize(ast::Normalize { alias, ty }) =>
+                Ok(DomainGoal::Normalize(Normalize {
+                    alias: self.lower_alias_ty(alias)?,
+                    ty: self.lower_ty(ty)?,
+                })),
+            ast::DomainGoal::Reveal => Ok(DomainGoal::Reveal),
+            ast::DomainGoal::FromEnv(ast::FromEnv::Ty(ty)) =>
+                Ok(DomainGoal::FromEnv(FromEnv::Ty(self.lower_ty(ty)?))),
+            ast::DomainGoal::FromEnv(ast::FromEnv::Trait(impl_constraint)) => {
+                let impl_constraint = self.lower_impl_constraint(impl_constraint)?;
+                Ok(DomainGoal::FromEnv(FromEnv::Trait(impl_constraint)))
+            }
+        }
+    }
+
+    pub fn lower_consequent(
+        &mut self,
+        consequent: &ast::DomainGoal,
+    ) -> LoweringResult<DomainGoal<I>> {
+        match consequent {
+            ast::DomainGoal::Holds(ast::Constraint::Eq(..)) =>
+                Err(LoweringError::EqInConsequent(consequent.clone())),
+            ast::DomainGoal::Holds(
+                ast::Constraint::LifetimeOutlives(..) | ast::Constraint::TyOutlives(..),
+            ) => Err(LoweringError::OutlivesInConsequent(consequent.clone())),
+            _ => self.lower_domain_goal(consequent),
         }
     }
 
     pub fn lower_constraint(
         &mut self,
         constraint: &ast::Constraint,
-    ) -> LoweringResult<Constraint<LogicInterner>> {
+    ) -> LoweringResult<Constraint<I>> {
         let lowered = match constraint {
             ast::Constraint::Implemented(impl_constraint) =>
-                Constraint::Implemented(ImplConstraint {
-                    ty: self.lower_ty(&impl_constraint.ty)?,
-                    trait_ref: self.lower_trait_ref(&impl_constraint.trait_ref)?,
+                Constraint::Implemented(self.lower_impl_constraint(impl_constraint)?),
+            ast::Constraint::Predicate(predicate) => Constraint::Predicate(Predicate {
+                name: predicate.name.clone(),
+                args: self.lower_generic_args(&predicate.args)?,
+            }),
+            ast::Constraint::Eq(eq) => Constraint::Eq(EqConstraint {
+                lhs: self.lower_ty(&eq.lhs)?,
+                rhs: self.lower_ty(&eq.rhs)?,
+            }),
+            ast::Constraint::LifetimeOutlives(outlives) =>
+                Constraint::LifetimeOutlives(LifetimeOutlives {
+                    a: self.lower_lifetime(&outlives.a)?,
+                    b: self.lower_lifetime(&outlives.b)?,
                 }),
+            ast::Constraint::TyOutlives(outlives) => Constraint::TyOutlives(TyOutlives {
+                ty: self.lower_ty(&outlives.ty)?,
+                lifetime: self.lower_lifetime(&outlives.lifetime)?,
+            }),
         };
         Ok(lowered)
     }
 
-    pub fn lower_trait_ref(
+    pub fn lower_impl_constraint(
         &mut self,
-        trait_ref: &ast::TraitRef,
-    ) -> LoweringResult<TraitRef<LogicInterner>> {
+        impl_constraint: &ast::ImplConstraint,
+    ) -> LoweringResult<ImplConstraint<I>> {
+        Ok(ImplConstraint {
+            ty: self.lower_ty(&impl_constraint.ty)?,
+            trait_ref: self.lower_trait_ref(&impl_constraint.trait_ref)?,
+        })
+    }
+
+    pub fn lower_trait_ref(&mut self, trait_ref: &ast::TraitRef) -> LoweringResult<TraitRef<I>> {
         Ok(TraitRef {
             trait_name: trait_ref.trait_name.clone(),
-            args: self.lower_tys(&trait_ref.args)?,
+            args: self.lower_generic_args(&trait_ref.args)?,
         })
     }
 
-    pub fn _lower_goals(&mut self, goals: &[ast::Goal]) -> LoweringResult<Goals<LogicInterner>> {
+    pub fn _lower_goals(&mut self, goals: &[ast::Goal]) -> LoweringResult<Goals<I>> {
         Goals::try_intern(self.interner, goals.into_iter().map(|goal| self.lower_goal(goal)))
     }
 
-    pub fn lower_tys<'a>(&mut self, tys: &[ast::Ty]) -> LoweringResult<Subst<LogicInterner>> {
-        Subst::try_intern(self.interner, tys.into_iter().map(|ty| self.lower_ty(ty)))
+    pub fn lower_generic_args(&mut self, args: &[ast::GenericArg]) -> LoweringResult<Subst<I>> {
+        Subst::try_intern(self.interner, args.iter().map(|arg| self.lower_generic_arg(arg)))
     }
 
-    pub fn lower_ty(&mut self, ty: &ast::Ty) -> LoweringResult<Ty<LogicInterner>> {
+    pub fn lower_generic_arg(&mut self, arg: &ast::GenericArg) -> LoweringResult<GenericArg<I>> {
+        let arg = match arg {
+            // a const parameter is indistinguishable from a type until it is looked up
+            ast::GenericArg::Ty(ty @ ast::Ty::Structure(name, args)) if args.is_empty() =>
+                match self.env.lookup_const(self.interner, name) {
+                    Some(constant) => GenericArgData::Const(constant),
+                    None => GenericArgData::Ty(self.lower_ty(ty)?),
+                },
+            ast::GenericArg::Ty(ty) => GenericArgData::Ty(self.lower_ty(ty)?),
+            ast::GenericArg::Lifetime(lifetime) =>
+                GenericArgData::Lifetime(self.lower_lifetime(lifetime)?),
+            &ast::GenericArg::Const(value) =>
+                GenericArgData::Const(ConstData::Value(value).intern(self.interner)),
+        };
+        Ok(arg.intern(self.interner))
+    }
+
+    pub fn lower_lifetime(&mut self, lifetime: &ast::Lifetime) -> LoweringResult<Lifetime<I>> {
+        match lifetime {
+            ast::Lifetime::Named(name) => self.env.lookup_lifetime(self.interner, name),
+            ast::Lifetime::Static => Ok(LifetimeData::Static.intern(self.interner)),
+        }
+    }
+
+    pub fn lower_ty(&mut self, ty: &ast::Ty) -> LoweringResult<Ty<I>> {
         let kind = match ty {
-            ast::Ty::Structure(functor, tys) => match &tys[..] {
+            ast::Ty::Structure(functor, args) => {
                 // if no arguments it might be referencing a type by name
-                &[] => match self.env.lookup_type(&functor.symbol)? {
-                    Some(ty) => return Ok(ty),
-                    None => TyKind::Structure(functor.clone(), Subst::empty(self.interner)),
-                },
-                _ => TyKind::Structure(functor.clone(), self.lower_tys(tys)?),
-            },
+                if args.is_empty() {
+                    if let Some(ty) = self.env.lookup_type(self.interner, functor)? {
+                        return Ok(ty);
+                    }
+                }
+                let args = self.lower_generic_args(args)?;
+                if self.opaque_tys.contains(&functor.symbol) {
+                    TyKind::Opaque(OpaqueTy { name: functor.clone(), args })
+                } else {
+                    TyKind::Structure(functor.clone(), args)
+                }
+            }
+            ast::Ty::Projection(projection) =>
+                TyKind::Projection(self.lower_projection_ty(projection)?),
+            ast::Ty::Tuple(tys) => TyKind::Tuple(self.lower_tys(tys)?),
+            &ast::Ty::Ref(mutability, ref lifetime, ref ty) =>
+                TyKind::Ref(mutability, self.lower_lifetime(lifetime)?, self.lower_ty(ty)?),
+            ast::Ty::Slice(ty) => TyKind::Slice(self.lower_ty(ty)?),
+            ast::Ty::Array(ty, len) => TyKind::Array(self.lower_ty(ty)?, self.lower_const(len)?),
+            ast::Ty::Fn(params, ret) =>
+                TyKind::Fn(FnSig { params: self.lower_tys(params)?, ret: self.lower_ty(ret)? }),
+            ast::Ty::Never => TyKind::Never,
+            ast::Ty::Dyn(bounds) => TyKind::Dyn(DynTy {
+                bounds: bounds
+                    .iter()
+                    .map(|bound| self.lower_dyn_bound(bound))
+                    .collect::<LoweringResult<_>>()?,
+            }),
         };
 
         Ok(kind.intern(self.interner))
     }
 
-    pub fn lower_clause(&mut self, clause: &ast::Clause) -> LoweringResult<Clause<LogicInterner>> {
+    fn lower_dyn_bound(&mut self, bound: &ast::TraitRef) -> LoweringResult<TraitRef<I>> {
+        if self.object_unsafe_traits.contains(&bound.trait_name.symbol) {
+            return Err(LoweringError::NotObjectSafe(bound.trait_name.clone()));
+        }
+        self.lower_trait_ref(bound)
+    }
+
+    /// lowers the types into a substitution of types only
+    pub fn lower_tys(&mut self, tys: &[ast::Ty]) -> LoweringResult<Subst<I>> {
+        let interner = self.interner;
+        let tys = tys.iter().map(|ty| Ok(self.lower_ty(ty)?.into_generic_arg(interner)));
+        Subst::try_intern(interner, tys)
+    }
+
+    pub fn lower_const(&mut self, constant: &ast::Const) -> LoweringResult<Const<I>> {
+        match constant {
+            ast::Const::Param(name) => {
+                let unbound = || LoweringError::UnboundConst(name.clone());
+                self.env.lookup_const(self.interner, name).ok_or_else(unbound)
+            }
+            &ast::Const::Value(value) => Ok(ConstData::Value(value).intern(self.interner)),
+        }
+    }
+
+    /// the alias of a `Normalize`, which is an opaque type by its position alone
+    pub fn lower_alias_ty(&mut self, alias: &ast::AliasTy) -> LoweringResult<AliasTy<I>> {
+        match alias {
+            ast::AliasTy::Projection(projection) =>
+                Ok(AliasTy::Projection(self.lower_projection_ty(projection)?)),
+            ast::AliasTy::Opaque(name, args) => Ok(AliasTy::Opaque(OpaqueTy {
+                name: name.clone(),
+                args: self.lower_generic_args(args)?,
+            })),
+        }
+    }
+
+    pub fn lower_projection_ty(
+        &mut self,
+        projection: &ast::ProjectionTy,
+    ) -> LoweringResult<ProjectionTy<I>> {
+        Ok(ProjectionTy {
+            ty: self.lower_ty(&projection.ty)?,
+            trait_ref: self.lower_trait_ref(&projection.trait_ref)?,
+            name: projection.name.clone(),
+        })
+    }
+
+    pub fn lower_clause(&mut self, clause: &ast::Clause) -> LoweringResult<Clause<I>> {
         let clause_data = match clause {
             // lower known domain goals into an implication with a trivially true condition
-            ast::Clause::DomainGoal(domain_goal) => ClauseData::Implies(Binders::empty(
-                self.interner,
-                Implication {
-                    consequent: self.lower_domain_goal(domain_goal)?,
-                    condition: Goal::intern(self.interner, GoalData::True),
-                },
-            )),
+            // the (empty) binder still counts as a level w.r.t. de Bruijn indices
+            ast::Clause::DomainGoal(domain_goal) =>
+                ClauseData::Implies(self.enter_binders(&[], |this| {
+                    Ok(Implication {
+                        consequent: this.lower_consequent(domain_goal)?,
+                        condition: Goal::intern(this.interner, GoalData::True),
+                    })
+                })?),
             ast::Clause::Implies(implication) =>
                 ClauseData::Implies(self.lower_implication(implication)?),
             ast::Clause::And(_, _) => todo!(),
@@ -150,53 +491,172 @@ impl AstLoweringCtx {
         Ok(Clause::intern(self.interner, clause_data))
     }
 
+    /// A hypothesis is lowered like a clause, except that a region constraint can be assumed,
+    /// the solver then takes it to hold rather than returning it with the solution
+    pub fn lower_hypothesis(&mut self, clause: &ast::Clause) -> LoweringResult<Clause<I>> {
+        match clause {
+            ast::Clause::DomainGoal(
+                domain_goal @ ast::DomainGoal::Holds(
+                    ast::Constraint::LifetimeOutlives(..) | ast::Constraint::TyOutlives(..),
+                ),
+            ) => {
+                let implication = self.enter_binders(&[], |this| {
+                    Ok(Implication {
+                        consequent: this.lower_domain_goal(domain_goal)?,
+                        condition: Goal::intern(this.interner, GoalData::True),
+                    })
+                })?;
+                Ok(Clause::intern(self.interner, ClauseData::Implies(implication)))
+            }
+            _ => self.lower_clause(clause),
+        }
+    }
+
     pub fn enter_binders<R>(
         &mut self,
         binder_slice: &[ast::Var],
         f: impl FnOnce(&mut Self) -> LoweringResult<R>,
     ) -> LoweringResult<Binders<R>>
     where
-        R: HasInterner<Interner = LogicInterner>,
+        R: HasInterner<Interner = I>,
     {
         let expected_len = self.env.variables.len() + binder_slice.len();
 
-        let binders = binder_slice
+        let kinds = binder_slice
             .iter()
-            .cloned()
-            .enumerate()
-            .map(|(i, var)| (var.ident.symbol, BoundVar::new(DebruijnIdx::ZERO, i)));
+            .map(|var| lower_var_kind(&var.kind))
+            .collect::<LoweringResult<Vec<_>>>()?;
+        let binders = binder_slice.iter().zip(&kinds).enumerate().map(|(i, (var, &kind))| {
+            (var.ident.symbol.clone(), (BoundVar::new(DebruijnIdx::ZERO, i), kind))
+        });
 
         let variables = self
             .env
             .variables
             .iter()
-            .map(|(name, bound)| (name.clone(), bound.shifted_in()))
+            .map(|(name, &(bound, kind))| (name.clone(), (bound.shifted_in(), kind)))
             .chain(binders)
             .collect::<HashMap<_, _>>();
 
         assert_eq!(variables.len(), expected_len, "found shadowed parameter names");
 
-        let value = f(&mut Self { env: Env { variables }, interner: self.interner })?;
+        let outer = std::mem::replace(&mut self.env, Env { variables });
+        let value = f(self);
+        self.env = outer;
         Ok(Binders {
-            binders: Variables::intern(
-                LogicInterner,
-                (0..binder_slice.len()).map(|_| Variable::new()),
-            ),
-            quantified: value,
+            binders: Variables::intern(self.interner, kinds.into_iter().map(Variable::new)),
+            quantified: value?,
         })
     }
 
     pub fn lower_implication(
         &mut self,
         implication: &ast::Implication,
-    ) -> LoweringResult<Binders<Implication<LogicInterner>>> {
+    ) -> LoweringResult<Binders<Implication<I>>> {
         self.enter_binders(&implication.vars, |ctxt| {
-            let consequent = ctxt.lower_domain_goal(&implication.consequent)?;
+            let consequent = ctxt.lower_consequent(&implication.consequent)?;
             let condition = ctxt.lower_goal(&implication.condition)?;
             Ok(Implication { consequent, condition })
         })
     }
 }
 
+/// The traits that can't be used in a `dyn` type. A trait is object safe unless it requires
+/// `Self: Sized`, names `Self` in its where clauses other than as the self type of a bound,
+/// or has a supertrait that isn't object safe.
+fn object_unsafe_traits(program: &ast::Program) -> HashSet<Symbol> {
+    let trait_defs = program
+        .items
+        .iter()
+        .filter_map(|item| match &item.kind {
+            ast::ItemKind::Trait(trait_def) => Some(trait_def),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let mut object_unsafe = trait_defs
+        .iter()
+        .filter(|trait_def| !trait_def.where_clauses.iter().all(object_safe_where_clause))
+        .map(|trait_def| trait_def.name.symbol.clone())
+        .collect::<HashSet<_>>();
+    // supertraits can be declared in any order, so iterate until nothing changes
+    loop {
+        let len = object_unsafe.len();
+        for trait_def in &trait_defs {
+            let mut supertraits = trait_def.where_clauses.iter().filter_map(|wc| match wc {
+                ast::Constraint::Implemented(ast::ImplConstraint { ty, trait_ref })
+                    if is_self(ty) =>
+                    Some(&trait_ref.trait_name.symbol),
+                _ => None,
+            });
+            if supertraits.any(|name| object_unsafe.contains(name)) {
+                object_unsafe.insert(trait_def.name.symbol.clone());
+            }
+        }
+        if object_unsafe.len() == len {
+            return object_unsafe;
+        }
+    }
+}
+
+fn object_safe_where_clause(where_clause: &ast::Constraint) -> bool {
+    match where_clause {
+        ast::Constraint::Implemented(ast::ImplConstraint { ty, trait_ref }) if is_self(ty) =>
+            &*trait_ref.trait_name.symbol != "Sized" && !trait_ref.mentions("Self"),
+        ast::Constraint::TyOutlives(ast::TyOutlives { ty, .. }) if is_self(ty) => true,
+        _ => !where_clause.mentions("Self"),
+    }
+}
+
+fn is_self(ty: &ast::Ty) -> bool {
+    matches!(ty, ast::Ty::Structure(name, args) if args.is_empty() && &*name.symbol == "Self")
+}
+
+/// the attributes of a declaration
+#[derive(Default)]
+struct Attrs {
+    /// `#[crate(name)]`, the crate owning the declaration, `None` for the local crate
+    krate: Option<Ident>,
+    /// `#[fundamental]`
+    fundamental: bool,
+    /// `#[marker]`
+    marker: bool,
+}
+
+/// checks that each attribute is one of the `allowed` ones, is given at most once and has an
+/// argument only if it takes one
+fn lower_attrs(attrs: &[ast::Attr], allowed: &[&str]) -> LoweringResult<Attrs> {
+    let mut lowered = Attrs::default();
+    for (i, attr) in attrs.iter().enumerate() {
+        let name = &*attr.name.symbol;
+        let repeated = attrs[..i].iter().any(|other| other.name.symbol == attr.name.symbol);
+        if !allowed.contains(&name) || repeated {
+            return Err(LoweringError::InvalidAttr(attr.clone()));
+        }
+        match (name, &attr.arg) {
+            ("crate", Some(krate)) => lowered.krate = Some(krate.clone()),
+            ("fundamental", None) => lowered.fundamental = true,
+            ("marker", None) => lowered.marker = true,
+            _ => return Err(LoweringError::InvalidAttr(attr.clone())),
+        }
+    }
+    Ok(lowered)
+}
+
+fn lower_var_kind(kind: &ast::VarKind) -> LoweringResult<VariableKind> {
+    let const_ty = |ty: &ast::Ty| match ty {
+        ast::Ty::Structure(name, args) if args.is_empty() => ConstTy::from_name(&name.symbol),
+        _ => None,
+    };
+    match kind {
+        ast::VarKind::Ty => Ok(VariableKind::Ty),
+        ast::VarKind::Lifetime => Ok(VariableKind::Lifetime),
+        ast::VarKind::Const(None) => Ok(VariableKind::Const(None)),
+        ast::VarKind::Const(Some(ty)) => match const_ty(ty) {
+            Some(ty) => Ok(VariableKind::Const(Some(ty))),
+            None => Err(LoweringError::InvalidConstTy(ty.clone())),
+        },
+    }
+}
+
 #[cfg(test)]
 mod tests;
@@ -1,3 +1,14 @@
+/// returns early from a visit if it breaks
+#[macro_export]
+macro_rules! try_break {
+    ($e:expr) => {
+        match $e {
+            $crate::ControlFlow::Continue(c) => c,
+            $crate::ControlFlow::Break(b) => return $crate::ControlFlow::Break(b),
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! lower_goal {
     ($program:expr) => {{
@@ -8,8 +19,8 @@ macro_rules! lower_goal {
 
 #[macro_export]
 macro_rules! subst {
-    ($($ty:expr),*) => {
-        $crate::Subst::intern(LogicInterner, [$($ty)*])
+    ($($arg:expr),*) => {
+        $crate::Subst::intern(LogicInterner, [$($arg.into_generic_arg(LogicInterner)),*])
     };
 }
 
@@ -21,12 +32,16 @@ macro_rules! domain_goal {
             trait_ref: $trait_ref,
         }))
     };
+    (eq $lhs:expr, $rhs:expr) => {
+        DomainGoal::Holds(Constraint::Eq(EqConstraint { lhs: $lhs, rhs: $rhs }))
+    };
 }
 
 #[macro_export]
 macro_rules! bound {
     ($n:literal, $bound:expr) => {{
-        let variables = Variables::intern(LogicInterner, (0..$n).map(|_| Variable::new()));
+        let variables =
+            Variables::intern(LogicInterner, (0..$n).map(|_| Variable::new(VariableKind::Ty)));
         Binders::new(variables, $bound)
     }};
 }
@@ -0,0 +1,78 @@
+//! the orphan rule, an impl is only allowed in the crate owning either its trait or
+//! one of the types it is implemented for, see rfc 2451
+use crate::*;
+
+impl<I: Interner> Program<I> {
+    /// the indices of the impls violating the orphan rule
+    pub fn orphan_impls(&self) -> Vec<usize> {
+        (0..self.impls.len()).filter(|&i| self.is_orphan(&self.impls[i])).collect()
+    }
+
+    /// `impl<P...> Trait<T1...Tn> for T0` is allowed if the trait is local or if some `Ti` is
+    /// local and no `Tj` before it contains an uncovered parameter
+    fn is_orphan(&self, impl_datum: &ImplDatum<I>) -> bool {
+        let krate = &impl_datum.krate;
+        let header = &impl_datum.binders.skip_binders().header;
+        if self.is_local_trait(&header.trait_ref.trait_name, krate) {
+            return false;
+        }
+        let args = header.trait_ref.args.iter().filter_map(|arg| arg.ty(self.interner));
+        for ty in std::iter::once(&header.ty).chain(args) {
+            if self.is_local_ty(ty, krate) {
+                return false;
+            }
+            if self.has_uncovered_param(ty) {
+                return true;
+            }
+        }
+        true
+    }
+
+    /// undeclared traits belong to no crate, so they are never local
+    fn is_local_trait(&self, name: &Ident, krate: &Option<Ident>) -> bool {
+        self.traits
+            .iter()
+            .any(|trait_datum| trait_datum.name == *name && trait_datum.krate == *krate)
+    }
+
+    /// a struct declared in the crate, or a reference or fundamental struct of a local type,
+    /// or a trait object of a local trait
+    fn is_local_ty(&self, ty: &Ty<I>, krate: &Option<Ident>) -> bool {
+        match ty.kind(self.interner) {
+            TyKind::Structure(name, args) => match self.struct_datum(name) {
+                Some(struct_datum) if struct_datum.krate == *krate => true,
+                Some(struct_datum) if struct_datum.fundamental =>
+                    self.fundamental_arg(args).is_some_and(|ty| self.is_local_ty(ty, krate)),
+                _ => false,
+            },
+            TyKind::Ref(_, _, ty) => self.is_local_ty(ty, krate),
+            TyKind::Dyn(DynTy { bounds }) =>
+                bounds.iter().any(|bound| self.is_local_trait(&bound.trait_name, krate)),
+            _ => false,
+        }
+    }
+
+    /// whether the type is a parameter of the impl, possibly behind references or fundamental
+    /// structs, which don't cover it
+    fn has_uncovered_param(&self, ty: &Ty<I>) -> bool {
+        match ty.kind(self.interner) {
+            TyKind::Bound(..) => true,
+            TyKind::Ref(_, _, ty) => self.has_uncovered_param(ty),
+            TyKind::Structure(name, args) => match self.struct_datum(name) {
+                Some(struct_datum) if struct_datum.fundamental =>
+                    self.fundamental_arg(args).is_some_and(|ty| self.has_uncovered_param(ty)),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn struct_datum(&self, name: &Ident) -> Option<&StructDatum<I>> {
+        self.structs.iter().find(|struct_datum| struct_datum.name == *name)
+    }
+
+    /// a fundamental struct is transparent in its first type argument, like `Box<T>` is in `T`
+    fn fundamental_arg<'a>(&self, args: &'a Subst<I>) -> Option<&'a Ty<I>> {
+        args.iter().find_map(|arg| arg.ty(self.interner))
+    }
+}
@@ -0,0 +1,586 @@
+//! renders ir back into surface syntax that the parser accepts
+use crate::*;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// renders `value` as `.logic` source, naming bound variables as it goes
+pub fn print<I: Interner, T: Print<I> + ?Sized>(interner: I, value: &T) -> String {
+    // the first pass only discovers the type names that occur in `value`,
+    // the second pass avoids them when naming variables so they don't get captured on reparsing
+    let mut printer = Printer::new(interner, HashSet::new());
+    value.print(&mut printer);
+    let mut printer = Printer::new(interner, printer.ty_names);
+    value.print(&mut printer);
+    printer.out
+}
+
+pub trait Print<I: Interner> {
+    fn print(&self, printer: &mut Printer<I>);
+}
+
+pub struct Printer<I: Interner> {
+    interner: I,
+    out: String,
+    /// names of the variables bound by each enclosing binder, innermost last
+    scopes: Vec<Vec<Symbol>>,
+    /// names that must not be given to variables
+    reserved: HashSet<Symbol>,
+    /// names of all types printed so far
+    ty_names: HashSet<Symbol>,
+}
+
+/// binding strength of the goal connectives, weakest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Prec {
+    Or,
+    And,
+    Atom,
+}
+
+impl<I: Interner> Printer<I> {
+    fn new(interner: I, reserved: HashSet<Symbol>) -> Self {
+        Self { interner, reserved, out: String::new(), scopes: vec![], ty_names: HashSet::new() }
+    }
+
+    fn write(&mut self, s: impl std::fmt::Display) {
+        write!(self.out, "{}", s).expect("writing to a `String` is infallible")
+    }
+
+    fn comma_sep<T>(&mut self, xs: &[T], f: impl FnMut(&mut Self, &T)) {
+        self.sep(xs, ", ", f)
+    }
+
+    fn sep<T>(&mut self, xs: &[T], sep: &str, mut f: impl FnMut(&mut Self, &T)) {
+        for (i, x) in xs.iter().enumerate() {
+            if i > 0 {
+                self.write(sep);
+            }
+            f(self, x);
+        }
+    }
+
+    fn generic_args(&mut self, args: &Subst<I>) {
+        if args.is_empty() {
+            return;
+        }
+        self.write("<");
+        self.comma_sep(args.as_slice(), |this, arg| arg.print(this));
+        self.write(">");
+    }
+
+    fn var_name(kind: VariableKind, i: usize) -> Symbol {
+        const TY_NAMES: [&str; 7] = ["T", "U", "V", "W", "X", "Y", "Z"];
+        const LIFETIME_NAMES: [&str; 5] = ["'a", "'b", "'c", "'d", "'e"];
+        const CONST_NAMES: [&str; 3] = ["N", "M", "K"];
+        let names = match kind {
+            VariableKind::Ty => &TY_NAMES[..],
+            VariableKind::Lifetime => &LIFETIME_NAMES[..],
+            VariableKind::Const(..) => &CONST_NAMES[..],
+        };
+        let name = names[i % names.len()];
+        match i / names.len() {
+            0 => Symbol::from(name),
+            n => Symbol::from(format!("{}{}", name, n)),
+        }
+    }
+
+    /// picks names for new variables of the given kinds, they must be distinct from every
+    /// variable in scope as the lowering rejects shadowing
+    fn fresh_names(&self, kinds: impl IntoIterator<Item = VariableKind>) -> Vec<Symbol> {
+        let mut names = vec![];
+        for kind in kinds {
+            let is_taken = |name: &Symbol| {
+                self.reserved.contains(name)
+                    || self.scopes.iter().flatten().chain(&names).any(|x| x == name)
+            };
+            let name = (0..).map(|i| Self::var_name(kind, i)).find(|name| !is_taken(name));
+            names.push(name.unwrap());
+        }
+        names
+    }
+
+    /// prints `<T, U, ...>` for the binders and then the bound value with those names in scope
+    fn binders<T>(&mut self, binders: &Variables<I>, f: impl FnOnce(&mut Self) -> T) -> T {
+        let kinds = binders.iter().map(|var| var.kind).collect::<Vec<_>>();
+        let names = self.fresh_names(kinds.iter().copied());
+        self.write("<");
+        self.vars(&kinds, &names);
+        self.write(">");
+        self.enter_scope(names, f)
+    }
+
+    /// prints the `<T, U, ...>` of a declaration, which is omitted if there are no names
+    fn params(&mut self, kinds: &[VariableKind], names: &[Symbol]) {
+        if names.is_empty() {
+            return;
+        }
+        self.write("<");
+        self.vars(kinds, names);
+        self.write(">");
+    }
+
+    /// the names of the variables, separated by commas and with const variables marked
+    fn vars(&mut self, kinds: &[VariableKind], names: &[Symbol]) {
+        let vars = kinds.iter().zip(names).collect::<Vec<_>>();
+        self.comma_sep(&vars, |this, &(&kind, name)| match kind {
+            VariableKind::Const(Some(ty)) => this.write(format!("const {}: {}", name, ty)),
+            VariableKind::Const(None) => this.write(format!("const {}", name)),
+            _ => this.write(name),
+        });
+    }
+
+    fn where_clauses(&mut self, where_clauses: &[Constraint<I>]) {
+        if !where_clauses.is_empty() {
+            self.write(" where ");
+            self.comma_sep(where_clauses, |this, constraint| constraint.print(this));
+        }
+    }
+
+    /// prints the attributes of a declaration, each followed by a space,
+    /// `attr` is an attribute without arguments such as `fundamental`
+    fn attrs(&mut self, attr: Option<&str>, krate: &Option<Ident>) {
+        if let Some(attr) = attr {
+            self.write(format_args!("#[{}] ", attr));
+        }
+        if let Some(krate) = krate {
+            self.write("#[crate(");
+            self.write(krate);
+            self.write(")] ");
+        }
+    }
+
+    /// prints ` { item item ... }` on a single line
+    fn body<T>(&mut self, items: &[T], mut f: impl FnMut(&mut Self, &T)) {
+        self.write(" {");
+        for item in items {
+            self.write(" ");
+            f(self, item);
+        }
+        self.write(if items.is_empty() { "}" } else { " }" });
+    }
+
+    fn enter_scope<T>(&mut self, names: Vec<Symbol>, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.scopes.push(names);
+        let ret = f(self);
+        self.scopes.pop();
+        ret
+    }
+
+    fn bound_var(&mut self, bound: BoundVar) {
+        let name = self
+            .scopes
+            .len()
+            .checked_sub(bound.debruijn.index() + 1)
+            .and_then(|scope| self.scopes[scope].get(bound.index))
+            .cloned();
+        match name {
+            Some(name) => self.write(name),
+            // there is no surface syntax for escaping bound variables
+            None => self.write(format_args!("{:?}", bound)),
+        }
+    }
+
+    fn goal_prec(&mut self, goal: &Goal<I>, prec: Prec) {
+        let goal_prec = match goal.data(self.interner) {
+            GoalData::Or(..) => Prec::Or,
+            GoalData::And(..) => Prec::And,
+            _ => Prec::Atom,
+        };
+        if goal_prec < prec {
+            self.write("(");
+            goal.print(self);
+            self.write(")");
+        } else {
+            goal.print(self)
+        }
+    }
+}
+
+impl<I: Interner> Print<I> for Program<I> {
+    fn print(&self, printer: &mut Printer<I>) {
+        for struct_datum in &self.structs {
+            struct_datum.print(printer);
+            printer.write("\n");
+        }
+        for trait_datum in &self.traits {
+            trait_datum.print(printer);
+            printer.write("\n");
+        }
+        for opaque_ty_datum in &self.opaque_tys {
+            opaque_ty_datum.print(printer);
+            printer.write("\n");
+        }
+        for impl_datum in &self.impls {
+            impl_datum.print(printer);
+            printer.write("\n");
+        }
+        for clause in &self.clauses {
+            clause.print(printer);
+            printer.write(".\n");
+        }
+    }
+}
+
+impl<I: Interner> Print<I> for StructDatum<I> {
+    fn print(&self, printer: &mut Printer<I>) {
+        printer.attrs(self.fundamental.then_some("fundamental"), &self.krate);
+        printer.write("struct ");
+        printer.write(&self.name);
+        let kinds = self.binders.binders.iter().map(|var| var.kind).collect::<Vec<_>>();
+        let names = printer.fresh_names(kinds.iter().copied());
+        printer.params(&kinds, &names);
+        printer.enter_scope(names, |printer| {
+            printer.where_clauses(&self.binders.skip_binders().where_clauses);
+            printer.write(" {}");
+        })
+    }
+}
+
+impl<I: Interner> Print<I> for TraitDatum<I> {
+    fn print(&self, printer: &mut Printer<I>) {
+        printer.attrs(self.marker.then_some("marker"), &self.krate);
+        printer.write("trait ");
+        printer.write(&self.name);
+        // the first binder is the implicit `Self`
+        let kinds = self.binders.binders.iter().skip(1).map(|var| var.kind).collect::<Vec<_>>();
+        let mut names = vec![Symbol::from("Self")];
+        names.extend(printer.fresh_names(kinds.iter().copied()));
+        printer.params(&kinds, &names[1..]);
+        printer.enter_scope(names, |printer| {
+            printer.where_clauses(&self.binders.skip_binders().where_clauses);
+            printer.body(&self.assoc_tys, |printer, name| {
+                printer.write("type ");
+                printer.write(name);
+                printer.write(";");
+            });
+        })
+    }
+}
+
+impl<I: Interner> Print<I> for ImplDatum<I> {
+    fn print(&self, printer: &mut Printer<I>) {
+        printer.attrs(None, &self.krate);
+        if self.default {
+            printer.write("default ");
+        }
+        printer.write("impl");
+        let kinds = self.binders.binders.iter().map(|var| var.kind).collect::<Vec<_>>();
+        let names = printer.fresh_names(kinds.iter().copied());
+        printer.params(&kinds, &names);
+        printer.enter_scope(names, |printer| {
+            let ImplDatumBound { header, where_clauses, assoc_ty_values } =
+                self.binders.skip_binders();
+            printer.write(" ");
+            header.trait_ref.print(printer);
+            printer.write(" for ");
+            header.ty.print(printer);
+            printer.where_clauses(where_clauses);
+            printer.body(assoc_ty_values, |printer, AssocTyValue { name, ty }| {
+                printer.write("type ");
+                printer.write(name);
+                printer.write(" = ");
+                ty.print(printer);
+                printer.write(";");
+            });
+        })
+    }
+}
+
+impl<I: Interner> Print<I> for OpaqueTyDatum<I> {
+    fn print(&self, printer: &mut Printer<I>) {
+        printer.write("opaque type ");
+        printer.ty_names.insert(self.name.symbol.clone());
+        printer.write(&self.name);
+        let kinds = self.binders.binders.iter().map(|var| var.kind).collect::<Vec<_>>();
+        let names = printer.fresh_names(kinds.iter().copied());
+        printer.params(&kinds, &names);
+        printer.enter_scope(names, |printer| {
+            let OpaqueTyDatumBound { bounds, hidden_ty } = self.binders.skip_binders();
+            if !bounds.is_empty() {
+                printer.write(": ");
+                printer.sep(bounds, " + ", |printer, bound| bound.print(printer));
+            }
+            printer.write(" = ");
+            hidden_ty.print(printer);
+            printer.write(";");
+        })
+    }
+}
+
+impl<I: Interner> Print<I> for Clause<I> {
+    fn print(&self, printer: &mut Printer<I>) {
+        match self.data(printer.interner) {
+            ClauseData::Implies(implication) => implication.print(printer),
+        }
+    }
+}
+
+impl<I: Interner> Print<I> for Binders<Implication<I>> {
+    fn print(&self, printer: &mut Printer<I>) {
+        let Implication { consequent, condition } = &self.quantified;
+        let is_fact = matches!(condition.data(printer.interner), GoalData::True);
+        if self.binders.is_empty() {
+            // there is nothing to name, but the binder still shifts the de Bruijn indices
+            return printer.enter_scope(vec![], |printer| {
+                consequent.print(printer);
+                if !is_fact {
+                    printer.write(" :- ");
+                    condition.print(printer);
+                }
+            });
+        }
+
+        printer.write("for");
+        printer.binders(&self.binders, |printer| {
+            printer.write(" { ");
+            consequent.print(printer);
+            // a quantified clause always requires a condition syntactically
+            printer.write(" :- ");
+            condition.print(printer);
+            printer.write(" }");
+        })
+    }
+}
+
+impl<I: Interner> Print<I> for Goal<I> {
+    fn print(&self, printer: &mut Printer<I>) {
+        match self.data(printer.interner) {
+            GoalData::DomainGoal(domain_goal) => domain_goal.print(printer),
+            GoalData::Quantified(quantifier, goal) => {
+                printer.write(match quantifier {
+                    Quantifier::Exists => "exists",
+                    Quantifier::ForAll => "forall",
+                });
+                printer.binders(&goal.binders, |printer| {
+                    printer.write(" { ");
+                    goal.quantified.print(printer);
+                    printer.write(" }");
+                })
+            }
+            GoalData::And(lhs, rhs) => {
+                printer.goal_prec(lhs, Prec::Atom);
+                printer.write(", ");
+                printer.goal_prec(rhs, Prec::And);
+            }
+            GoalData::Or(lhs, rhs) => {
+                printer.goal_prec(lhs, Prec::And);
+                printer.write("; ");
+                printer.goal_prec(rhs, Prec::Or);
+            }
+            GoalData::Implies(clause, goal) => {
+                printer.write("if (");
+                clause.print(printer);
+                printer.write(") { ");
+                goal.print(printer);
+                printer.write(" }");
+            }
+            GoalData::Not(goal) => {
+                printer.write("not { ");
+                goal.print(printer);
+                printer.write(" }");
+            }
+            GoalData::True => printer.write("true"),
+        }
+    }
+}
+
+impl<I: Interner> Print<I> for DomainGoal<I> {
+    fn print(&self, printer: &mut Printer<I>) {
+        match self {
+            DomainGoal::Holds(constraint) => constraint.print(printer),
+            DomainGoal::WellFormed(wf) => {
+                printer.write("WellFormed(");
+                match wf {
+                    WellFormed::Ty(ty) => ty.print(printer),
+                    WellFormed::Trait(impl_constraint) => impl_constraint.print(printer),
+                }
+                printer.write(")");
+            }
+            DomainGoal::Normalize(Normalize { alias, ty }) => {
+                printer.write("Normalize(");
+                alias.print(printer);
+                printer.write(" -> ");
+                ty.print(printer);
+                printer.write(")");
+            }
+            DomainGoal::FromEnv(from_env) => {
+                printer.write("FromEnv(");
+                match from_env {
+                    FromEnv::Ty(ty) => ty.print(printer),
+                    FromEnv::Trait(impl_constraint) => impl_constraint.print(printer),
+                }
+                printer.write(")");
+            }
+            DomainGoal::Reveal => printer.write("Reveal"),
+        }
+    }
+}
+
+impl<I: Interner> Print<I> for Constraint<I> {
+    fn print(&self, printer: &mut Printer<I>) {
+        match self {
+            Constraint::Implemented(impl_constraint) => impl_constraint.print(printer),
+            Constraint::Predicate(Predicate { name, args }) => {
+                printer.write(name);
+                printer.write("(");
+                printer.comma_sep(args.as_slice(), |printer, arg| arg.print(printer));
+                printer.write(")");
+            }
+            Constraint::Eq(EqConstraint { lhs, rhs }) => {
+                lhs.print(printer);
+                printer.write(" = ");
+                rhs.print(printer);
+            }
+            Constraint::LifetimeOutlives(LifetimeOutlives { a, b }) => {
+                a.print(printer);
+                printer.write(": ");
+                b.print(printer);
+            }
+            Constraint::TyOutlives(TyOutlives { ty, lifetime }) => {
+                ty.print(printer);
+                printer.write(": ");
+                lifetime.print(printer);
+            }
+        }
+    }
+}
+
+impl<I: Interner> Print<I> for ImplConstraint<I> {
+    fn print(&self, printer: &mut Printer<I>) {
+        self.ty.print(printer);
+        printer.write(": ");
+        self.trait_ref.print(printer);
+    }
+}
+
+impl<I: Interner> Print<I> for ProjectionTy<I> {
+    fn print(&self, printer: &mut Printer<I>) {
+        printer.write("<");
+        self.ty.print(printer);
+        printer.write(" as ");
+        self.trait_ref.print(printer);
+        printer.write(">::");
+        printer.write(&self.name);
+    }
+}
+
+impl<I: Interner> Print<I> for OpaqueTy<I> {
+    fn print(&self, printer: &mut Printer<I>) {
+        printer.ty_names.insert(self.name.symbol.clone());
+        printer.write(&self.name);
+        printer.generic_args(&self.args);
+    }
+}
+
+impl<I: Interner> Print<I> for AliasTy<I> {
+    fn print(&self, printer: &mut Printer<I>) {
+        match self {
+            AliasTy::Projection(projection) => projection.print(printer),
+            AliasTy::Opaque(opaque) => opaque.print(printer),
+        }
+    }
+}
+
+impl<I: Interner> Print<I> for TraitRef<I> {
+    fn print(&self, printer: &mut Printer<I>) {
+        printer.write(&self.trait_name);
+        printer.generic_args(&self.args);
+    }
+}
+
+impl<I: Interner> Print<I> for Ty<I> {
+    fn print(&self, printer: &mut Printer<I>) {
+        match self.kind(printer.interner) {
+            &TyKind::Bound(bound) => printer.bound_var(bound),
+            // neither of these have surface syntax
+            TyKind::Infer(infer) => printer.write(format_args!("{:?}", infer)),
+            TyKind::Placeholder(placeholder) => printer.write(format_args!("{:?}", placeholder)),
+            TyKind::Structure(name, args) => {
+                printer.ty_names.insert(name.symbol.clone());
+                printer.write(name);
+                printer.generic_args(args);
+            }
+            TyKind::Projection(projection) => projection.print(printer),
+            TyKind::Opaque(opaque) => opaque.print(printer),
+            TyKind::Tuple(tys) => {
+                printer.write("(");
+                printer.comma_sep(tys.as_slice(), |printer, ty| ty.print(printer));
+                printer.write(if tys.len() == 1 { ",)" } else { ")" });
+            }
+            TyKind::Ref(mutability, lifetime, ty) => {
+                printer.write("&");
+                lifetime.print(printer);
+                printer.write(" ");
+                printer.write(mutability);
+                ty.print(printer);
+            }
+            TyKind::Slice(ty) => {
+                printer.write("[");
+                ty.print(printer);
+                printer.write("]");
+            }
+            TyKind::Array(ty, len) => {
+                printer.write("[");
+                ty.print(printer);
+                printer.write("; ");
+                len.print(printer);
+                printer.write("]");
+            }
+            TyKind::Fn(FnSig { params, ret }) => {
+                printer.write("fn(");
+                printer.comma_sep(params.as_slice(), |printer, ty| ty.print(printer));
+                printer.write(")");
+                // the return type defaults to `()`
+                if ret.kind(printer.interner) != &TyKind::unit(printer.interner) {
+                    printer.write(" -> ");
+                    ret.print(printer);
+                }
+            }
+            TyKind::Never => printer.write("!"),
+            TyKind::Dyn(DynTy { bounds }) => {
+                printer.write("dyn ");
+                printer.sep(bounds, " + ", |printer, bound| bound.print(printer));
+            }
+        }
+    }
+}
+
+impl<I: Interner> Print<I> for Lifetime<I> {
+    fn print(&self, printer: &mut Printer<I>) {
+        match self.data(printer.interner) {
+            &LifetimeData::Bound(bound) => printer.bound_var(bound),
+            LifetimeData::Static => printer.write("'static"),
+            // neither of these have surface syntax
+            data @ (LifetimeData::Infer(..) | LifetimeData::Placeholder(..)) =>
+                printer.write(format_args!("{:?}", data)),
+        }
+    }
+}
+
+impl<I: Interner> Print<I> for Const<I> {
+    fn print(&self, printer: &mut Printer<I>) {
+        match self.data(printer.interner) {
+            &ConstData::Bound(bound) => printer.bound_var(bound),
+            ConstData::Value(value) => printer.write(value),
+            // neither of these have surface syntax
+            data @ (ConstData::Infer(..) | ConstData::Placeholder(..)) =>
+                printer.write(format_args!("{:?}", data)),
+        }
+    }
+}
+
+impl<I: Interner> Print<I> for GenericArg<I> {
+    fn print(&self, printer: &mut Printer<I>) {
+        match self.data(printer.interner) {
+            GenericArgData::Ty(ty) => ty.print(printer),
+            GenericArgData::Lifetime(lifetime) => lifetime.print(printer),
+            GenericArgData::Const(constant) => constant.print(printer),
+        }
+    }
+}
+
+impl<I: Interner> Display for Program<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", print(self.interner, self))
+    }
+}
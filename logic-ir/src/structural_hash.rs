@@ -0,0 +1,96 @@
+//! hashing values by their contents, which `Hash` only does for interned values if the interner
+//! hashes them by their contents
+use crate::*;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+pub trait StructuralHash<I: Interner> {
+    /// hashes the data of the interned values within `self` rather than the interned values,
+    /// so equal values have the same hash for every interner
+    fn structural_hash<H: Hasher>(&self, interner: I, state: &mut H);
+}
+
+impl<I: Interner> StructuralHash<I> for TyData<I> {
+    fn structural_hash<H: Hasher>(&self, interner: I, state: &mut H) {
+        // the flags are computed from the kind
+        self.kind.structural_hash(interner, state)
+    }
+}
+
+impl<I: Interner, T: StructuralHash<I>> StructuralHash<I> for [T] {
+    fn structural_hash<H: Hasher>(&self, interner: I, state: &mut H) {
+        self.len().hash(state);
+        for value in self {
+            value.structural_hash(interner, state);
+        }
+    }
+}
+
+impl<I: Interner, T: StructuralHash<I>> StructuralHash<I> for Vec<T> {
+    fn structural_hash<H: Hasher>(&self, interner: I, state: &mut H) {
+        self.as_slice().structural_hash(interner, state)
+    }
+}
+
+impl<I: Interner, T: StructuralHash<I>> StructuralHash<I> for Option<T> {
+    fn structural_hash<H: Hasher>(&self, interner: I, state: &mut H) {
+        self.as_ref().map_or(&[][..], std::slice::from_ref).structural_hash(interner, state)
+    }
+}
+
+impl<I: Interner> StructuralHash<I> for PhantomData<I> {
+    fn structural_hash<H: Hasher>(&self, _interner: I, _state: &mut H) {}
+}
+
+macro_rules! structural_hash_interned {
+    ($interned:ty) => {
+        impl<I: Interner> StructuralHash<I> for $interned {
+            fn structural_hash<H: Hasher>(&self, interner: I, state: &mut H) {
+                self.data(interner).structural_hash(interner, state)
+            }
+        }
+    };
+}
+
+structural_hash_interned!(Ty<I>);
+structural_hash_interned!(Lifetime<I>);
+structural_hash_interned!(Const<I>);
+structural_hash_interned!(GenericArg<I>);
+structural_hash_interned!(Goal<I>);
+structural_hash_interned!(Clause<I>);
+
+macro_rules! structural_hash_interned_slice {
+    ($seq:ty) => {
+        impl<I: Interner> StructuralHash<I> for $seq {
+            fn structural_hash<H: Hasher>(&self, interner: I, state: &mut H) {
+                self.as_slice().structural_hash(interner, state)
+            }
+        }
+    };
+}
+
+structural_hash_interned_slice!(Subst<I>);
+structural_hash_interned_slice!(Goals<I>);
+structural_hash_interned_slice!(Clauses<I>);
+structural_hash_interned_slice!(Variables<I>);
+
+/// types without interned values within them, which `Hash` already hashes by their contents
+macro_rules! structural_hash_by_hash {
+    ($t:ty) => {
+        impl<I: Interner> StructuralHash<I> for $t {
+            fn structural_hash<H: Hasher>(&self, _interner: I, state: &mut H) {
+                self.hash(state)
+            }
+        }
+    };
+}
+
+structural_hash_by_hash!(Ident);
+structural_hash_by_hash!(Quantifier);
+structural_hash_by_hash!(Mutability);
+structural_hash_by_hash!(ConstValue);
+structural_hash_by_hash!(VariableKind);
+structural_hash_by_hash!(UniverseIdx);
+structural_hash_by_hash!(BoundVar);
+structural_hash_by_hash!(PlaceholderVar);
+structural_hash_by_hash!(InferVar<I>);
@@ -1,25 +1,84 @@
 use crate::*;
+use std::cmp::Ordering;
 
 pub trait Substitute<I: Interner>: Fold<I> {
     fn subst(self, interner: I, subst: &Subst<I>) -> Self::Folded;
 }
 
+/// Replaces the variables bound by the (removed) outermost binder of a value.
 struct SubstFolder<'a, I: Interner> {
     interner: I,
     subst: &'a Subst<I>,
 }
 
+impl<I: Interner> SubstFolder<'_, I> {
+    /// the value of a variable of the removed binder,
+    /// or the variable itself adjusted for the removal of that binder
+    fn bound_var(
+        &self,
+        bound: BoundVar,
+        kind: VariableKind,
+        outer_binder: DebruijnIdx,
+    ) -> GenericArg<I> {
+        let BoundVar { debruijn, index } = bound;
+        match debruijn.cmp(&outer_binder) {
+            Ordering::Less => bound.to_generic_arg(self.interner, kind),
+            // the value is placed under the binders entered since, which its own free
+            // variables must skip over
+            Ordering::Equal =>
+                self.subst.at(index).clone().shifted_in_by(self.interner, outer_binder),
+            // the outermost binder is gone, so variables that escape it refer to
+            // one fewer binder
+            Ordering::Greater => BoundVar::new(DebruijnIdx::new(debruijn.index() - 1), index)
+                .to_generic_arg(self.interner, kind),
+        }
+    }
+}
+
 impl<I: Interner> Folder<I> for SubstFolder<'_, I> {
     fn interner(&self) -> I {
         self.interner
     }
 
-    fn fold_ty(&mut self, ty: Ty<I>) -> LogicResult<Ty<I>> {
+    fn fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIdx) -> LogicResult<Ty<I>> {
+        // only the variables of the removed binder and those escaping it change
+        if !ty.flags(self.interner).has_bound_above(outer_binder.index()) {
+            return Ok(ty);
+        }
         match ty.kind(self.interner) {
-            // TODO not a correct impl
-            TyKind::Bound(BoundVar { debruijn, index }) =>
-                Ok(self.subst.as_slice()[*index].clone()),
-            _ => ty.fold_inner_with(self),
+            &TyKind::Bound(bound) => {
+                let arg = self.bound_var(bound, VariableKind::Ty, outer_binder);
+                Ok(arg.assert_ty_ref(self.interner).clone())
+            }
+            _ => ty.fold_inner_with(self, outer_binder),
+        }
+    }
+
+    fn fold_lifetime(
+        &mut self,
+        lifetime: Lifetime<I>,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Lifetime<I>> {
+        match lifetime.data(self.interner) {
+            &LifetimeData::Bound(bound) => {
+                let arg = self.bound_var(bound, VariableKind::Lifetime, outer_binder);
+                Ok(arg.assert_lifetime_ref(self.interner).clone())
+            }
+            _ => lifetime.fold_inner_with(self, outer_binder),
+        }
+    }
+
+    fn fold_const(
+        &mut self,
+        constant: Const<I>,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Const<I>> {
+        match constant.data(self.interner) {
+            &ConstData::Bound(bound) => {
+                let arg = self.bound_var(bound, VariableKind::Const(None), outer_binder);
+                Ok(arg.assert_const_ref(self.interner).clone())
+            }
+            _ => constant.fold_inner_with(self, outer_binder),
         }
     }
 }
@@ -32,6 +91,148 @@ impl<I: Interner> Subst<I> {
 
 impl<I: Interner, T: Fold<I>> Substitute<I> for T {
     fn subst(self, interner: I, subst: &Subst<I>) -> Self::Folded {
-        self.fold_with(&mut SubstFolder { interner, subst }).unwrap()
+        SubstFolder { interner, subst }.fold(self).unwrap()
+    }
+}
+
+pub trait Shift<I: Interner>: Fold<I> {
+    /// adjusts the free bound variables of `self` so it can be placed under one more binder
+    fn shifted_in(self, interner: I) -> Self::Folded;
+
+    /// adjusts the free bound variables of `self` so it can be placed under `by` more binders
+    fn shifted_in_by(self, interner: I, by: DebruijnIdx) -> Self::Folded;
+
+    /// adjusts the free bound variables of `self` so it can be taken out from under one binder,
+    /// which fails if `self` refers to that binder
+    fn shifted_out(self, interner: I) -> LogicResult<Self::Folded>;
+
+    /// adjusts the free bound variables of `self` so it can be taken out from under `by`
+    /// binders, which fails if `self` refers to any of them
+    fn shifted_out_by(self, interner: I, by: DebruijnIdx) -> LogicResult<Self::Folded>;
+}
+
+impl<I: Interner, T: Fold<I>> Shift<I> for T {
+    fn shifted_in(self, interner: I) -> Self::Folded {
+        self.shifted_in_by(interner, DebruijnIdx::ONE)
+    }
+
+    fn shifted_in_by(self, interner: I, by: DebruijnIdx) -> Self::Folded {
+        ShiftIn { interner, by }.fold(self).unwrap()
+    }
+
+    fn shifted_out(self, interner: I) -> LogicResult<Self::Folded> {
+        self.shifted_out_by(interner, DebruijnIdx::ONE)
+    }
+
+    fn shifted_out_by(self, interner: I, by: DebruijnIdx) -> LogicResult<Self::Folded> {
+        ShiftOut { interner, by }.fold(self)
+    }
+}
+
+/// Increases the debruijn index of every free bound variable by `by`.
+struct ShiftIn<I: Interner> {
+    interner: I,
+    by: DebruijnIdx,
+}
+
+impl<I: Interner> Folder<I> for ShiftIn<I> {
+    fn interner(&self) -> I {
+        self.interner
+    }
+
+    fn fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIdx) -> LogicResult<Ty<I>> {
+        if !ty.flags(self.interner).has_bound_above(outer_binder.index()) {
+            return Ok(ty);
+        }
+        match ty.kind(self.interner) {
+            &TyKind::Bound(bound) if bound.debruijn >= outer_binder =>
+                Ok(bound.shifted_in_by(self.by).to_ty(self.interner)),
+            _ => ty.fold_inner_with(self, outer_binder),
+        }
+    }
+
+    fn fold_lifetime(
+        &mut self,
+        lifetime: Lifetime<I>,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Lifetime<I>> {
+        match lifetime.data(self.interner) {
+            &LifetimeData::Bound(bound) if bound.debruijn >= outer_binder =>
+                Ok(bound.shifted_in_by(self.by).to_lifetime(self.interner)),
+            _ => lifetime.fold_inner_with(self, outer_binder),
+        }
+    }
+
+    fn fold_const(
+        &mut self,
+        constant: Const<I>,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Const<I>> {
+        match constant.data(self.interner) {
+            &ConstData::Bound(bound) if bound.debruijn >= outer_binder =>
+                Ok(bound.shifted_in_by(self.by).to_const(self.interner)),
+            _ => constant.fold_inner_with(self, outer_binder),
+        }
+    }
+}
+
+/// Decreases the debruijn index of every free bound variable by `by`, the inverse of [`ShiftIn`].
+struct ShiftOut<I: Interner> {
+    interner: I,
+    by: DebruijnIdx,
+}
+
+impl<I: Interner> ShiftOut<I> {
+    /// `bound` adjusted for the removal of the binders, if it doesn't refer to one of them
+    fn shift(&self, bound: BoundVar, outer_binder: DebruijnIdx) -> LogicResult<BoundVar> {
+        if bound.debruijn < outer_binder {
+            return Ok(bound);
+        }
+        let shifted = bound.shifted_out_by(self.by).ok_or(LogicError::NoSolution)?;
+        // variables that end up bound within the value referred to a removed binder
+        if shifted.debruijn < outer_binder {
+            return Err(LogicError::NoSolution);
+        }
+        Ok(shifted)
+    }
+}
+
+impl<I: Interner> Folder<I> for ShiftOut<I> {
+    fn interner(&self) -> I {
+        self.interner
+    }
+
+    fn fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIdx) -> LogicResult<Ty<I>> {
+        if !ty.flags(self.interner).has_bound_above(outer_binder.index()) {
+            return Ok(ty);
+        }
+        match ty.kind(self.interner) {
+            &TyKind::Bound(bound) => Ok(self.shift(bound, outer_binder)?.to_ty(self.interner)),
+            _ => ty.fold_inner_with(self, outer_binder),
+        }
+    }
+
+    fn fold_lifetime(
+        &mut self,
+        lifetime: Lifetime<I>,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Lifetime<I>> {
+        match lifetime.data(self.interner) {
+            &LifetimeData::Bound(bound) =>
+                Ok(self.shift(bound, outer_binder)?.to_lifetime(self.interner)),
+            _ => lifetime.fold_inner_with(self, outer_binder),
+        }
+    }
+
+    fn fold_const(
+        &mut self,
+        constant: Const<I>,
+        outer_binder: DebruijnIdx,
+    ) -> LogicResult<Const<I>> {
+        match constant.data(self.interner) {
+            &ConstData::Bound(bound) =>
+                Ok(self.shift(bound, outer_binder)?.to_const(self.interner)),
+            _ => constant.fold_inner_with(self, outer_binder),
+        }
     }
 }
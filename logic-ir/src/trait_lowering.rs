@@ -0,0 +1,345 @@
+//! lowers the struct, trait, impl and opaque type declarations of a program into program clauses,
+//! not to be confused with the lowering of the ast into ir
+use crate::*;
+
+impl<I: Interner> Program<I> {
+    /// the clauses written in the program followed by the clauses generated from its declarations
+    pub fn program_clauses(&self) -> Clauses<I> {
+        let mut clauses = self.clauses.as_slice().to_vec();
+        for struct_datum in &self.structs {
+            struct_datum.to_program_clauses(self.interner, &mut clauses);
+        }
+        for trait_datum in &self.traits {
+            trait_datum.to_program_clauses(self.interner, &mut clauses);
+            if trait_datum.object_safe {
+                self.object_clauses(trait_datum, &mut clauses);
+            }
+        }
+        for opaque_ty_datum in &self.opaque_tys {
+            opaque_ty_datum.to_program_clauses(self.interner, &mut clauses);
+        }
+        for impl_datum in &self.impls {
+            impl_datum.to_program_clauses(self.interner, &mut clauses);
+        }
+        Clauses::intern(self.interner, clauses)
+    }
+
+    /// `trait Foo<T...> where Self: Bar<U...> {}` gives `for<T...> { dyn Foo<T...>: Foo<T...> }`
+    /// and `for<T...> { dyn Foo<T...>: Bar<U...> }` for each of its supertraits,
+    /// including the supertraits of its supertraits
+    fn object_clauses(&self, trait_datum: &TraitDatum<I>, clauses: &mut Vec<Clause<I>>) {
+        let interner = self.interner;
+        // the clauses bind the parameters of the trait but not `Self`
+        let binders =
+            Variables::intern(interner, trait_datum.binders.binders.iter().skip(1).cloned());
+        let trait_ref =
+            TraitRef { trait_name: trait_datum.name.clone(), args: bound_vars(interner, &binders) };
+        let dyn_ty = TyKind::Dyn(DynTy { bounds: vec![trait_ref.clone()] }).intern(interner);
+
+        let mut bounds = vec![trait_ref];
+        let mut i = 0;
+        while let Some(TraitRef { trait_name, args }) = bounds.get(i).cloned() {
+            i += 1;
+            let supertrait = match self.traits.iter().find(|datum| datum.name == trait_name) {
+                Some(supertrait) => supertrait,
+                None => continue,
+            };
+            let self_ty = dyn_ty.clone().into_generic_arg(interner);
+            let subst =
+                Subst::intern(interner, std::iter::once(self_ty).chain(args.iter().cloned()));
+            for wc in &supertrait.binders.skip_binders().where_clauses {
+                match subst.apply(interner, wc.clone()) {
+                    Constraint::Implemented(ImplConstraint { ty, trait_ref })
+                        if ty == dyn_ty && !bounds.contains(&trait_ref) =>
+                        bounds.push(trait_ref),
+                    _ => {}
+                }
+            }
+        }
+
+        for trait_ref in bounds {
+            let implication = Binders::new(
+                binders.clone(),
+                Implication {
+                    consequent: DomainGoal::Holds(Constraint::Implemented(ImplConstraint {
+                        ty: dyn_ty.clone(),
+                        trait_ref,
+                    })),
+                    condition: Goal::intern(interner, GoalData::True),
+                },
+            );
+            clauses.push(Clause::intern(interner, ClauseData::Implies(implication)));
+        }
+    }
+}
+
+pub trait ToProgramClauses<I: Interner> {
+    fn to_program_clauses(&self, interner: I, clauses: &mut Vec<Clause<I>>);
+}
+
+impl<I: Interner> ToProgramClauses<I> for StructDatum<I> {
+    /// `struct Foo<T...> where WC {}` gives
+    /// `for<T...> { WellFormed(Foo<T...>) :- WC }` and
+    /// `for<T...> { FromEnv(WC) :- FromEnv(Foo<T...>) }` for each where clause
+    fn to_program_clauses(&self, interner: I, clauses: &mut Vec<Clause<I>>) {
+        let ty = TyKind::Structure(self.name.clone(), bound_vars(interner, &self.binders.binders))
+            .intern(interner);
+        let implication = self.binders.map_ref(|bound| Implication {
+            consequent: DomainGoal::WellFormed(WellFormed::Ty(ty.clone())),
+            condition: where_clauses_goal(interner, &bound.where_clauses),
+        });
+        clauses.push(Clause::intern(interner, ClauseData::Implies(implication)));
+
+        let from_env = DomainGoal::FromEnv(FromEnv::Ty(ty));
+        let where_clauses = &self.binders.skip_binders().where_clauses;
+        implied_bounds(interner, &self.binders.binders, where_clauses, from_env, clauses);
+    }
+}
+
+impl<I: Interner> ToProgramClauses<I> for TraitDatum<I> {
+    /// `trait Foo<T...> where WC {}` gives
+    /// `for<Self, T...> { WellFormed(Self: Foo<T...>) :- Self: Foo<T...>, WellFormed(WC) }`,
+    /// `for<Self, T...> { Self: Foo<T...> :- FromEnv(Self: Foo<T...>) }` and
+    /// `for<Self, T...> { FromEnv(WC) :- FromEnv(Self: Foo<T...>) }` for each where clause
+    fn to_program_clauses(&self, interner: I, clauses: &mut Vec<Clause<I>>) {
+        let bound_vars = bound_vars(interner, &self.binders.binders);
+        let (self_ty, args) = bound_vars.as_slice().split_first().unwrap();
+        let trait_ref = TraitRef {
+            trait_name: self.name.clone(),
+            args: Subst::intern(interner, args.to_vec()),
+        };
+        let self_ty = self_ty.assert_ty_ref(interner).clone();
+        let impl_constraint = ImplConstraint { ty: self_ty, trait_ref };
+        let implication = self.binders.map_ref(|bound| {
+            let implemented = Constraint::Implemented(impl_constraint.clone());
+            let goals = std::iter::once(holds(interner, &implemented))
+                .chain(bound.where_clauses.iter().map(|wc| well_formed(interner, wc)))
+                .collect::<Vec<_>>();
+            Implication {
+                consequent: DomainGoal::WellFormed(WellFormed::Trait(impl_constraint.clone())),
+                condition: conjunction(interner, goals),
+            }
+        });
+        clauses.push(Clause::intern(interner, ClauseData::Implies(implication)));
+
+        let from_env = DomainGoal::FromEnv(FromEnv::Trait(impl_constraint.clone()));
+        let implication = self.binders.map_ref(|_| Implication {
+            consequent: DomainGoal::Holds(Constraint::Implemented(impl_constraint)),
+            condition: Goal::intern(interner, GoalData::DomainGoal(from_env.clone())),
+        });
+        clauses.push(Clause::intern(interner, ClauseData::Implies(implication)));
+
+        let where_clauses = &self.binders.skip_binders().where_clauses;
+        implied_bounds(interner, &self.binders.binders, where_clauses, from_env, clauses);
+    }
+}
+
+impl<I: Interner> ToProgramClauses<I> for ImplDatum<I> {
+    /// `impl<T...> Trait for Ty where WC { type Name = U; }` gives
+    /// `for<T...> { Ty: Trait :- WC }` and
+    /// `for<T...> { Normalize(<Ty as Trait>::Name -> U) :- WC }` for each associated type
+    fn to_program_clauses(&self, interner: I, clauses: &mut Vec<Clause<I>>) {
+        let ImplDatumBound { header, where_clauses, assoc_ty_values } = self.binders.skip_binders();
+        let implication = self.implemented_implication(interner);
+        clauses.push(Clause::intern(interner, ClauseData::Implies(implication)));
+
+        for AssocTyValue { name, ty } in assoc_ty_values {
+            let alias = ProjectionTy {
+                ty: header.ty.clone(),
+                trait_ref: header.trait_ref.clone(),
+                name: name.clone(),
+            };
+            let implication = self.binders.map_ref(|_| Implication {
+                consequent: DomainGoal::Normalize(Normalize {
+                    alias: AliasTy::Projection(alias),
+                    ty: ty.clone(),
+                }),
+                condition: where_clauses_goal(interner, where_clauses),
+            });
+            clauses.push(Clause::intern(interner, ClauseData::Implies(implication)));
+        }
+    }
+}
+
+impl<I: Interner> ToProgramClauses<I> for OpaqueTyDatum<I> {
+    /// `opaque type Foo<T...>: Bound = Ty;` gives `for<T...> { Foo<T...>: Bound :- true }`
+    /// for each of its bounds and `for<T...> { Normalize(Foo<T...> -> Ty) :- Reveal }`,
+    /// so the hidden type is only visible where `Reveal` is assumed
+    fn to_program_clauses(&self, interner: I, clauses: &mut Vec<Clause<I>>) {
+        let OpaqueTyDatumBound { bounds, hidden_ty } = self.binders.skip_binders();
+        let opaque =
+            OpaqueTy { name: self.name.clone(), args: bound_vars(interner, &self.binders.binders) };
+        let opaque_ty = TyKind::Opaque(opaque.clone()).intern(interner);
+        for trait_ref in bounds {
+            let impl_constraint =
+                ImplConstraint { ty: opaque_ty.clone(), trait_ref: trait_ref.clone() };
+            let implication = self.binders.map_ref(|_| Implication {
+                consequent: DomainGoal::Holds(Constraint::Implemented(impl_constraint)),
+                condition: Goal::intern(interner, GoalData::True),
+            });
+            clauses.push(Clause::intern(interner, ClauseData::Implies(implication)));
+        }
+
+        let implication = self.binders.map_ref(|_| Implication {
+            consequent: DomainGoal::Normalize(Normalize {
+                alias: AliasTy::Opaque(opaque),
+                ty: hidden_ty.clone(),
+            }),
+            condition: Goal::intern(interner, GoalData::DomainGoal(DomainGoal::Reveal)),
+        });
+        clauses.push(Clause::intern(interner, ClauseData::Implies(implication)));
+    }
+}
+
+impl<I: Interner> ImplDatum<I> {
+    /// The goal that holds if the impl is well formed, that is, assuming its where clauses
+    /// the trait reference and every type named in the header are well formed.
+    /// `impl<T...> Trait for Ty where WC {}` gives
+    /// `forall<T...> { if (FromEnv(WC)) { WellFormed(Ty: Trait), WellFormed(Ty), ... } }`
+    pub fn well_formed_goal(&self, interner: I) -> Goal<I> {
+        let ImplDatumBound { header, where_clauses, .. } = self.binders.skip_binders();
+        let mut tys = vec![];
+        collect_structure_tys(interner, &header.ty, &mut tys);
+        for ty in header.trait_ref.args.iter().filter_map(|arg| arg.ty(interner)) {
+            collect_structure_tys(interner, ty, &mut tys);
+        }
+        let goals = std::iter::once(DomainGoal::WellFormed(WellFormed::Trait(header.clone())))
+            .chain(tys.into_iter().map(|ty| DomainGoal::WellFormed(WellFormed::Ty(ty))))
+            .map(|domain_goal| Goal::intern(interner, GoalData::DomainGoal(domain_goal)))
+            .collect::<Vec<_>>();
+
+        let goal = where_clauses.iter().rev().fold(conjunction(interner, goals), |goal, wc| {
+            // the fact introduces a binder of its own
+            let fact = Binders::empty(
+                interner,
+                Implication {
+                    consequent: DomainGoal::Holds(wc.clone().shifted_in(interner))
+                        .into_from_env_goal(),
+                    condition: Goal::intern(interner, GoalData::True),
+                },
+            );
+            let clause = Clause::intern(interner, ClauseData::Implies(fact));
+            Goal::intern(interner, GoalData::Implies(clause, goal))
+        });
+        if self.binders.binders.is_empty() {
+            return goal;
+        }
+        let binders = Binders::new(self.binders.binders.clone(), goal);
+        Goal::intern(interner, GoalData::Quantified(Quantifier::ForAll, binders))
+    }
+
+    /// `for<T...> { Ty: Trait :- WC }`, the clause the impl proves its trait with
+    pub fn implemented_implication(&self, interner: I) -> Binders<Implication<I>> {
+        self.binders.map_ref(|bound| Implication {
+            consequent: DomainGoal::Holds(Constraint::Implemented(bound.header.clone())),
+            condition: where_clauses_goal(interner, &bound.where_clauses),
+        })
+    }
+}
+
+/// `for<T...> { FromEnv(WC) :- from_env }` for each of the where clauses,
+/// `from_env` and the where clauses are under the same binders
+fn implied_bounds<I: Interner>(
+    interner: I,
+    binders: &Variables<I>,
+    where_clauses: &[Constraint<I>],
+    from_env: DomainGoal<I>,
+    clauses: &mut Vec<Clause<I>>,
+) {
+    let condition = Goal::intern(interner, GoalData::DomainGoal(from_env));
+    for wc in where_clauses {
+        // equality is builtin and region constraints are only collected,
+        // so there is no way to assume either
+        if matches!(wc, Constraint::Eq(..)) || wc.is_region_constraint() {
+            continue;
+        }
+        let implication = Binders::new(
+            binders.clone(),
+            Implication {
+                consequent: DomainGoal::Holds(wc.clone()).into_from_env_goal(),
+                condition: condition.clone(),
+            },
+        );
+        clauses.push(Clause::intern(interner, ClauseData::Implies(implication)));
+    }
+}
+
+/// the variables bound by the innermost binder, in order
+fn bound_vars<I: Interner>(interner: I, binders: &Variables<I>) -> Subst<I> {
+    let args = binders.iter().enumerate().map(|(index, var)| {
+        BoundVar::new(DebruijnIdx::ZERO, index).to_generic_arg(interner, var.kind)
+    });
+    Subst::intern(interner, args)
+}
+
+/// `ty` and all the types within it that are structures, with duplicates removed,
+/// the builtin types have no declaration to check them against so only the types within them
+/// are collected
+fn collect_structure_tys<I: Interner>(interner: I, ty: &Ty<I>, tys: &mut Vec<Ty<I>>) {
+    let args = match ty.kind(interner) {
+        TyKind::Structure(_, args) => {
+            if !tys.contains(ty) {
+                tys.push(ty.clone());
+            }
+            args
+        }
+        // an opaque type has no where clauses to check, only its arguments do
+        TyKind::Tuple(args) | TyKind::Opaque(OpaqueTy { args, .. }) => args,
+        TyKind::Fn(FnSig { params, ret }) => {
+            collect_structure_tys(interner, ret, tys);
+            params
+        }
+        TyKind::Ref(_, _, ty) | TyKind::Slice(ty) | TyKind::Array(ty, _) =>
+            return collect_structure_tys(interner, ty, tys),
+        TyKind::Dyn(DynTy { bounds }) => {
+            for arg in bounds.iter().flat_map(|bound| bound.args.iter()) {
+                if let Some(ty) = arg.ty(interner) {
+                    collect_structure_tys(interner, ty, tys);
+                }
+            }
+            return;
+        }
+        TyKind::Bound(..)
+        | TyKind::Infer(..)
+        | TyKind::Placeholder(..)
+        | TyKind::Projection(..)
+        | TyKind::Never => return,
+    };
+    for arg in args.iter().filter_map(|arg| arg.ty(interner)) {
+        collect_structure_tys(interner, arg, tys);
+    }
+}
+
+/// the conjunction of the where clauses, or `true` if there are none
+fn where_clauses_goal<I: Interner>(interner: I, where_clauses: &[Constraint<I>]) -> Goal<I> {
+    conjunction(interner, where_clauses.iter().map(|wc| holds(interner, wc)))
+}
+
+fn holds<I: Interner>(interner: I, constraint: &Constraint<I>) -> Goal<I> {
+    Goal::intern(interner, GoalData::DomainGoal(DomainGoal::Holds(constraint.clone())))
+}
+
+/// trait bounds must be well formed, other constraints must simply hold
+fn well_formed<I: Interner>(interner: I, constraint: &Constraint<I>) -> Goal<I> {
+    match constraint {
+        Constraint::Implemented(impl_constraint) => {
+            let well_formed = WellFormed::Trait(impl_constraint.clone());
+            Goal::intern(interner, GoalData::DomainGoal(DomainGoal::WellFormed(well_formed)))
+        }
+        Constraint::Predicate(..)
+        | Constraint::Eq(..)
+        | Constraint::LifetimeOutlives(..)
+        | Constraint::TyOutlives(..) => holds(interner, constraint),
+    }
+}
+
+/// the conjunction of the goals, or `true` if there are none
+fn conjunction<I: Interner>(interner: I, goals: impl IntoIterator<Item = Goal<I>>) -> Goal<I> {
+    let goals = goals.into_iter().collect::<Vec<_>>();
+    let mut goals = goals.into_iter().rev();
+    let last = match goals.next() {
+        Some(goal) => goal,
+        None => return Goal::intern(interner, GoalData::True),
+    };
+    goals.fold(last, |conjunction, goal| Goal::intern(interner, GoalData::And(goal, conjunction)))
+}
@@ -0,0 +1,210 @@
+//! the read-only counterpart of `fold`, visitors walk a value without rebuilding it and can stop
+//! early by breaking
+use crate::Interner;
+use logic_ir::*;
+pub use std::ops::ControlFlow;
+
+pub trait Visit<I: Interner>: Debug {
+    fn visit_with<V: Visitor<I>>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy>;
+}
+
+pub trait SuperVisit<I: Interner>: Visit<I> {
+    fn super_visit_with<V: Visitor<I>>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy>;
+}
+
+pub trait Visitor<I: Interner>: Sized {
+    type BreakTy;
+
+    fn interner(&self) -> I;
+
+    fn visit_ty(&mut self, ty: &Ty<I>) -> ControlFlow<Self::BreakTy> {
+        ty.super_visit_with(self)
+    }
+
+    fn visit_infer_var(&mut self, _infer: InferVar<I>) -> ControlFlow<Self::BreakTy> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_lifetime(&mut self, lifetime: &Lifetime<I>) -> ControlFlow<Self::BreakTy> {
+        lifetime.super_visit_with(self)
+    }
+
+    fn visit_infer_lifetime_var(&mut self, _infer: InferVar<I>) -> ControlFlow<Self::BreakTy> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit_const(&mut self, constant: &Const<I>) -> ControlFlow<Self::BreakTy> {
+        constant.super_visit_with(self)
+    }
+
+    fn visit_infer_const_var(&mut self, _infer: InferVar<I>) -> ControlFlow<Self::BreakTy> {
+        ControlFlow::Continue(())
+    }
+
+    fn visit<T: Visit<I>>(&mut self, visitable: &T) -> ControlFlow<Self::BreakTy> {
+        visitable.visit_with(self)
+    }
+
+    /// visits the bound value, override this to keep track of the binders that have been entered
+    fn visit_binders<T>(&mut self, binders: &Binders<T>) -> ControlFlow<Self::BreakTy>
+    where
+        T: HasInterner<Interner = I> + Visit<I>,
+    {
+        binders.skip_binders().visit_with(self)
+    }
+}
+
+impl<I: Interner> Visit<I> for Ty<I> {
+    fn visit_with<V: Visitor<I>>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy> {
+        visitor.visit_ty(self)
+    }
+}
+
+impl<I: Interner> SuperVisit<I> for Ty<I> {
+    fn super_visit_with<V: Visitor<I>>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy> {
+        match self.kind(visitor.interner()) {
+            &TyKind::Infer(infer) => visitor.visit_infer_var(infer),
+            TyKind::Bound(..) | TyKind::Placeholder(..) | TyKind::Never =>
+                ControlFlow::Continue(()),
+            TyKind::Structure(_, args) | TyKind::Tuple(args) => args.visit_with(visitor),
+            TyKind::Projection(projection) => projection.visit_with(visitor),
+            TyKind::Opaque(opaque) => opaque.visit_with(visitor),
+            TyKind::Ref(_, lifetime, ty) => {
+                try_break!(lifetime.visit_with(visitor));
+                ty.visit_with(visitor)
+            }
+            TyKind::Slice(ty) => ty.visit_with(visitor),
+            TyKind::Array(ty, len) => {
+                try_break!(ty.visit_with(visitor));
+                len.visit_with(visitor)
+            }
+            TyKind::Fn(sig) => sig.visit_with(visitor),
+            TyKind::Dyn(dyn_ty) => dyn_ty.visit_with(visitor),
+        }
+    }
+}
+
+impl<I: Interner> Visit<I> for Lifetime<I> {
+    fn visit_with<V: Visitor<I>>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy> {
+        visitor.visit_lifetime(self)
+    }
+}
+
+impl<I: Interner> SuperVisit<I> for Lifetime<I> {
+    fn super_visit_with<V: Visitor<I>>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy> {
+        match self.data(visitor.interner()) {
+            &LifetimeData::Infer(infer) => visitor.visit_infer_lifetime_var(infer),
+            LifetimeData::Bound(..) | LifetimeData::Placeholder(..) | LifetimeData::Static =>
+                ControlFlow::Continue(()),
+        }
+    }
+}
+
+impl<I: Interner> Visit<I> for Const<I> {
+    fn visit_with<V: Visitor<I>>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy> {
+        visitor.visit_const(self)
+    }
+}
+
+impl<I: Interner> SuperVisit<I> for Const<I> {
+    fn super_visit_with<V: Visitor<I>>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy> {
+        match self.data(visitor.interner()) {
+            &ConstData::Infer(infer) => visitor.visit_infer_const_var(infer),
+            ConstData::Bound(..) | ConstData::Placeholder(..) | ConstData::Value(..) =>
+                ControlFlow::Continue(()),
+        }
+    }
+}
+
+impl<I, T> Visit<I> for Canonical<T>
+where
+    I: Interner,
+    T: Visit<I> + HasInterner<Interner = I>,
+{
+    fn visit_with<V: Visitor<I>>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy> {
+        self.value.visit_with(visitor)
+    }
+}
+
+impl<I: Interner, T: Visit<I>> Visit<I> for [T] {
+    fn visit_with<V: Visitor<I>>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy> {
+        for value in self {
+            try_break!(value.visit_with(visitor));
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+impl<I: Interner, T: Visit<I>> Visit<I> for Vec<T> {
+    fn visit_with<V: Visitor<I>>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy> {
+        self.as_slice().visit_with(visitor)
+    }
+}
+
+impl<I: Interner> Visit<I> for Subst<I> {
+    fn visit_with<V: Visitor<I>>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy> {
+        self.as_slice().visit_with(visitor)
+    }
+}
+
+impl<I: Interner> Visit<I> for Clauses<I> {
+    fn visit_with<V: Visitor<I>>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy> {
+        for clause in self.iter() {
+            try_break!(clause.visit_with(visitor));
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+impl<I: Interner> Visit<I> for Environment<I> {
+    fn visit_with<V: Visitor<I>>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy> {
+        self.clauses.visit_with(visitor)
+    }
+}
+
+impl<I, G> Visit<I> for InEnvironment<G>
+where
+    I: Interner,
+    G: Visit<I> + HasInterner<Interner = I>,
+{
+    fn visit_with<V: Visitor<I>>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy> {
+        try_break!(self.environment.visit_with(visitor));
+        self.goal.visit_with(visitor)
+    }
+}
+
+impl<I: Interner, T> Visit<I> for Binders<T>
+where
+    T: HasInterner<Interner = I> + Visit<I>,
+{
+    fn visit_with<V: Visitor<I>>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy> {
+        visitor.visit_binders(self)
+    }
+}
+
+macro_rules! visit_interned {
+    ($interned:ty) => {
+        impl<I: Interner> Visit<I> for $interned {
+            fn visit_with<V: Visitor<I>>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy> {
+                self.data(visitor.interner()).visit_with(visitor)
+            }
+        }
+    };
+}
+
+visit_interned!(Clause<I>);
+visit_interned!(Goal<I>);
+visit_interned!(GenericArg<I>);
+
+macro_rules! const_visit {
+    ($t:ty) => {
+        impl<I: Interner> Visit<I> for $t {
+            fn visit_with<V: Visitor<I>>(&self, _visitor: &mut V) -> ControlFlow<V::BreakTy> {
+                ControlFlow::Continue(())
+            }
+        }
+    };
+}
+
+const_visit!(Ident);
+const_visit!(Quantifier);
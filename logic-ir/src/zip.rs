@@ -3,6 +3,8 @@ use crate::*;
 pub trait Zipper<I: Interner>: Sized {
     fn interner(&self) -> I;
     fn zip_tys(&mut self, a: &Ty<I>, b: &Ty<I>) -> LogicResult<()>;
+    fn zip_lifetimes(&mut self, a: &Lifetime<I>, b: &Lifetime<I>) -> LogicResult<()>;
+    fn zip_consts(&mut self, a: &Const<I>, b: &Const<I>) -> LogicResult<()>;
     fn zip_binders<T>(&mut self, a: &Binders<T>, b: &Binders<T>) -> LogicResult<()>
     where
         T: HasInterner<Interner = I> + Zip<I>;
@@ -42,6 +44,18 @@ impl<I: Interner> Zip<I> for Ty<I> {
     }
 }
 
+impl<I: Interner> Zip<I> for Lifetime<I> {
+    fn zip_with<Z: Zipper<I>>(zipper: &mut Z, a: &Self, b: &Self) -> LogicResult<()> {
+        zipper.zip_lifetimes(a, b)
+    }
+}
+
+impl<I: Interner> Zip<I> for Const<I> {
+    fn zip_with<Z: Zipper<I>>(zipper: &mut Z, a: &Self, b: &Self) -> LogicResult<()> {
+        zipper.zip_consts(a, b)
+    }
+}
+
 impl<I: Interner, T> Zip<I> for Binders<T>
 where
     T: HasInterner<Interner = I> + Zip<I>,
@@ -65,6 +79,12 @@ impl<I: Interner, T: Zip<I>> Zip<I> for [T] {
     }
 }
 
+impl<I: Interner, T: Zip<I>> Zip<I> for Vec<T> {
+    fn zip_with<Z: Zipper<I>>(zipper: &mut Z, xs: &Self, ys: &Self) -> LogicResult<()> {
+        Zip::zip_with(zipper, xs.as_slice(), ys.as_slice())
+    }
+}
+
 impl<I: Interner> Zip<I> for Subst<I> {
     fn zip_with<Z: Zipper<I>>(zipper: &mut Z, a: &Self, b: &Self) -> LogicResult<()> {
         Zip::zip_with(zipper, a.as_slice(), b.as_slice())
@@ -104,3 +124,4 @@ zip_eq!(InferVar<I>);
 
 zip_data!(Clause);
 zip_data!(Goal);
+zip_data!(GenericArg);
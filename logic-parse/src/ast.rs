@@ -6,12 +6,14 @@ use std::hash::{Hash, Hasher};
 #[derive(Debug, Eq, Clone, PartialEq)]
 pub struct Program {
     pub items: Vec<Item>,
+    /// comments after the last item
+    pub trailing_comments: Vec<Comment>,
 }
 
 impl Display for Program {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         for item in &self.items {
-            writeln!(f, "{}.", item)?;
+            writeln!(f, "{}", item)?;
         }
         Ok(())
     }
@@ -19,41 +21,274 @@ impl Display for Program {
 
 impl Program {
     pub fn new(items: Vec<Item>) -> Self {
-        Self { items }
+        Self { items, trailing_comments: vec![] }
+    }
+}
+
+#[derive(Debug, Eq, Clone)]
+pub struct Item {
+    pub span: Span,
+    /// the comments preceding this item (and any inside it)
+    pub comments: Vec<Comment>,
+    /// a line comment following the item on its last line
+    pub trailing_comment: Option<Comment>,
+    pub kind: ItemKind,
+}
+
+impl Item {
+    pub fn new(span: Span, kind: ItemKind) -> Self {
+        Self { span, kind, comments: vec![], trailing_comment: None }
+    }
+
+    pub fn unspanned(kind: ItemKind) -> Self {
+        Self::new(Span::new(0, 0), kind)
+    }
+}
+
+// spans are ignored for the same reason as they are for `Ident`
+impl PartialEq for Item {
+    fn eq(&self, other: &Self) -> bool {
+        self.comments == other.comments
+            && self.trailing_comment == other.trailing_comment
+            && self.kind == other.kind
+    }
+}
+
+impl Display for Item {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ItemKind::Clause(clause) => write!(f, "{}.", clause),
+            ItemKind::Struct(struct_def) => write!(f, "{}", struct_def),
+            ItemKind::Trait(trait_def) => write!(f, "{}", trait_def),
+            ItemKind::Impl(impl_def) => write!(f, "{}", impl_def),
+            ItemKind::OpaqueTy(opaque_ty_def) => write!(f, "{}", opaque_ty_def),
+        }
     }
 }
 
 #[derive(Debug, Eq, Clone, PartialEq)]
-pub enum Item {
+pub enum ItemKind {
     Clause(Clause),
+    Struct(StructDef),
+    Trait(TraitDef),
+    Impl(ImplDef),
+    OpaqueTy(OpaqueTyDef),
 }
 
-impl Display for Item {
+/// `#[name]` or `#[name(arg)]` before a declaration, which ones a declaration can have is
+/// checked by the lowering so that their names are still ordinary identifiers
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub struct Attr {
+    pub name: Ident,
+    pub arg: Option<Ident>,
+}
+
+impl Display for Attr {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        match self {
-            Item::Clause(clause) => write!(f, "{}", clause),
+        match &self.arg {
+            Some(arg) => write!(f, "#[{}({})]", self.name, arg),
+            None => write!(f, "#[{}]", self.name),
         }
     }
 }
 
+/// `#[fundamental] #[crate(name)] struct Name<T, ...> where ... {}`, the attributes are
+/// optional, `#[crate(name)]` is the crate owning the struct and `#[fundamental]` makes the
+/// struct transparent to the orphan rule like `Box`
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub struct StructDef {
+    pub attrs: Vec<Attr>,
+    pub name: Ident,
+    pub vars: Vec<Var>,
+    pub where_clauses: Vec<Constraint>,
+}
+
+impl Display for StructDef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", fmt_attrs(&self.attrs))?;
+        write!(f, "struct {}{}", self.name, fmt_generic_args(&self.vars))?;
+        write!(f, "{} {{}}", fmt_where_clauses(&self.where_clauses))
+    }
+}
+
+/// `#[marker] #[crate(name)] trait Name<T, ...> where ... { type Assoc; ... }`, `Self` is in
+/// scope implicitly, the impls of a `#[marker]` trait may overlap
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub struct TraitDef {
+    pub attrs: Vec<Attr>,
+    pub name: Ident,
+    pub vars: Vec<Var>,
+    pub where_clauses: Vec<Constraint>,
+    /// the names of the associated types
+    pub assoc_tys: Vec<Ident>,
+}
+
+impl Display for TraitDef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", fmt_attrs(&self.attrs))?;
+        write!(f, "trait {}{}", self.name, fmt_generic_args(&self.vars))?;
+        let assoc_tys = self.assoc_tys.iter().map(|name| format!("type {};", name));
+        write!(f, "{} {}", fmt_where_clauses(&self.where_clauses), fmt_body(assoc_tys))
+    }
+}
+
+/// `#[crate(name)] default impl<T, ...> Trait<...> for Ty where ... { type Assoc = Ty; ... }`
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub struct ImplDef {
+    pub attrs: Vec<Attr>,
+    /// whether more specific impls may specialize this one
+    pub default: bool,
+    pub vars: Vec<Var>,
+    pub trait_ref: TraitRef,
+    pub self_ty: Ty,
+    pub where_clauses: Vec<Constraint>,
+    pub assoc_ty_values: Vec<AssocTyValue>,
+}
+
+impl Display for ImplDef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", fmt_attrs(&self.attrs), fmt_default(self.default))?;
+        write!(f, "impl{} {} for {}", fmt_generic_args(&self.vars), self.trait_ref, self.self_ty)?;
+        let assoc_ty_values = self.assoc_ty_values.iter().map(AssocTyValue::to_string);
+        write!(f, "{} {}", fmt_where_clauses(&self.where_clauses), fmt_body(assoc_ty_values))
+    }
+}
+
+/// `opaque type Name<T, ...>: Trait + ... = Ty;`, the hidden type is only visible under `Reveal`
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub struct OpaqueTyDef {
+    pub name: Ident,
+    pub vars: Vec<Var>,
+    pub bounds: Vec<TraitRef>,
+    pub hidden_ty: Ty,
+}
+
+impl Display for OpaqueTyDef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "opaque type {}{}", self.name, fmt_generic_args(&self.vars))?;
+        if !self.bounds.is_empty() {
+            write!(f, ": {}", util::join(&self.bounds, " + "))?;
+        }
+        write!(f, " = {};", self.hidden_ty)
+    }
+}
+
+/// `type Name = Ty;` in an impl
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub struct AssocTyValue {
+    pub name: Ident,
+    pub ty: Ty,
+}
+
+impl Display for AssocTyValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "type {} = {};", self.name, self.ty)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentKind {
+    /// `// ...`
+    Line,
+    /// `/* ... */`, possibly nested
+    Block,
+}
+
+#[derive(Debug, Eq, Clone)]
+pub struct Comment {
+    pub span: Span,
+    pub kind: CommentKind,
+    /// the source text of the comment including its delimiters
+    pub text: String,
+}
+
+impl Comment {
+    /// the text of the comment without its delimiters
+    pub fn content(&self) -> &str {
+        match self.kind {
+            CommentKind::Line => &self.text[2..],
+            CommentKind::Block => &self.text[2..self.text.len() - 2],
+        }
+    }
+}
+
+impl PartialEq for Comment {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.text == other.text
+    }
+}
+
+impl Display for Comment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
 #[derive(Debug, Eq, Clone, PartialEq)]
 pub enum Goal {
     DomainGoal(DomainGoal),
     And(Box<Goal>, Box<Goal>),
     Or(Box<Goal>, Box<Goal>),
     Implies(Box<Clause>, Box<Goal>),
-    Exists(Vec<Var>, Box<Goal>), // Quantified(Quantifier, Binders, Goal),,
-                                 // todo exists, impl, forall, implies
+    Exists(Vec<Var>, Box<Goal>),
+    ForAll(Vec<Var>, Box<Goal>),
+    /// negation as failure, `not { Goal }`
+    Not(Box<Goal>),
+    True,
+}
+
+impl Goal {
+    fn fmt_prec(&self, f: &mut Formatter<'_>, prec: GoalPrec) -> fmt::Result {
+        write!(f, "{}", self.to_string_prec(prec))
+    }
+
+    /// wraps `self` in parentheses if it binds looser than `prec`
+    pub(crate) fn to_string_prec(&self, prec: GoalPrec) -> String {
+        if self.prec() < prec {
+            format!("({})", self)
+        } else {
+            self.to_string()
+        }
+    }
+
+    fn prec(&self) -> GoalPrec {
+        match self {
+            Goal::Or(..) => GoalPrec::Or,
+            Goal::And(..) => GoalPrec::And,
+            _ => GoalPrec::Atom,
+        }
+    }
+}
+
+/// binding strength of the goal connectives, weakest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum GoalPrec {
+    Or,
+    And,
+    Atom,
 }
 
 impl Display for Goal {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Goal::DomainGoal(domain_goal) => write!(f, "{}", domain_goal),
-            Goal::And(lhs, rhs) => write!(f, "{} & {}", lhs, rhs),
-            Goal::Or(lhs, rhs) => write!(f, "{} | {}", lhs, rhs),
-            Goal::Implies(clause, goal) => write!(f, "{} => {}", clause, goal),
-            Goal::Exists(vars, goal) => write!(f, "∃{}.{}", util::join(vars, ","), goal),
+            Goal::And(lhs, rhs) => {
+                lhs.fmt_prec(f, GoalPrec::Atom)?;
+                write!(f, ", ")?;
+                rhs.fmt_prec(f, GoalPrec::And)
+            }
+            Goal::Or(lhs, rhs) => {
+                lhs.fmt_prec(f, GoalPrec::And)?;
+                write!(f, "; ")?;
+                rhs.fmt_prec(f, GoalPrec::Or)
+            }
+            Goal::Implies(clause, goal) => write!(f, "if ({}) {{ {} }}", clause, goal),
+            Goal::Exists(vars, goal) =>
+                write!(f, "exists<{}> {{ {} }}", util::join(vars, ", "), goal),
+            Goal::ForAll(vars, goal) =>
+                write!(f, "forall<{}> {{ {} }}", util::join(vars, ", "), goal),
+            Goal::Not(goal) => write!(f, "not {{ {} }}", goal),
+            Goal::True => write!(f, "true"),
         }
     }
 }
@@ -61,12 +296,83 @@ impl Display for Goal {
 #[derive(Debug, Eq, Clone, PartialEq)]
 pub enum DomainGoal {
     Holds(Constraint),
+    WellFormed(WellFormed),
+    FromEnv(FromEnv),
+    Normalize(Normalize),
+    /// holds where opaque types may be normalized to their hidden types
+    Reveal,
 }
 
 impl Display for DomainGoal {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            DomainGoal::Holds(constraint) => todo!(),
+            DomainGoal::Holds(constraint) => write!(f, "{}", constraint),
+            DomainGoal::WellFormed(wf) => write!(f, "{}", wf),
+            DomainGoal::FromEnv(from_env) => write!(f, "{}", from_env),
+            DomainGoal::Normalize(normalize) => write!(f, "{}", normalize),
+            DomainGoal::Reveal => write!(f, "Reveal"),
+        }
+    }
+}
+
+/// `WellFormed(Ty)` or `WellFormed(Ty: Trait)`
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub enum WellFormed {
+    Ty(Ty),
+    Trait(ImplConstraint),
+}
+
+impl Display for WellFormed {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WellFormed::Ty(ty) => write!(f, "WellFormed({})", ty),
+            WellFormed::Trait(impl_constraint) => write!(f, "WellFormed({})", impl_constraint),
+        }
+    }
+}
+
+/// `Normalize(<Ty as Trait>::Name -> Ty)` or `Normalize(Opaque<...> -> Ty)`
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub struct Normalize {
+    pub alias: AliasTy,
+    pub ty: Ty,
+}
+
+impl Display for Normalize {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Normalize({} -> {})", self.alias, self.ty)
+    }
+}
+
+/// the left hand side of a `Normalize`
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub enum AliasTy {
+    Projection(ProjectionTy),
+    /// an opaque type, named like a struct
+    Opaque(Ident, Vec<GenericArg>),
+}
+
+impl Display for AliasTy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AliasTy::Projection(projection) => write!(f, "{}", projection),
+            AliasTy::Opaque(name, args) => write!(f, "{}{}", name, fmt_generic_args(args)),
+        }
+    }
+}
+
+/// `FromEnv(Ty)` or `FromEnv(Ty: Trait)`
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub enum FromEnv {
+    Ty(Ty),
+    Trait(ImplConstraint),
+}
+
+impl Display for FromEnv {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FromEnv::Ty(ty) => write!(f, "FromEnv({})", ty),
+            FromEnv::Trait(impl_constraint) => write!(f, "FromEnv({})", impl_constraint),
         }
     }
 }
@@ -74,19 +380,119 @@ impl Display for DomainGoal {
 #[derive(Debug, Eq, Clone, PartialEq)]
 pub enum Constraint {
     Implemented(ImplConstraint),
+    Predicate(Predicate),
+    Eq(EqConstraint),
+    LifetimeOutlives(LifetimeOutlives),
+    TyOutlives(TyOutlives),
 }
 
+impl Constraint {
+    /// whether any type within the constraint is or contains a type named `name`
+    pub fn mentions(&self, name: &str) -> bool {
+        match self {
+            Constraint::Implemented(ImplConstraint { ty, trait_ref }) =>
+                ty.mentions(name) || trait_ref.mentions(name),
+            Constraint::Predicate(predicate) => predicate.args.iter().any(|arg| arg.mentions(name)),
+            Constraint::Eq(EqConstraint { lhs, rhs }) => lhs.mentions(name) || rhs.mentions(name),
+            Constraint::LifetimeOutlives(..) => false,
+            Constraint::TyOutlives(TyOutlives { ty, .. }) => ty.mentions(name),
+        }
+    }
+}
+
+impl Display for Constraint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Constraint::Implemented(impl_constraint) => write!(f, "{}", impl_constraint),
+            Constraint::Predicate(predicate) => write!(f, "{}", predicate),
+            Constraint::Eq(eq) => write!(f, "{}", eq),
+            Constraint::LifetimeOutlives(outlives) => write!(f, "{}", outlives),
+            Constraint::TyOutlives(outlives) => write!(f, "{}", outlives),
+        }
+    }
+}
+
+/// `'a: 'b`, `'a` outlives `'b`
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub struct LifetimeOutlives {
+    pub a: Lifetime,
+    pub b: Lifetime,
+}
+
+impl Display for LifetimeOutlives {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.a, self.b)
+    }
+}
+
+/// `Ty: 'a`, the type outlives `'a`
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub struct TyOutlives {
+    pub ty: Ty,
+    pub lifetime: Lifetime,
+}
+
+impl Display for TyOutlives {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.ty, self.lifetime)
+    }
+}
+
+/// `lhs = rhs`
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub struct EqConstraint {
+    pub lhs: Ty,
+    pub rhs: Ty,
+}
+
+impl Display for EqConstraint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}", self.lhs, self.rhs)
+    }
+}
+
+/// prolog style `name(arg, ...)`
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub struct Predicate {
+    pub name: Ident,
+    pub args: Vec<GenericArg>,
+}
+
+impl Display for Predicate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({})", self.name, util::join(&self.args, ", "))
+    }
+}
+
+/// a type, lifetime or const parameter, the name of a lifetime parameter includes its leading `'`
 #[derive(Debug, Eq, Clone, PartialEq)]
 pub struct Var {
     pub ident: Ident,
+    pub kind: VarKind,
 }
 
 impl Var {
     pub fn new(ident: Ident) -> Self {
-        Self { ident }
+        Self { ident, kind: VarKind::Ty }
+    }
+
+    pub fn lifetime(ident: Ident) -> Self {
+        Self { ident, kind: VarKind::Lifetime }
+    }
+
+    pub fn constant(ident: Ident, ty: Option<Ty>) -> Self {
+        Self { ident, kind: VarKind::Const(ty) }
     }
 }
 
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub enum VarKind {
+    Ty,
+    Lifetime,
+    /// a const parameter with its declared type if any, `const N: usize`
+    Const(Option<Ty>),
+}
+
 // does `ty` implement `trait`?
 #[derive(Debug, Eq, Clone, PartialEq)]
 pub struct ImplConstraint {
@@ -94,10 +500,28 @@ pub struct ImplConstraint {
     pub trait_ref: TraitRef,
 }
 
+impl Display for ImplConstraint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.ty, self.trait_ref)
+    }
+}
+
 #[derive(Debug, Eq, Clone, PartialEq)]
 pub struct TraitRef {
     pub trait_name: Ident,
-    pub args: Vec<Ty>,
+    pub args: Vec<GenericArg>,
+}
+
+impl TraitRef {
+    pub fn mentions(&self, name: &str) -> bool {
+        self.args.iter().any(|arg| arg.mentions(name))
+    }
+}
+
+impl Display for TraitRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.trait_name, fmt_generic_args(&self.args))
+    }
 }
 
 // "things we know"
@@ -132,19 +556,27 @@ pub struct Implication {
 
 impl Display for Implication {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "for<{}> {{ {} :- {} }}",
-            util::join(&self.vars, ","),
-            self.consequent,
-            self.condition
-        )
+        if self.vars.is_empty() {
+            write!(f, "{} :- {}", self.consequent, self.condition)
+        } else {
+            write!(
+                f,
+                "for<{}> {{ {} :- {} }}",
+                util::join(&self.vars, ", "),
+                self.consequent,
+                self.condition
+            )
+        }
     }
 }
 
 impl Display for Var {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.ident)
+        match &self.kind {
+            VarKind::Ty | VarKind::Lifetime => write!(f, "{}", self.ident),
+            VarKind::Const(Some(ty)) => write!(f, "const {}: {}", self.ident, ty),
+            VarKind::Const(None) => write!(f, "const {}", self.ident),
+        }
     }
 }
 
@@ -200,13 +632,201 @@ impl Ident {
 /// a.k.a DomainGoal
 #[derive(Debug, Eq, Clone, PartialEq)]
 pub enum Ty {
-    Structure(Ident, Vec<Ty>),
+    Structure(Ident, Vec<GenericArg>),
+    Projection(ProjectionTy),
+    /// `(A, B, ...)`, a tuple of one element is written `(A,)`
+    Tuple(Vec<Ty>),
+    /// `&'a T` or `&'a mut T`, there is no elision so the lifetime is always named
+    Ref(Mutability, Lifetime, Box<Ty>),
+    /// `[T]`
+    Slice(Box<Ty>),
+    /// `[T; N]`
+    Array(Box<Ty>, Box<Const>),
+    /// `fn(A, B) -> R`, the return type is `()` if it is omitted
+    Fn(Vec<Ty>, Box<Ty>),
+    /// `!`
+    Never,
+    /// `dyn Trait + ...`, the bounds are kept in the order they are written
+    Dyn(Vec<TraitRef>),
+}
+
+impl Ty {
+    pub fn unit() -> Self {
+        Ty::Tuple(vec![])
+    }
+
+    /// whether the type is or contains a type named `name`
+    pub fn mentions(&self, name: &str) -> bool {
+        match self {
+            Ty::Structure(functor, args) =>
+                &*functor.symbol == name || args.iter().any(|arg| arg.mentions(name)),
+            Ty::Projection(projection) =>
+                projection.ty.mentions(name) || projection.trait_ref.mentions(name),
+            Ty::Tuple(tys) => tys.iter().any(|ty| ty.mentions(name)),
+            Ty::Ref(_, _, ty) | Ty::Slice(ty) | Ty::Array(ty, _) => ty.mentions(name),
+            Ty::Fn(params, ret) => params.iter().any(|ty| ty.mentions(name)) || ret.mentions(name),
+            Ty::Never => false,
+            Ty::Dyn(bounds) => bounds.iter().any(|bound| bound.mentions(name)),
+        }
+    }
 }
 
 impl Display for Ty {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            Ty::Structure(functor, terms) => write!(f, "{}<{}>", functor, util::join(terms, ",")),
+            Ty::Structure(functor, tys) => write!(f, "{}{}", functor, fmt_generic_args(tys)),
+            Ty::Projection(projection) => write!(f, "{}", projection),
+            Ty::Tuple(tys) if tys.len() == 1 => write!(f, "({},)", tys[0]),
+            Ty::Tuple(tys) => write!(f, "({})", util::join(tys, ", ")),
+            Ty::Ref(mutability, lifetime, ty) => write!(f, "&{} {}{}", lifetime, mutability, ty),
+            Ty::Slice(ty) => write!(f, "[{}]", ty),
+            Ty::Array(ty, len) => write!(f, "[{}; {}]", ty, len),
+            Ty::Fn(params, ret) if **ret == Ty::unit() =>
+                write!(f, "fn({})", util::join(params, ", ")),
+            Ty::Fn(params, ret) => write!(f, "fn({}) -> {}", util::join(params, ", "), ret),
+            Ty::Never => write!(f, "!"),
+            Ty::Dyn(bounds) => write!(f, "dyn {}", util::join(bounds, " + ")),
+        }
+    }
+}
+
+#[derive(Debug, Eq, Clone, Copy, PartialEq, Hash)]
+pub enum Mutability {
+    Not,
+    Mut,
+}
+
+/// the keyword (with a trailing space) that precedes the type of a reference
+impl Display for Mutability {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Mutability::Not => Ok(()),
+            Mutability::Mut => write!(f, "mut "),
+        }
+    }
+}
+
+/// the length of an array, a const parameter or a literal
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub enum Const {
+    Param(Ident),
+    Value(ConstValue),
+}
+
+impl Display for Const {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Const::Param(name) => write!(f, "{}", name),
+            Const::Value(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// `<Ty as Trait>::Name`
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub struct ProjectionTy {
+    pub ty: Box<Ty>,
+    pub trait_ref: TraitRef,
+    pub name: Ident,
+}
+
+impl Display for ProjectionTy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "<{} as {}>::{}", self.ty, self.trait_ref, self.name)
+    }
+}
+
+/// an argument of a type, trait or predicate,
+/// a const parameter is parsed as a type and only resolved when lowering
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub enum GenericArg {
+    Ty(Ty),
+    Lifetime(Lifetime),
+    Const(ConstValue),
+}
+
+impl GenericArg {
+    pub fn mentions(&self, name: &str) -> bool {
+        match self {
+            GenericArg::Ty(ty) => ty.mentions(name),
+            GenericArg::Lifetime(..) | GenericArg::Const(..) => false,
+        }
+    }
+}
+
+impl Display for GenericArg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GenericArg::Ty(ty) => write!(f, "{}", ty),
+            GenericArg::Lifetime(lifetime) => write!(f, "{}", lifetime),
+            GenericArg::Const(value) => write!(f, "{}", value),
         }
     }
 }
+
+/// a literal const argument, a const parameter without a type can be either kind of value
+#[derive(Debug, Eq, Clone, Copy, PartialEq, Hash)]
+pub enum ConstValue {
+    Int(u128),
+    Bool(bool),
+}
+
+impl Display for ConstValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstValue::Int(i) => write!(f, "{}", i),
+            ConstValue::Bool(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+/// `'a` or `'static`, the name of a named lifetime includes its leading `'`
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub enum Lifetime {
+    Named(Ident),
+    Static,
+}
+
+impl Display for Lifetime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Lifetime::Named(name) => write!(f, "{}", name),
+            Lifetime::Static => write!(f, "'static"),
+        }
+    }
+}
+
+pub(crate) fn fmt_generic_args<T: Display>(args: &[T]) -> String {
+    if args.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", util::join(args, ", "))
+    }
+}
+
+pub(crate) fn fmt_where_clauses(where_clauses: &[Constraint]) -> String {
+    if where_clauses.is_empty() {
+        String::new()
+    } else {
+        format!(" where {}", util::join(where_clauses, ", "))
+    }
+}
+
+/// the attributes of a declaration, each followed by a space
+pub(crate) fn fmt_attrs(attrs: &[Attr]) -> String {
+    attrs.iter().map(|attr| format!("{} ", attr)).collect()
+}
+
+pub(crate) fn fmt_default(default: bool) -> &'static str {
+    if default { "default " } else { "" }
+}
+
+/// the body of a declaration on a single line
+fn fmt_body(items: impl Iterator<Item = String>) -> String {
+    let items = items.collect::<Vec<_>>();
+    if items.is_empty() {
+        "{}".to_owned()
+    } else {
+        format!("{{ {} }}", items.join(" "))
+    }
+}
@@ -0,0 +1,127 @@
+//! comments are handled outside the grammar, the lalrpop generated lexer is regex based and so
+//! can't recognise nested block comments
+use crate::ast::{Comment, CommentKind, Program, Span};
+use crate::ParseResult;
+
+/// Returns `src` with every comment replaced by whitespace, and the comments that were removed.
+/// Newlines and the byte offsets of everything else are unchanged so spans remain valid.
+pub fn strip_comments(src: &str) -> ParseResult<(String, Vec<Comment>)> {
+    let bytes = src.as_bytes();
+    let mut stripped = bytes.to_vec();
+    let mut comments = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let (kind, end) = match &bytes[i..] {
+            [b'/', b'/', ..] => (CommentKind::Line, line_comment_end(bytes, i)),
+            [b'/', b'*', ..] => (CommentKind::Block, block_comment_end(bytes, i)?),
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+
+        for b in &mut stripped[i..end] {
+            if *b != b'\n' {
+                *b = b' ';
+            }
+        }
+        comments.push(Comment { span: Span::new(i, end), kind, text: src[i..end].to_owned() });
+        i = end;
+    }
+
+    // comments start and end on ascii characters, so only entire characters were overwritten
+    Ok((String::from_utf8(stripped).unwrap(), comments))
+}
+
+/// the end of the line comment starting at `start`, excluding the line terminator
+fn line_comment_end(bytes: &[u8], start: usize) -> usize {
+    match bytes[start..].iter().position(|&b| b == b'\n') {
+        Some(n) if n > 0 && bytes[start + n - 1] == b'\r' => start + n - 1,
+        Some(n) => start + n,
+        None => bytes.len(),
+    }
+}
+
+fn block_comment_end(bytes: &[u8], start: usize) -> ParseResult<usize> {
+    let mut depth = 0;
+    let mut i = start;
+    while i < bytes.len() {
+        match &bytes[i..] {
+            [b'/', b'*', ..] => {
+                depth += 1;
+                i += 2;
+            }
+            [b'*', b'/', ..] => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return Ok(i);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    Err(format!("unterminated block comment at {}", start))
+}
+
+/// Attaches each comment to the first item that ends after it, so comments inside an item belong
+/// to that item. The exception is a line comment on the same line as the end of an item, which
+/// is kept as that item's trailing comment.
+pub fn attach_comments(program: &mut Program, src: &str, comments: Vec<Comment>) {
+    let mut items = program.items.iter_mut().peekable();
+    let mut prev_item = None;
+    for comment in comments {
+        while let Some(item) = items.next_if(|item| item.span.hi <= comment.span.lo) {
+            prev_item = Some(item);
+        }
+
+        if let Some(prev) = &mut prev_item {
+            let between = &src[prev.span.hi..comment.span.lo];
+            if comment.kind == CommentKind::Line
+                && prev.trailing_comment.is_none()
+                && !between.contains('\n')
+            {
+                prev.trailing_comment = Some(comment);
+                continue;
+            }
+        }
+
+        match items.peek_mut() {
+            Some(item) => item.comments.push(comment),
+            None => program.trailing_comments.push(comment),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_comments_preserves_offsets() -> ParseResult<()> {
+        let src = "a // line\nb /* block */ c // end";
+        let (stripped, comments) = strip_comments(src)?;
+        assert_eq!(stripped, "a        \nb             c       ");
+        let texts = comments.iter().map(|comment| comment.text.as_str()).collect::<Vec<_>>();
+        assert_eq!(texts, ["// line", "/* block */", "// end"]);
+        assert_eq!(comments[1].span, Span::new(12, 23));
+        assert_eq!(comments[1].content(), " block ");
+        Ok(())
+    }
+
+    #[test]
+    fn test_strip_nested_block_comments() -> ParseResult<()> {
+        let src = "/* outer /* inner */ still outer */ a /* ünïcödé\n */";
+        let (stripped, comments) = strip_comments(src)?;
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[0].text, "/* outer /* inner */ still outer */");
+        assert_eq!(stripped.len(), src.len());
+        assert_eq!(stripped.trim(), "a");
+        Ok(())
+    }
+
+    #[test]
+    fn test_unterminated_block_comment() {
+        assert!(strip_comments("a /* /* */").is_err());
+    }
+}
@@ -0,0 +1,301 @@
+//! canonical source formatting of `.logic` programs
+use crate::ast::*;
+
+/// clauses longer than this are broken across multiple lines
+pub const MAX_WIDTH: usize = 100;
+
+const INDENT: &str = "    ";
+
+pub fn format_program(program: &Program) -> String {
+    let mut out = String::new();
+    for item in &program.items {
+        format_comments(&mut out, &item.comments);
+        match &item.kind {
+            ItemKind::Clause(clause) => format_clause(&mut out, clause),
+            ItemKind::Struct(..) | ItemKind::Trait(..) | ItemKind::Impl(..) =>
+                format_decl(&mut out, item),
+            ItemKind::OpaqueTy(..) => out.push_str(&item.to_string()),
+        }
+        if let Some(comment) = &item.trailing_comment {
+            out.push(' ');
+            out.push_str(&comment.text);
+        }
+        out.push('\n');
+    }
+    format_comments(&mut out, &program.trailing_comments);
+    out
+}
+
+/// puts each comment on its own line(s), block comments are kept verbatim
+fn format_comments(out: &mut String, comments: &[Comment]) {
+    for comment in comments {
+        out.push_str(&comment.text);
+        out.push('\n');
+    }
+}
+
+/// writes a struct, trait or impl (without a trailing newline), a `where` that doesn't fit is
+/// broken into one where clause per line and the items of a non-empty body go on their own lines
+fn format_decl(out: &mut String, item: &Item) {
+    let (header, where_clauses, body) = match &item.kind {
+        ItemKind::Struct(StructDef { attrs, name, vars, where_clauses }) => {
+            let attrs = fmt_attrs(attrs);
+            (format!("{}struct {}{}", attrs, name, fmt_generic_args(vars)), where_clauses, vec![])
+        }
+        ItemKind::Trait(TraitDef { attrs, name, vars, where_clauses, assoc_tys }) => {
+            let attrs = fmt_attrs(attrs);
+            let header = format!("{}trait {}{}", attrs, name, fmt_generic_args(vars));
+            let body = assoc_tys.iter().map(|name| format!("type {};", name)).collect();
+            (header, where_clauses, body)
+        }
+        ItemKind::Impl(ImplDef {
+            attrs,
+            default,
+            vars,
+            trait_ref,
+            self_ty,
+            where_clauses,
+            assoc_ty_values,
+        }) => {
+            let attrs = format!("{}{}", fmt_attrs(attrs), fmt_default(*default));
+            let header =
+                format!("{}impl{} {} for {}", attrs, fmt_generic_args(vars), trait_ref, self_ty);
+            let body = assoc_ty_values.iter().map(AssocTyValue::to_string).collect();
+            (header, where_clauses, body)
+        }
+        ItemKind::Clause(..) | ItemKind::OpaqueTy(..) => unreachable!(),
+    };
+
+    let line = item.to_string();
+    if line.len() <= MAX_WIDTH && body.is_empty() {
+        out.push_str(&line);
+        return;
+    }
+
+    let header_line = format!("{}{} {{", header, fmt_where_clauses(where_clauses));
+    // an empty body is closed on the same line
+    let fits = header_line.len() + body.is_empty() as usize <= MAX_WIDTH;
+    if where_clauses.is_empty() || fits {
+        out.push_str(&header_line);
+    } else {
+        out.push_str(&header);
+        out.push_str("\nwhere");
+        for where_clause in where_clauses {
+            out.push_str(&format!("\n{}{},", INDENT, where_clause));
+        }
+        out.push_str("\n{");
+    }
+    for item in &body {
+        out.push_str(&format!("\n{}{}", INDENT, item));
+    }
+    if !body.is_empty() {
+        out.push('\n');
+    }
+    out.push('}');
+}
+
+/// writes the clause (without a trailing newline)
+fn format_clause(out: &mut String, clause: &Clause) {
+    let line = format!("{}.", clause);
+    if line.len() <= MAX_WIDTH {
+        out.push_str(&line);
+        return;
+    }
+
+    match clause {
+        Clause::Implies(Implication { vars, consequent, condition }) if !vars.is_empty() => {
+            out.push_str(&format!("for<{}> {{\n", util::join(vars, ", ")));
+            format_implication(out, INDENT, consequent, condition);
+            out.push_str("\n}.");
+        }
+        Clause::Implies(Implication { consequent, condition, .. }) => {
+            format_implication(out, "", consequent, condition);
+            out.push('.');
+        }
+        // nothing sensible to break on
+        Clause::DomainGoal(..) | Clause::And(..) => out.push_str(&line),
+    }
+}
+
+/// writes `consequent :- condition` at the given indentation (without a trailing newline),
+/// putting each operand of the condition on its own line if it doesn't fit on one
+fn format_implication(out: &mut String, indent: &str, consequent: &DomainGoal, condition: &Goal) {
+    let line = format!("{}{} :- {}", indent, consequent, condition);
+    if line.len() <= MAX_WIDTH {
+        out.push_str(&line);
+        return;
+    }
+
+    out.push_str(&format!("{}{} :-", indent, consequent));
+    let (operands, sep) = split_goal(condition);
+    for (i, operand) in operands.iter().enumerate() {
+        out.push_str(&format!("\n{}{}{}", indent, INDENT, operand));
+        if i + 1 < operands.len() {
+            out.push_str(sep);
+        }
+    }
+}
+
+/// splits the outermost chain of `,` or `;` into its (already parenthesized) operands
+fn split_goal(goal: &Goal) -> (Vec<String>, &'static str) {
+    let mut operands = vec![];
+    let sep = match goal {
+        Goal::And(..) => ",",
+        Goal::Or(..) => ";",
+        _ => return (vec![goal.to_string()], ""),
+    };
+
+    let mut goal = goal;
+    loop {
+        match goal {
+            Goal::And(lhs, rhs) if sep == "," => {
+                operands.push(lhs.to_string_prec(GoalPrec::Atom));
+                goal = rhs;
+            }
+            Goal::Or(lhs, rhs) if sep == ";" => {
+                operands.push(lhs.to_string_prec(GoalPrec::And));
+                goal = rhs;
+            }
+            // the tail of a `,` chain may still be a disjunction that needs parenthesizing
+            _ => {
+                operands.push(goal.to_string_prec(GoalPrec::And));
+                break;
+            }
+        }
+    }
+    (operands, sep)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{format, ParseResult};
+
+    #[test]
+    fn test_format_normalizes_spacing() -> ParseResult<()> {
+        let src = "Vec<T,U>:Clone:-T:Clone.
+            for<T>{Vec<T>:Copy:-T:Copy,T:Clone;exists<U>{U:Eq<T>}}.";
+        let expected = "\
+Vec<T, U>: Clone :- T: Clone.
+for<T> { Vec<T>: Copy :- T: Copy, T: Clone; exists<U> { U: Eq<T> } }.
+";
+        assert_eq!(format(src)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_breaks_long_clauses() -> ParseResult<()> {
+        let src = "
+            for<T, U> {
+                HashMap<T, U>: Clone :- T: Clone, U: Clone, T: PartialEq<U>,
+                    T: Hash, U: Hash, T: Eq, U: Eq
+            }.
+            for<T, U> {
+                HashMap<T, U>: LongTraitName<T, U> :- T: LongTraitName<U>, U: LongTraitName<T>,
+                    T: LongTraitName<T>, U: LongTraitName<U>
+            }.
+        ";
+        let expected = "\
+for<T, U> {
+    HashMap<T, U>: Clone :- T: Clone, U: Clone, T: PartialEq<U>, T: Hash, U: Hash, T: Eq, U: Eq
+}.
+for<T, U> {
+    HashMap<T, U>: LongTraitName<T, U> :-
+        T: LongTraitName<U>,
+        U: LongTraitName<T>,
+        T: LongTraitName<T>,
+        U: LongTraitName<U>
+}.
+";
+        assert_eq!(format(src)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_keeps_comments() -> ParseResult<()> {
+        let src = "
+            // leading
+            i32:Copy.   // trailing
+            /* block
+               comment /* nested */ */ u32:Copy.
+            for<T>{Vec<T>:Copy:- /* moved */ T:Copy}.
+            // at the end
+        ";
+        let expected = "\
+// leading
+i32: Copy. // trailing
+/* block
+               comment /* nested */ */
+u32: Copy.
+/* moved */
+for<T> { Vec<T>: Copy :- T: Copy }.
+// at the end
+";
+        assert_eq!(format(src)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_decls() -> ParseResult<()> {
+        let src = "
+            struct Vec<T>{}
+            #[fundamental]  #[crate(std)]struct Box<T> {}
+            trait Clone {}
+            #[marker]trait Eq {}
+            impl<T>Clone for Vec<T> where T:Clone{}
+            default   impl<T> Clone for T {}
+            impl<T, U> LongTraitName<T, U> for HashMap<T, U> where T: LongTraitName<U>,
+                U: LongTraitName<T>, T: Hash, U: Hash {}
+            trait Iterator{type Item;}
+            impl<T> Iterator for IntoIter<T> where T: Clone {type Item=T;}
+            impl<T, U> LongTraitName<T, U> for HashMap<T, U> where T: LongTraitName<U>,
+                U: LongTraitName<T>, T: Hash, U: Hash { type Item = <T as Iterator>::Item; }
+        ";
+        let expected = "\
+struct Vec<T> {}
+#[fundamental] #[crate(std)] struct Box<T> {}
+trait Clone {}
+#[marker] trait Eq {}
+impl<T> Clone for Vec<T> where T: Clone {}
+default impl<T> Clone for T {}
+impl<T, U> LongTraitName<T, U> for HashMap<T, U>
+where
+    T: LongTraitName<U>,
+    U: LongTraitName<T>,
+    T: Hash,
+    U: Hash,
+{}
+trait Iterator {
+    type Item;
+}
+impl<T> Iterator for IntoIter<T> where T: Clone {
+    type Item = T;
+}
+impl<T, U> LongTraitName<T, U> for HashMap<T, U>
+where
+    T: LongTraitName<U>,
+    U: LongTraitName<T>,
+    T: Hash,
+    U: Hash,
+{
+    type Item = <T as Iterator>::Item;
+}
+";
+        assert_eq!(format(src)?, expected);
+        assert_eq!(format(expected)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_is_idempotent() -> ParseResult<()> {
+        let src = "
+            for<T, U> {
+                HashMap<T, U>: LongTraitName<T, U> :- (T: LongTraitName<U>; U: LongTraitName<T>),
+                    T: LongTraitName<T>; U: LongTraitName<U>
+            }.
+            i32: Copy.
+        ";
+        let formatted = format(src)?;
+        assert_eq!(format(&formatted)?, formatted);
+        Ok(())
+    }
+}
@@ -1,72 +1,141 @@
 use clap::Clap;
-// use logic_engine::Environment;
-// use logic_ir::{LogicInterner, LogicResult, Program};
-// use logic_parse::{ast, ParseResult};
-use rustyline::error::ReadlineError;
-use rustyline::Editor;
-use std::sync::Arc;
+use logic_driver::{Database, LoweringDatabase, Orphan, Overlap};
+use logic_ir::{print, LogicInterner};
+use logic_parse::ast::Span;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clap)]
 struct Opts {
-    path: Option<String>,
+    #[clap(subcommand)]
+    cmd: Option<Cmd>,
+}
+
+#[derive(Debug, Clap)]
+enum Cmd {
+    /// Format `.logic` files in place
+    Fmt(FmtOpts),
+    /// Check that every impl in a `.logic` file is well formed
+    Check(CheckOpts),
+    /// Report the impls in a `.logic` file that overlap
+    Coherence(CheckOpts),
+    /// Report the impls in a `.logic` file that violate the orphan rule
+    Orphans(CheckOpts),
+}
+
+#[derive(Debug, Clap)]
+struct CheckOpts {
+    path: PathBuf,
+}
+
+#[derive(Debug, Clap)]
+struct FmtOpts {
+    /// Report the files that are not formatted instead of rewriting them
+    #[clap(long)]
+    check: bool,
+    files: Vec<PathBuf>,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let opts = Opts::parse();
 
-    // let src =
-    //     if let Some(path) = &opts.path { std::fs::read_to_string(path)? } else { String::new() };
-    // let db = Database::new(src);
-    // repl(db)?;
+    match opts.cmd {
+        Some(Cmd::Fmt(fmt_opts)) => return fmt(fmt_opts),
+        Some(Cmd::Check(check_opts)) => return check(check_opts),
+        Some(Cmd::Coherence(check_opts)) => return coherence(check_opts),
+        Some(Cmd::Orphans(check_opts)) => return orphans(check_opts),
+        None => {}
+    }
+    Ok(())
+}
+
+fn fmt(opts: FmtOpts) -> Result<(), Box<dyn std::error::Error>> {
+    let mut unformatted = false;
+    for path in &opts.files {
+        let src = std::fs::read_to_string(path)?;
+        let formatted = logic_parse::format(&src)
+            .map_err(|err| format!("failed to parse `{}`: {}", path.display(), err))?;
+        if formatted == src {
+            continue;
+        }
+
+        if opts.check {
+            println!("{} is not formatted", path.display());
+            unformatted = true;
+        } else {
+            std::fs::write(path, formatted)?;
+        }
+    }
+
+    if unformatted {
+        std::process::exit(1);
+    }
     Ok(())
 }
 
-// fn repl(db: Database) -> Result<(), Box<dyn std::error::Error>> {
-//     let mut rl = Editor::<()>::new();
-//     let _ = rl.load_history("history.txt");
-
-//     loop {
-//         let readline = rl.readline("?- ");
-//         match readline {
-//             Ok(line) => {
-//                 if line.is_empty() {
-//                     continue;
-//                 }
-//                 rl.add_history_entry(line.as_str());
-//                 let goal = match logic_parse::parse_goal(&line) {
-//                     Ok(goal) => goal,
-//                     Err(err) => {
-//                         eprintln!("{}", err);
-//                         continue;
-//                     }
-//                 };
-//                 dbg!(&goal);
-//                 // solve(&db, goal)?;
-//             }
-//             Err(ReadlineError::Interrupted) => {
-//                 break;
-//             }
-//             Err(ReadlineError::Eof) => {
-//                 break;
-//             }
-//             Err(err) => {
-//                 println!("Error: {:?}", err);
-//                 break;
-//             }
-//         }
-//     }
-//     rl.save_history("history.txt")?;
-//     Ok(())
-// }
-
-// fn solve(db: &Database, goal: ast::Goal) -> LogicResult<()> {
-//     todo!();
-//     // tls::set_debug_ctxt(Box::new(IRInterner));
-//     // let goal = logic_ir::lower_goal(&goal);
-//     // let env = db.env()?;
-//     // dbg!(&env);
-//     // dbg!(&goal);
-//     // let solver = logic_engine::RecursiveSolver::new(IRInterner, env);
-//     // dbg!(solver.solve(&goal));
-//     Ok(())
-// }
+/// the database of the program at `path`
+fn load(path: &Path) -> Result<(String, Database), Box<dyn std::error::Error>> {
+    let src = std::fs::read_to_string(path)?;
+    // the driver panics on programs that can't be parsed or lowered, so report those errors here
+    let ast = logic_parse::parse_program(&src)
+        .map_err(|err| format!("failed to parse `{}`: {}", path.display(), err))?;
+    logic_ir::lower_ast(&ast)
+        .map_err(|err| format!("failed to lower `{}`: {}", path.display(), err))?;
+    let db = Database::new(&src);
+    Ok((src, db))
+}
+
+fn check(opts: CheckOpts) -> Result<(), Box<dyn std::error::Error>> {
+    let (_, db) = load(&opts.path)?;
+    let ill_formed = db.ill_formed_impls().map_err(|err| format!("{:?}", err))?;
+    for impl_datum in &ill_formed {
+        println!("impl is not well formed: {}", print(LogicInterner, impl_datum));
+    }
+
+    if !ill_formed.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn coherence(opts: CheckOpts) -> Result<(), Box<dyn std::error::Error>> {
+    let (src, db) = load(&opts.path)?;
+    let overlapping = db.overlapping_impls().map_err(|err| format!("{:?}", err))?;
+    for Overlap { spans, .. } in &overlapping {
+        let [a, b] = spans.clone().map(|span| location(&opts.path, &src, span));
+        println!("impls overlap:\n  {}\n  {}", a, b);
+    }
+
+    if !overlapping.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn orphans(opts: CheckOpts) -> Result<(), Box<dyn std::error::Error>> {
+    let (src, db) = load(&opts.path)?;
+    let orphans = db.orphan_impls();
+    for Orphan { span, .. } in &orphans {
+        println!(
+            "impl is for neither a local trait nor a local type:\n  {}",
+            location(&opts.path, &src, span.clone())
+        );
+    }
+
+    if !orphans.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// `path:line:col: `source``
+fn location(path: &Path, src: &str, span: Span) -> String {
+    let (line, col) = line_col(src, span.lo);
+    format!("{}:{}:{}: `{}`", path.display(), line, col, &src[span.lo..span.hi])
+}
+
+/// the one-based line and column of a byte offset into `src`
+fn line_col(src: &str, offset: usize) -> (usize, usize) {
+    let before = &src[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+}